@@ -0,0 +1,420 @@
+//! Translates a verified `MirModule` into a C99 translation unit.
+//!
+//! Every `MirFunction` becomes a C function named after it with an `sbl_`
+//! prefix, parameters become `aN`, and each `alloca` and instruction
+//! result becomes a local `vN` declared at the top of the function. Blocks
+//! become labels jumped to with `goto`. A phi `vN` also gets a `pN` that
+//! each predecessor assigns before its terminator and the phi reads at the
+//! start of its block, so phis take their values in parallel. Arithmetic
+//! goes through helpers that give `i32` the wrapping semantics of the
+//! interpreter and trap on division by zero. A C `main` calling `sbl_main`
+//! is added when the module has a `main` the interpreter could run.
+//!
+//! When the module names its source file, a `#line` directive precedes
+//! each statement coming from a new source line, so the C compiler's
+//! diagnostics and debug info point at the Sable source.
+
+use std::fmt::Write;
+
+use sable_mir::{
+  analysis::cfg::outgoing_phi_values,
+  mir::{
+    function::{MirFunction, block::MirBlockId},
+    instruction::{CmpPredicate, Instruction, MirInstId},
+    module::MirModule,
+    place::Place,
+    value::{Constant, MirValue},
+  },
+};
+use sable_parser::info::ValType;
+
+const PRELUDE: &str = r#"#include <math.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+static inline void sbl_trap(const char *message) {
+  fprintf(stderr, "error: %s\n", message);
+  exit(1);
+}
+
+static inline int32_t sbl_add_i32(int32_t a, int32_t b) {
+  return (int32_t)((uint32_t)a + (uint32_t)b);
+}
+
+static inline int32_t sbl_sub_i32(int32_t a, int32_t b) {
+  return (int32_t)((uint32_t)a - (uint32_t)b);
+}
+
+static inline int32_t sbl_mul_i32(int32_t a, int32_t b) {
+  return (int32_t)((uint32_t)a * (uint32_t)b);
+}
+
+static inline int32_t sbl_div_i32(int32_t a, int32_t b) {
+  if (b == 0) {
+    sbl_trap("integer division by zero");
+  }
+  if (b == -1) {
+    return sbl_sub_i32(0, a);
+  }
+  return a / b;
+}
+"#;
+
+/// Emits the C translation unit for `module`.
+pub fn emit_module(module: &MirModule) -> String {
+  let mut out = String::new();
+  writeln!(
+    out,
+    "/* generated by sable from module `{}` */",
+    module.name()
+  )
+  .unwrap();
+  out.push_str(PRELUDE);
+
+  out.push('\n');
+  for func in module.get_funcs() {
+    writeln!(out, "{};", signature(func)).unwrap();
+  }
+
+  for func in module.get_funcs() {
+    out.push('\n');
+    emit_function(&mut out, module, func);
+  }
+
+  let main = module.get_funcs().iter().find(|func| func.name() == "main");
+  if let Some(main) = main.filter(|main| main.get_params().is_empty()) {
+    let call = match main.ret_type() {
+      ValType::I32 => "  return sbl_main();\n",
+      ValType::Void => "  sbl_main();\n  return 0;\n",
+      _ => return out,
+    };
+    write!(out, "\nint main(void) {{\n{}}}\n", call).unwrap();
+  }
+  out
+}
+
+fn c_type(type_: &ValType) -> &'static str {
+  match type_ {
+    ValType::I32 => "int32_t",
+    ValType::F32 => "float",
+    ValType::Bool => "bool",
+    ValType::Void => "void",
+    ValType::Untyped => unreachable!("untyped value in verified MIR"),
+  }
+}
+
+fn signature(func: &MirFunction) -> String {
+  let params = func
+    .get_params()
+    .iter()
+    .enumerate()
+    .map(|(idx, type_)| format!("{} a{}", c_type(type_), idx))
+    .collect::<Vec<_>>();
+  let params = if params.is_empty() {
+    "void".to_string()
+  } else {
+    params.join(", ")
+  };
+  format!(
+    "{} sbl_{}({})",
+    c_type(&func.ret_type()),
+    func.name(),
+    params
+  )
+}
+
+/// The type of the local declared for `inst`, if it needs one.
+fn local_type(inst: &Instruction) -> Option<ValType> {
+  let type_ = match inst {
+    Instruction::Alloca(alloca) => Some(alloca.type_()),
+    _ => inst.value_type(),
+  };
+  type_.filter(|type_| *type_ != ValType::Void)
+}
+
+fn value(value: &MirValue) -> String {
+  match value {
+    MirValue::Inst(id) => format!("v{}", id.0),
+    MirValue::Constant(constant) => match constant {
+      Constant::IntValue(_, value) => match *value as i32 {
+        i32::MIN => "INT32_MIN".to_string(),
+        value => value.to_string(),
+      },
+      Constant::FloatValue(_, value) => {
+        let value = *value as f32;
+        if value.is_finite() {
+          format!("{:?}f", value)
+        } else {
+          "INFINITY".to_string()
+        }
+      }
+      Constant::BoolValue(value) => value.to_string(),
+      Constant::Null => "0".to_string(),
+    },
+  }
+}
+
+/// `file` as a C string literal.
+fn quoted(file: &str) -> String {
+  format!("\"{}\"", file.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn label(id: MirBlockId) -> String {
+  format!("bb{}", id.0)
+}
+
+fn emit_function(out: &mut String, module: &MirModule, func: &MirFunction) {
+  writeln!(out, "{} {{", signature(func)).unwrap();
+  let order = func.get_layout();
+  for &blk_id in &order {
+    for (inst_id, inst) in func.get_insts(blk_id) {
+      if let Some(type_) = local_type(inst) {
+        writeln!(out, "  {} v{};", c_type(&type_), inst_id.0).unwrap();
+      }
+      if let Instruction::Phi(phi) = inst {
+        writeln!(out, "  {} p{};", c_type(&phi.type_()), inst_id.0).unwrap();
+      }
+    }
+  }
+
+  if order.first() != Some(&MirBlockId(0)) {
+    writeln!(out, "  goto {};", label(MirBlockId(0))).unwrap();
+  }
+  let mut last_line = None;
+  for &blk_id in &order {
+    writeln!(out, "{}:", label(blk_id)).unwrap();
+    for (inst_id, inst) in func.get_insts(blk_id) {
+      if let Some(file) = module.source_file()
+        && let Some(pos) = func.get_inst_pos(inst_id)
+        && last_line != Some(pos.line)
+      {
+        writeln!(out, "#line {} {}", pos.line, quoted(file)).unwrap();
+        last_line = Some(pos.line);
+      }
+      if inst.is_terminator() {
+        for (phi, incoming) in outgoing_phi_values(func, blk_id) {
+          writeln!(out, "  p{} = {};", phi.0, value(incoming)).unwrap();
+        }
+      }
+      emit_inst(out, module, inst_id, inst);
+    }
+  }
+  out.push_str("}\n");
+}
+
+fn emit_inst(out: &mut String, module: &MirModule, id: MirInstId, inst: &Instruction) {
+  let dest = format!("v{}", id.0);
+  let stmt = match inst {
+    Instruction::Alloca(_) => return,
+    Instruction::Store(store) => format!("v{} = {};", store.target().0, value(store.value())),
+    Instruction::Load(load) => match load.from() {
+      Place::Local(slot) => format!("{} = v{};", dest, slot.0),
+      Place::Param(idx) => format!("{} = a{};", dest, idx),
+    },
+    Instruction::Add(inst) => arith(&dest, "add", "+", &inst.type_(), inst.lhs(), inst.rhs()),
+    Instruction::Sub(inst) => arith(&dest, "sub", "-", &inst.type_(), inst.lhs(), inst.rhs()),
+    Instruction::Mul(inst) => arith(&dest, "mul", "*", &inst.type_(), inst.lhs(), inst.rhs()),
+    Instruction::Div(inst) => arith(&dest, "div", "/", &inst.type_(), inst.lhs(), inst.rhs()),
+    Instruction::Cmp(cmp) => {
+      let op = match cmp.predicate() {
+        CmpPredicate::Eq => "==",
+        CmpPredicate::Ne => "!=",
+        CmpPredicate::Lt => "<",
+        CmpPredicate::Le => "<=",
+        CmpPredicate::Gt => ">",
+        CmpPredicate::Ge => ">=",
+      };
+      format!(
+        "{} = {} {} {};",
+        dest,
+        value(cmp.lhs()),
+        op,
+        value(cmp.rhs())
+      )
+    }
+    Instruction::And(and) => format!("{} = {} && {};", dest, value(and.lhs()), value(and.rhs())),
+    Instruction::Or(or) => format!("{} = {} || {};", dest, value(or.lhs()), value(or.rhs())),
+    Instruction::Not(not) => format!("{} = !{};", dest, value(not.value())),
+    Instruction::Return(ret) => match ret.type_() {
+      ValType::Void => "return;".to_string(),
+      _ => format!("return {};", value(ret.ret_value())),
+    },
+    Instruction::Call(call) => {
+      let callee = module.get_func(call.callee()).unwrap().name();
+      let args = call.args().iter().map(value).collect::<Vec<_>>();
+      let call_expr = format!("sbl_{}({})", callee, args.join(", "));
+      match call.type_() {
+        ValType::Void => format!("{};", call_expr),
+        _ => format!("{} = {};", dest, call_expr),
+      }
+    }
+    Instruction::Branch(branch) => format!(
+      "if ({}) goto {}; else goto {};",
+      value(branch.cond()),
+      label(branch.then_blk()),
+      label(branch.else_blk())
+    ),
+    Instruction::Jump(jump) => format!("goto {};", label(jump.target())),
+    Instruction::Phi(_) => format!("{} = p{};", dest, id.0),
+    Instruction::Unreachable => "sbl_trap(\"reached unreachable code\");".to_string(),
+  };
+  writeln!(out, "  {}", stmt).unwrap();
+}
+
+fn arith(
+  dest: &str,
+  name: &str,
+  op: &str,
+  type_: &ValType,
+  lhs: &MirValue,
+  rhs: &MirValue,
+) -> String {
+  match type_ {
+    ValType::I32 => format!(
+      "{} = sbl_{}_i32({}, {});",
+      dest,
+      name,
+      value(lhs),
+      value(rhs)
+    ),
+    _ => format!("{} = {} {} {};", dest, value(lhs), op, value(rhs)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    env,
+    process::{Command, Stdio},
+  };
+
+  use sable_driver::{
+    cc::{c_compiler, compile_c},
+    session::Session,
+  };
+  use sable_interp::{interpreter::Interpreter, value::Value};
+  use sable_mir::transform::pass::{OptLevel, PassManager};
+
+  use super::*;
+
+  fn emit(source: &str) -> String {
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    emit_module(&module.borrow())
+  }
+
+  #[test]
+  fn test_emit_function() {
+    let c = emit("func f32 half(f32 x, bool b) { if b { return x / 2.0; } return x; }");
+    let expected = "\
+float sbl_half(float a0, bool a1) {
+  bool v0;
+  float v2;
+  float v3;
+  float v5;
+bb0:
+  v0 = a1;
+  if (v0) goto bb1; else goto bb2;
+bb1:
+  v2 = a0;
+  v3 = v2 / 2.0f;
+  return v3;
+bb2:
+  v5 = a0;
+  return v5;
+}
+";
+    assert!(c.contains("float sbl_half(float a0, bool a1);\n"));
+    assert!(c.ends_with(expected), "{}", c);
+  }
+
+  #[test]
+  fn test_emit_line_directives() {
+    let session = Session::new(
+      "lines.sbl",
+      "func i32 f(i32 x) {\n  let i32 y = x;\n  return y;\n}",
+    );
+    let module = session.lower().ok().unwrap();
+    module.borrow_mut().set_source_file(Some("lines.sbl"));
+    let c = emit_module(&module.borrow());
+    assert!(
+      c.contains("bb0:\n#line 2 \"lines.sbl\"\n  v1 = a0;\n  v0 = v1;\n#line 3 \"lines.sbl\"\n")
+    );
+    assert!(!emit("func i32 f() { return 1; }").contains("#line"));
+  }
+
+  /// Compiles `source` natively and checks that the binary exits like the
+  /// interpreter says it should.
+  fn check_against_interpreter(name: &str, source: &str) {
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    let expected = match Interpreter::new(&module.borrow()).run_main() {
+      Ok(Value::I32(status)) => status as u8 as i32,
+      Ok(_) => 0,
+      Err(_) => 1,
+    };
+
+    // Once as lowered and once through the `-O2` pipeline.
+    for optimized in [false, true] {
+      if optimized {
+        PassManager::with_level(OptLevel::O2).run(&mut module.borrow_mut());
+      }
+      let exe = env::temp_dir().join(format!("sable-c-test-{}-{}", name, std::process::id()));
+      compile_c(&emit_module(&module.borrow()), &exe).unwrap();
+      let status = Command::new(&exe).stderr(Stdio::null()).status().unwrap();
+      std::fs::remove_file(&exe).unwrap();
+      assert_eq!(
+        status.code(),
+        Some(expected),
+        "{}, optimized: {}",
+        name,
+        optimized
+      );
+    }
+  }
+
+  #[test]
+  fn test_matches_interpreter() {
+    if Command::new(c_compiler())
+      .arg("--version")
+      .output()
+      .is_err()
+    {
+      return;
+    }
+
+    check_against_interpreter(
+      "loops",
+      "
+      func i32 collatz(i32 start) {
+        let i32 n = start;
+        let i32 steps = 0;
+        while n != 1 {
+          let i32 half = n / 2;
+          if half * 2 == n { n = half; } else { n = 3 * n + 1; }
+          steps = steps + 1;
+        }
+        return steps;
+      }
+      func i32 main() { return collatz(27); }
+      ",
+    );
+    check_against_interpreter(
+      "floats",
+      "
+      func bool close(f32 a, f32 b) { return a - b < 0.01 && b - a < 0.01; }
+      func i32 main() { if close(1.0 / 3.0 * 3.0, 1.0) || false { return 7; } return 3; }
+      ",
+    );
+    check_against_interpreter(
+      "wrapping",
+      "func i32 main() { let i32 big = 2147483647; return (big + 2) / (0 - 1) * 3; }",
+    );
+    check_against_interpreter(
+      "div_zero",
+      "func i32 main() { let i32 z = 0; return 5 / z; }",
+    );
+  }
+}
@@ -0,0 +1,3 @@
+pub mod c;
+pub mod wasm;
+pub mod x86_64;
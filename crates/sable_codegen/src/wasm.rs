@@ -0,0 +1,730 @@
+//! Translates a verified `MirModule` into a WebAssembly text-format module.
+//!
+//! `i32` and `bool` become wasm `i32`, `f32` stays `f32`. Every function is
+//! exported under its own name, parameters become `$aN` and each `alloca`
+//! and instruction result becomes a local `$vN`.
+//!
+//! Control flow becomes nested `block`, `loop` and `if` following the
+//! dominator tree, as in Ramsey's "Beyond Relooper": a block is emitted
+//! inside the one immediately dominating it, a loop header opens a wasm
+//! `loop` `$loopN` that its back edges branch to, and a block reached by
+//! more than one forward edge follows a wasm `block` `$bbN` that those
+//! edges leave. Every other block has a single predecessor, the block
+//! branching to it, and is emitted in place of the branch. Unreachable
+//! blocks are left out.
+//!
+//! This needs a reducible control-flow graph, which lowering always
+//! produces. Hand-written MIR may not be reducible; such a function falls
+//! back to a dispatch loop over its blocks in layout order, where a jump
+//! stores the position of its target in `$bb` and branches back to a
+//! `br_table` at the top of the loop.
+//!
+//! A phi `$vN` gets a second local `$pN` that each predecessor sets before
+//! its terminator, and the phi reads at the start of its block, so phis
+//! take their values in parallel.
+
+use std::{collections::HashSet, fmt::Write};
+
+use sable_mir::{
+  analysis::{
+    cfg::{Cfg, outgoing_phi_values, reverse_postorder},
+    dominators::DominatorTree,
+    loops::LoopInfo,
+  },
+  mir::{
+    function::{MirFunction, block::MirBlockId},
+    instruction::{CmpPredicate, Instruction, MirInstId},
+    module::MirModule,
+    place::Place,
+    value::{Constant, MirValue},
+  },
+};
+use sable_parser::info::ValType;
+
+/// `i32.div_s` traps on `i32::MIN / -1`, which wraps everywhere else.
+const DIV_I32: &str = r#"  (func $sable.div_i32 (param $a i32) (param $b i32) (result i32)
+    local.get $b
+    i32.const -1
+    i32.eq
+    if (result i32)
+      i32.const 0
+      local.get $a
+      i32.sub
+    else
+      local.get $a
+      local.get $b
+      i32.div_s
+    end
+  )
+"#;
+
+/// Emits the WAT module for `module`.
+pub fn emit_module(module: &MirModule) -> String {
+  let mut out = String::new();
+  writeln!(out, ";; generated by sable from module `{}`", module.name()).unwrap();
+  out.push_str("(module\n");
+  for func in module.get_funcs() {
+    FunctionEmitter::new(module, func).emit(&mut out);
+  }
+  if uses_div_i32(module) {
+    out.push_str(DIV_I32);
+  }
+  out.push_str(")\n");
+  out
+}
+
+fn uses_div_i32(module: &MirModule) -> bool {
+  module.get_funcs().iter().any(|func| {
+    (0..func.get_blocks().len()).any(|blk| {
+      func
+        .get_insts(MirBlockId(blk))
+        .any(|(_, inst)| matches!(inst, Instruction::Div(div) if div.type_() == ValType::I32))
+    })
+  })
+}
+
+fn wasm_type(type_: &ValType) -> Option<&'static str> {
+  match type_ {
+    ValType::I32 | ValType::Bool => Some("i32"),
+    ValType::F32 => Some("f32"),
+    ValType::Void => None,
+    ValType::Untyped => unreachable!("untyped value in verified MIR"),
+  }
+}
+
+/// The type of the local declared for `inst`, if it needs one.
+fn local_type(inst: &Instruction) -> Option<ValType> {
+  let type_ = match inst {
+    Instruction::Alloca(alloca) => Some(alloca.type_()),
+    _ => inst.value_type(),
+  };
+  type_.filter(|type_| *type_ != ValType::Void)
+}
+
+/// The control flow of a reducible function, as the structured
+/// translation walks it.
+struct Shape {
+  cfg: Cfg,
+  tree: DominatorTree,
+  /// The position of each reachable block in reverse postorder.
+  rpo_index: Vec<Option<usize>>,
+  loop_headers: HashSet<MirBlockId>,
+}
+
+impl Shape {
+  /// The shape of `func`, `None` if its control-flow graph is irreducible.
+  fn new(func: &MirFunction) -> Option<Self> {
+    let cfg = Cfg::new(func);
+    let tree = DominatorTree::new(func);
+    let mut rpo_index = vec![None; func.get_blocks().len()];
+    for (idx, blk) in reverse_postorder(func).into_iter().enumerate() {
+      rpo_index[blk.0] = Some(idx);
+    }
+    let loop_headers = LoopInfo::new(func, &cfg, &tree)
+      .loops()
+      .iter()
+      .map(|lp| lp.header())
+      .collect();
+    let shape = Self {
+      cfg,
+      tree,
+      rpo_index,
+      loop_headers,
+    };
+
+    // Reducible means every edge going back in reverse postorder ends at a
+    // loop header dominating its source.
+    let reducible = (0..func.get_blocks().len())
+      .map(MirBlockId)
+      .filter(|&blk| shape.tree.is_reachable(blk))
+      .all(|blk| {
+        shape
+          .cfg
+          .successors(blk)
+          .iter()
+          .all(|&succ| !shape.is_backward(blk, succ) || shape.tree.dominates(succ, blk))
+      });
+    reducible.then_some(shape)
+  }
+
+  fn is_backward(&self, from: MirBlockId, to: MirBlockId) -> bool {
+    self.rpo_index[to.0] <= self.rpo_index[from.0]
+  }
+
+  /// Whether more than one forward edge reaches `blk`.
+  fn is_merge(&self, blk: MirBlockId) -> bool {
+    let forward = self
+      .cfg
+      .predecessors(blk)
+      .iter()
+      .filter(|&&pred| self.tree.is_reachable(pred) && !self.is_backward(pred, blk))
+      .count();
+    forward > 1
+  }
+}
+
+struct FunctionEmitter<'a, 'ctx> {
+  module: &'a MirModule<'ctx>,
+  func: &'a MirFunction<'ctx>,
+  order: Vec<MirBlockId>,
+  /// The block whose instructions are being emitted, as a position in
+  /// `order`.
+  current: usize,
+  out: String,
+  indent: usize,
+}
+
+impl<'a, 'ctx> FunctionEmitter<'a, 'ctx> {
+  fn new(module: &'a MirModule<'ctx>, func: &'a MirFunction<'ctx>) -> Self {
+    Self {
+      module,
+      func,
+      order: func.get_layout(),
+      current: 0,
+      out: String::new(),
+      indent: 2,
+    }
+  }
+
+  fn line(&mut self, line: impl AsRef<str>) {
+    writeln!(
+      self.out,
+      "{:indent$}{}",
+      "",
+      line.as_ref(),
+      indent = self.indent
+    )
+    .unwrap();
+  }
+
+  fn position(&self, blk: MirBlockId) -> usize {
+    self.order.iter().position(|&id| id == blk).unwrap()
+  }
+
+  fn emit(mut self, out: &mut String) {
+    let name = self.func.name();
+    let mut header = format!("(func ${} (export \"{}\")", name, name);
+    for (idx, type_) in self.func.get_params().iter().enumerate() {
+      write!(header, " (param $a{} {})", idx, wasm_type(type_).unwrap()).unwrap();
+    }
+    if let Some(ret) = wasm_type(&self.func.ret_type()) {
+      write!(header, " (result {})", ret).unwrap();
+    }
+    self.line(header);
+    self.indent += 2;
+
+    for &blk_id in &self.order.clone() {
+      for (inst_id, inst) in self.func.get_insts(blk_id) {
+        if let Some(type_) = local_type(inst) {
+          self.line(format!(
+            "(local $v{} {})",
+            inst_id.0,
+            wasm_type(&type_).unwrap()
+          ));
+        }
+        if let Instruction::Phi(phi) = inst {
+          self.line(format!(
+            "(local $p{} {})",
+            inst_id.0,
+            wasm_type(&phi.type_()).unwrap()
+          ));
+        }
+      }
+    }
+
+    let jumps = self.order.iter().any(|&blk| {
+      matches!(
+        self.func.get_terminator(blk),
+        Some(Instruction::Branch(_) | Instruction::Jump(_))
+      )
+    });
+    match Shape::new(self.func) {
+      Some(shape) => self.emit_tree(&shape, MirBlockId(0)),
+      None => self.emit_dispatch(),
+    }
+    // Wasm does not know that every path returns before the end of the body.
+    if jumps && self.func.ret_type() != ValType::Void {
+      self.line("unreachable");
+    }
+
+    self.indent -= 2;
+    self.line(")");
+    out.push_str(&self.out);
+  }
+
+  /// Emits `blk` and the blocks it dominates.
+  fn emit_tree(&mut self, shape: &Shape, blk: MirBlockId) {
+    let mut merges = shape
+      .tree
+      .children(blk)
+      .iter()
+      .copied()
+      .filter(|&child| shape.is_merge(child))
+      .collect::<Vec<_>>();
+    // The merge block coming last is left through the outermost `block`.
+    merges.sort_by_key(|child| std::cmp::Reverse(shape.rpo_index[child.0]));
+
+    if shape.loop_headers.contains(&blk) {
+      self.line(format!("loop $loop{}", blk.0));
+      self.indent += 2;
+      self.emit_within(shape, blk, &merges);
+      self.indent -= 2;
+      self.line("end");
+    } else {
+      self.emit_within(shape, blk, &merges);
+    }
+  }
+
+  /// Emits `blk` inside a wasm `block` for each of `merges`, each followed
+  /// by the code of its merge block.
+  fn emit_within(&mut self, shape: &Shape, blk: MirBlockId, merges: &[MirBlockId]) {
+    let Some((&merge, inner)) = merges.split_first() else {
+      match self.emit_body(blk) {
+        Some((_, Instruction::Branch(branch))) => {
+          self.push(branch.cond());
+          if branch.then_blk() == branch.else_blk() {
+            self.line("drop");
+            self.emit_edge(shape, blk, branch.then_blk());
+            return;
+          }
+          self.line("if");
+          self.indent += 2;
+          self.emit_edge(shape, blk, branch.then_blk());
+          self.indent -= 2;
+          self.line("else");
+          self.indent += 2;
+          self.emit_edge(shape, blk, branch.else_blk());
+          self.indent -= 2;
+          self.line("end");
+        }
+        Some((_, Instruction::Jump(jump))) => self.emit_edge(shape, blk, jump.target()),
+        Some((id, inst)) => self.emit_inst(id, inst),
+        None => {}
+      }
+      return;
+    };
+
+    self.line(format!("block $bb{}", merge.0));
+    self.indent += 2;
+    self.emit_within(shape, blk, inner);
+    self.indent -= 2;
+    self.line("end");
+    self.emit_tree(shape, merge);
+  }
+
+  /// Transfers control from `from` to `to`.
+  fn emit_edge(&mut self, shape: &Shape, from: MirBlockId, to: MirBlockId) {
+    if shape.is_backward(from, to) {
+      self.line(format!("br $loop{}", to.0));
+    } else if shape.is_merge(to) {
+      self.line(format!("br $bb{}", to.0));
+    } else {
+      self.emit_tree(shape, to);
+    }
+  }
+
+  /// Emits the instructions of `blk` up to its terminator and the values
+  /// the phis of its successors take, and returns the terminator.
+  fn emit_body(&mut self, blk: MirBlockId) -> Option<(MirInstId, &'a Instruction)> {
+    self.line(format!(
+      ";; {}.{}",
+      self.func.get_block(blk).unwrap().name(),
+      blk.0
+    ));
+    let func = self.func;
+    for (inst_id, inst) in func.get_insts(blk) {
+      if inst.is_terminator() {
+        for (phi, value) in outgoing_phi_values(func, blk) {
+          self.push(value);
+          self.line(format!("local.set $p{}", phi.0));
+        }
+        return Some((inst_id, inst));
+      }
+      self.emit_inst(inst_id, inst);
+    }
+    None
+  }
+
+  /// The fallback for irreducible control flow.
+  fn emit_dispatch(&mut self) {
+    self.line("(local $bb i32)");
+    let entry = self.position(MirBlockId(0));
+    if entry != 0 {
+      self.line(format!("i32.const {}", entry));
+      self.line("local.set $bb");
+    }
+
+    self.line("loop $dispatch");
+    self.indent += 2;
+    let labels = self
+      .order
+      .iter()
+      .map(|id| format!("$bb{}", id.0))
+      .collect::<Vec<_>>();
+    for label in labels.iter().rev() {
+      self.line(format!("block {}", label));
+    }
+    self.line("local.get $bb");
+    self.line(format!(
+      "br_table {} {}",
+      labels.join(" "),
+      labels.last().unwrap()
+    ));
+    for pos in 0..self.order.len() {
+      self.line("end");
+      self.emit_dispatch_block(pos);
+    }
+    self.indent -= 2;
+    self.line("end");
+  }
+
+  fn emit_dispatch_block(&mut self, pos: usize) {
+    self.current = pos;
+    match self.emit_body(self.order[pos]) {
+      Some((_, Instruction::Branch(branch))) => {
+        self.push(branch.cond());
+        let next = self.order.get(self.current + 1).copied();
+        let (then_blk, else_blk) = (branch.then_blk(), branch.else_blk());
+        if Some(then_blk) == next && Some(else_blk) == next {
+          self.line("drop");
+          return;
+        }
+        let (taken, other) = if Some(then_blk) == next {
+          self.line("i32.eqz");
+          (else_blk, None)
+        } else if Some(else_blk) == next {
+          (then_blk, None)
+        } else {
+          (then_blk, Some(else_blk))
+        };
+        self.line("if");
+        self.indent += 2;
+        self.goto(taken);
+        if let Some(other) = other {
+          self.indent -= 2;
+          self.line("else");
+          self.indent += 2;
+          self.goto(other);
+        }
+        self.indent -= 2;
+        self.line("end");
+      }
+      Some((_, Instruction::Jump(jump))) => self.goto(jump.target()),
+      Some((id, inst)) => self.emit_inst(id, inst),
+      None => {}
+    }
+  }
+
+  /// Transfers control to `target` in the dispatch loop, falling through
+  /// when it comes next.
+  fn goto(&mut self, target: MirBlockId) {
+    let pos = self.position(target);
+    if pos != self.current + 1 {
+      self.line(format!("i32.const {}", pos));
+      self.line("local.set $bb");
+      self.line("br $dispatch");
+    }
+  }
+
+  fn push(&mut self, value: &MirValue) {
+    let line = match value {
+      MirValue::Inst(id) => format!("local.get $v{}", id.0),
+      MirValue::Constant(constant) => match constant {
+        Constant::IntValue(_, value) => format!("i32.const {}", *value as i32),
+        Constant::FloatValue(_, value) => {
+          let value = *value as f32;
+          if value.is_finite() {
+            format!("f32.const {:?}", value)
+          } else {
+            "f32.const inf".to_string()
+          }
+        }
+        Constant::BoolValue(value) => format!("i32.const {}", *value as i32),
+        Constant::Null => return,
+      },
+    };
+    self.line(line);
+  }
+
+  fn binary(&mut self, op: &str, lhs: &MirValue, rhs: &MirValue) {
+    self.push(lhs);
+    self.push(rhs);
+    self.line(op);
+  }
+
+  fn emit_inst(&mut self, id: MirInstId, inst: &Instruction) {
+    match inst {
+      Instruction::Alloca(_) => return,
+      Instruction::Store(store) => {
+        self.push(store.value());
+        self.line(format!("local.set $v{}", store.target().0));
+        return;
+      }
+      Instruction::Load(load) => match load.from() {
+        Place::Local(slot) => self.line(format!("local.get $v{}", slot.0)),
+        Place::Param(idx) => self.line(format!("local.get $a{}", idx)),
+      },
+      Instruction::Add(inst) => {
+        let op = format!("{}.add", wasm_type(&inst.type_()).unwrap());
+        self.binary(&op, inst.lhs(), inst.rhs());
+      }
+      Instruction::Sub(inst) => {
+        let op = format!("{}.sub", wasm_type(&inst.type_()).unwrap());
+        self.binary(&op, inst.lhs(), inst.rhs());
+      }
+      Instruction::Mul(inst) => {
+        let op = format!("{}.mul", wasm_type(&inst.type_()).unwrap());
+        self.binary(&op, inst.lhs(), inst.rhs());
+      }
+      Instruction::Div(inst) => {
+        let op = match inst.type_() {
+          ValType::I32 => "call $sable.div_i32",
+          _ => "f32.div",
+        };
+        self.binary(op, inst.lhs(), inst.rhs());
+      }
+      Instruction::Cmp(cmp) => {
+        let (name, signed) = match cmp.predicate() {
+          CmpPredicate::Eq => ("eq", ""),
+          CmpPredicate::Ne => ("ne", ""),
+          CmpPredicate::Lt => ("lt", "_s"),
+          CmpPredicate::Le => ("le", "_s"),
+          CmpPredicate::Gt => ("gt", "_s"),
+          CmpPredicate::Ge => ("ge", "_s"),
+        };
+        let op = match cmp.type_() {
+          ValType::F32 => format!("f32.{}", name),
+          // Booleans are 0 or 1, so they compare as unsigned integers.
+          ValType::Bool if !signed.is_empty() => format!("i32.{}_u", name),
+          _ => format!("i32.{}{}", name, signed),
+        };
+        self.binary(&op, cmp.lhs(), cmp.rhs());
+      }
+      Instruction::And(and) => self.binary("i32.and", and.lhs(), and.rhs()),
+      Instruction::Or(or) => self.binary("i32.or", or.lhs(), or.rhs()),
+      Instruction::Not(not) => {
+        self.push(not.value());
+        self.line("i32.eqz");
+      }
+      Instruction::Return(ret) => {
+        self.push(ret.ret_value());
+        self.line("return");
+        return;
+      }
+      Instruction::Call(call) => {
+        for arg in call.args() {
+          self.push(arg);
+        }
+        let callee = self.module.get_func(call.callee()).unwrap().name();
+        self.line(format!("call ${}", callee));
+        if call.type_() == ValType::Void {
+          return;
+        }
+      }
+      Instruction::Branch(_) | Instruction::Jump(_) => {
+        unreachable!("branches are emitted with the control flow")
+      }
+      Instruction::Phi(_) => self.line(format!("local.get $p{}", id.0)),
+      Instruction::Unreachable => {
+        self.line("unreachable");
+        return;
+      }
+    }
+    self.line(format!("local.set $v{}", id.0));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_driver::session::Session;
+  use sable_mir::{
+    mir::parser::parse_module,
+    transform::{mem2reg::Mem2Reg, pass::PassManager},
+  };
+
+  use super::*;
+
+  fn emit(source: &str) -> String {
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    emit_module(&module.borrow())
+  }
+
+  #[test]
+  fn test_emit_module() {
+    let wat = emit(
+      "
+      func i32 pick(bool b, i32 x) { if b { return x / 2; } return 0; }
+      func f32 scale(f32 x) { return x * 2.5; }
+      ",
+    );
+    let expected = r#";; generated by sable from module `test`
+(module
+  (func $pick (export "pick") (param $a0 i32) (param $a1 i32) (result i32)
+    (local $v0 i32)
+    (local $v2 i32)
+    (local $v3 i32)
+    ;; entry.0
+    local.get $a0
+    local.set $v0
+    local.get $v0
+    if
+      ;; then.1
+      local.get $a1
+      local.set $v2
+      local.get $v2
+      i32.const 2
+      call $sable.div_i32
+      local.set $v3
+      local.get $v3
+      return
+    else
+      ;; merge.2
+      i32.const 0
+      return
+    end
+    unreachable
+  )
+  (func $scale (export "scale") (param $a0 f32) (result f32)
+    (local $v0 f32)
+    (local $v1 f32)
+    ;; entry.0
+    local.get $a0
+    local.set $v0
+    local.get $v0
+    f32.const 2.5
+    f32.mul
+    local.set $v1
+    local.get $v1
+    return
+  )
+  (func $sable.div_i32 (param $a i32) (param $b i32) (result i32)
+    local.get $b
+    i32.const -1
+    i32.eq
+    if (result i32)
+      i32.const 0
+      local.get $a
+      i32.sub
+    else
+      local.get $a
+      local.get $b
+      i32.div_s
+    end
+  )
+)
+"#;
+    assert_eq!(wat, expected);
+  }
+
+  #[test]
+  fn test_emit_loops_and_void() {
+    let wat = emit(
+      "
+      func void spin(i32 n) {
+        let i32 i = 0;
+        while i < n { i = i + 1; if i == 3 { continue; } }
+      }
+      ",
+    );
+    assert!(wat.contains("(func $spin (export \"spin\") (param $a0 i32)\n"));
+    assert!(!wat.contains("$dispatch"), "{}", wat);
+    // The loop body is nested in the header's `if`, both arms of the inner
+    // `if` continue the loop, and the exit returns from the `else`.
+    assert!(
+      wat.contains("    loop $loop1\n      ;; header.1\n"),
+      "{}",
+      wat
+    );
+    assert_eq!(wat.matches("br $loop1").count(), 2, "{}", wat);
+    assert!(
+      wat.contains("      else\n        ;; exit.3\n        return\n"),
+      "{}",
+      wat
+    );
+    assert!(wat.contains("i32.lt_s\n"));
+    assert!(!wat.contains("sable.div_i32"));
+  }
+
+  #[test]
+  fn test_emit_phis() {
+    let source = "func i32 count() { let i32 i = 0; while i < 3 { i = i + 1; } return i; }";
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    let mut passes = PassManager::new();
+    passes.add_pass(Box::new(Mem2Reg));
+    passes.run(&mut module.borrow_mut());
+    let wat = emit_module(&module.borrow());
+    assert!(
+      wat.contains("(local $v1 i32)\n    (local $p1 i32)\n"),
+      "{}",
+      wat
+    );
+    // Both predecessors of the loop header set `$p1`, the phi reads it.
+    assert!(wat.contains("    i32.const 0\n    local.set $p1\n    loop $loop1\n"));
+    assert!(wat.contains("        local.get $v4\n        local.set $p1\n        br $loop1\n"));
+    assert!(wat.contains(";; header.1\n      local.get $p1\n      local.set $v1\n"));
+  }
+
+  #[test]
+  fn test_emit_merges_and_nested_loops() {
+    let wat = emit(
+      "
+      func i32 f(i32 n, bool c) {
+        let i32 acc = 0;
+        while n > 0 {
+          let i32 i = 0;
+          while i < n { i = i + 1; if c { break; } }
+          if c { acc = acc + i; } else { acc = acc - 1; }
+          n = n - 1;
+        }
+        return acc;
+      }
+      ",
+    );
+    assert!(!wat.contains("$dispatch"), "{}", wat);
+    assert_eq!(wat.matches("loop $loop").count(), 2, "{}", wat);
+    // The inner loop's exit is reached from its header and the `break`,
+    // the merge after the `if` from both of its arms.
+    assert_eq!(wat.matches("    block $bb").count(), 2, "{}", wat);
+    assert_eq!(wat.matches("br $bb").count(), 4, "{}", wat);
+  }
+
+  #[test]
+  fn test_irreducible_control_flow_dispatches() {
+    // `a` and `b` form a loop entered at both of them.
+    let module = parse_module(
+      "
+module m
+func main() -> i32 {
+entry.0:
+  %n = alloca i32
+  store %n, 0
+  %c = cmp eq i32 1, 1
+  br %c, a.1, b.2
+a.1:
+  %x = load i32 %n
+  %y = add i32 %x, 1
+  store %n, %y
+  jmp b.2
+b.2:
+  %z = load i32 %n
+  %d = cmp lt i32 %z, 5
+  br %d, a.1, exit.3
+exit.3:
+  %r = load i32 %n
+  ret i32 %r
+}
+",
+    )
+    .unwrap();
+    let wat = emit_module(&module);
+    assert!(wat.contains("    loop $dispatch\n"), "{}", wat);
+    assert!(
+      wat.contains("br_table $bb0 $bb1 $bb2 $bb3 $bb3\n"),
+      "{}",
+      wat
+    );
+    assert!(!wat.contains("$loop"), "{}", wat);
+  }
+}
@@ -0,0 +1,758 @@
+//! Translates a verified `MirModule` into x86-64 assembly for the GNU
+//! assembler, following the System V calling convention on Linux.
+//!
+//! Values live in the registers [`regalloc`] assigns them, `alloca` slots,
+//! parameters and spilled values in the `%rbp`-based frame. Parameters are
+//! copied from their argument registers into the frame on entry. Each phi
+//! has a frame slot its predecessors store its value in before their
+//! terminator, which the phi loads at the start of its block. Every
+//! instruction goes through the scratch registers `%eax`, `%ecx` and
+//! `%xmm0`/`%xmm1`, so any operand can be in a register or in memory.
+//! Functions are named after their MIR name with an `sbl_` prefix and a
+//! `main` calling `sbl_main` is added like in the C backend. Runtime
+//! faults write a message to stderr and exit with status 1 through raw
+//! system calls, so the output only needs the C runtime for its start-up
+//! code.
+//!
+//! When the module names its source file, it is declared with `.file` and
+//! the code of each instruction coming from a new source line is preceded
+//! by a `.loc`, from which the assembler builds the DWARF line table.
+
+pub mod regalloc;
+
+use std::{collections::HashMap, fmt::Write};
+
+use regalloc::{Allocation, Location, allocate};
+use sable_mir::{
+  analysis::cfg::outgoing_phi_values,
+  mir::{
+    function::{MirFunction, block::MirBlockId},
+    instruction::{CmpPredicate, Instruction, MirInstId},
+    module::MirModule,
+    place::Place,
+    value::{Constant, MirValue},
+  },
+};
+use sable_parser::info::ValType;
+
+const GPR_ARGS: [&str; 6] = ["%edi", "%esi", "%edx", "%ecx", "%r8d", "%r9d"];
+const SSE_ARGS: usize = 8;
+
+const DIV_BY_ZERO: &str = "error: integer division by zero\\n";
+const UNREACHABLE: &str = "error: reached unreachable code\\n";
+
+/// Emits the assembly for `module`.
+pub fn emit_module(module: &MirModule) -> String {
+  let mut out = String::new();
+  writeln!(out, "# generated by sable from module `{}`", module.name()).unwrap();
+  out.push_str("  .text\n");
+  if let Some(file) = module.source_file() {
+    writeln!(out, "  .file 1 {}", quoted(file)).unwrap();
+  }
+  for func in module.get_funcs() {
+    out.push('\n');
+    FunctionEmitter::new(module, func).emit(&mut out);
+  }
+
+  let main = module.get_funcs().iter().find(|func| func.name() == "main");
+  if let Some(main) = main.filter(|main| main.get_params().is_empty()) {
+    let call = match main.ret_type() {
+      ValType::I32 => "  call sbl_main\n",
+      ValType::Void => "  call sbl_main\n  xorl %eax, %eax\n",
+      _ => "",
+    };
+    if !call.is_empty() {
+      write!(
+        out,
+        "\n  .globl main\n  .type main, @function\nmain:\n  pushq %rbp\n  movq %rsp, %rbp\n\
+         {}  popq %rbp\n  ret\n",
+        call
+      )
+      .unwrap();
+    }
+  }
+
+  write!(
+    out,
+    "
+.Lsable_div_by_zero:
+  leaq .Lsable_msg_div(%rip), %rsi
+  movl ${}, %edx
+  jmp .Lsable_trap
+.Lsable_unreachable:
+  leaq .Lsable_msg_unreachable(%rip), %rsi
+  movl ${}, %edx
+.Lsable_trap:
+  movl $2, %edi
+  movl $1, %eax
+  syscall
+  movl $1, %edi
+  movl $231, %eax
+  syscall
+
+  .section .rodata
+.Lsable_msg_div:
+  .ascii \"{}\"
+.Lsable_msg_unreachable:
+  .ascii \"{}\"
+
+  .section .note.GNU-stack,\"\",@progbits
+",
+    DIV_BY_ZERO.len() - 1,
+    UNREACHABLE.len() - 1,
+    DIV_BY_ZERO,
+    UNREACHABLE
+  )
+  .unwrap();
+  out
+}
+
+/// `file` as an assembler string.
+fn quoted(file: &str) -> String {
+  format!("\"{}\"", file.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn is_float(type_: &ValType) -> bool {
+  *type_ == ValType::F32
+}
+
+/// Where the value of an operand can be read from.
+enum Operand {
+  Imm(u32),
+  Reg(String),
+  Mem(String),
+}
+
+struct FunctionEmitter<'a, 'ctx> {
+  module: &'a MirModule<'ctx>,
+  func: &'a MirFunction<'ctx>,
+  allocation: Allocation,
+  /// The frame offsets of the `alloca` and phi slots and of parameters.
+  slots: HashMap<MirInstId, i64>,
+  params: Vec<i64>,
+  /// The offset of the first spill slot.
+  spill_base: i64,
+  frame_size: i64,
+  order: Vec<MirBlockId>,
+  current: usize,
+  /// The source line of the last `.loc`.
+  last_line: Option<usize>,
+  out: String,
+}
+
+impl<'a, 'ctx> FunctionEmitter<'a, 'ctx> {
+  fn new(module: &'a MirModule<'ctx>, func: &'a MirFunction<'ctx>) -> Self {
+    let allocation = allocate(func);
+    let saved = allocation.callee_saved().len() as i64 * 8;
+
+    // Frame slots are 8 bytes each, below the saved registers.
+    let mut next = saved;
+    let mut slot = || {
+      next += 8;
+      -next
+    };
+
+    let mut slots = HashMap::new();
+    for (id, inst) in (0..func.get_inst_count()).map(|id| (id, func.get_inst(MirInstId(id)))) {
+      if let Some(Instruction::Alloca(_) | Instruction::Phi(_)) = inst {
+        slots.insert(MirInstId(id), slot());
+      }
+    }
+
+    // Arguments past the registers are passed on the stack, above the
+    // return address and the saved `%rbp`.
+    let (mut gprs, mut sses, mut stack) = (0, 0, 0);
+    let mut params = Vec::new();
+    for type_ in func.get_params() {
+      let in_reg = if is_float(type_) {
+        sses += 1;
+        sses <= SSE_ARGS
+      } else {
+        gprs += 1;
+        gprs <= GPR_ARGS.len()
+      };
+      if in_reg {
+        params.push(slot());
+      } else {
+        params.push(16 + stack * 8);
+        stack += 1;
+      }
+    }
+
+    let spill_base = next + 8;
+    next += allocation.spill_slots() as i64 * 8;
+    // Keep `%rsp` 16-byte aligned for calls.
+    let frame_size = (next + 15) / 16 * 16 - saved;
+
+    Self {
+      module,
+      func,
+      allocation,
+      slots,
+      params,
+      spill_base,
+      frame_size,
+      order: func.get_layout(),
+      current: 0,
+      last_line: None,
+      out: String::new(),
+    }
+  }
+
+  fn line(&mut self, line: impl AsRef<str>) {
+    writeln!(self.out, "  {}", line.as_ref()).unwrap();
+  }
+
+  fn symbol(&self) -> String {
+    format!("sbl_{}", self.func.name())
+  }
+
+  fn label(&self, blk: MirBlockId) -> String {
+    format!(".L{}_{}", self.func.name(), blk.0)
+  }
+
+  fn emit(mut self, out: &mut String) {
+    let symbol = self.symbol();
+    self.line(format!(".globl {}", symbol));
+    self.line(format!(".type {}, @function", symbol));
+    writeln!(self.out, "{}:", symbol).unwrap();
+    self.line("pushq %rbp");
+    self.line("movq %rsp, %rbp");
+    for reg in self.allocation.callee_saved() {
+      self.line(format!("pushq {}", reg.name64()));
+    }
+    if self.frame_size > 0 {
+      self.line(format!("subq ${}, %rsp", self.frame_size));
+    }
+
+    let (mut gprs, mut sses) = (0, 0);
+    for (idx, type_) in self.func.get_params().iter().enumerate() {
+      let offset = self.params[idx];
+      if is_float(type_) && sses < SSE_ARGS {
+        self.line(format!("movss %xmm{}, {}(%rbp)", sses, offset));
+        sses += 1;
+      } else if !is_float(type_) && gprs < GPR_ARGS.len() {
+        self.line(format!("movl {}, {}(%rbp)", GPR_ARGS[gprs], offset));
+        gprs += 1;
+      }
+    }
+
+    if self.order.first() != Some(&MirBlockId(0)) {
+      self.line(format!("jmp {}", self.label(MirBlockId(0))));
+    }
+    for pos in 0..self.order.len() {
+      self.current = pos;
+      let blk_id = self.order[pos];
+      writeln!(self.out, "{}:", self.label(blk_id)).unwrap();
+      for (inst_id, inst) in self.func.get_insts(blk_id) {
+        self.emit_loc(inst_id);
+        if inst.is_terminator() {
+          self.emit_phi_copies(blk_id);
+        }
+        self.emit_inst(inst_id, inst);
+      }
+    }
+
+    writeln!(self.out, ".L{}_ret:", self.func.name()).unwrap();
+    let saved = self.allocation.callee_saved();
+    if saved.is_empty() {
+      self.line("movq %rbp, %rsp");
+    } else {
+      self.line(format!("leaq -{}(%rbp), %rsp", saved.len() * 8));
+      for reg in saved.iter().rev() {
+        self.line(format!("popq {}", reg.name64()));
+      }
+    }
+    self.line("popq %rbp");
+    self.line("ret");
+    self.line(format!(".size {}, .-{}", symbol, symbol));
+    out.push_str(&self.out);
+  }
+
+  /// Marks the code of the instruction `id` as coming from its source
+  /// line, unless the code before it already is.
+  fn emit_loc(&mut self, id: MirInstId) {
+    if self.module.source_file().is_none() {
+      return;
+    }
+    if let Some(pos) = self.func.get_inst_pos(id)
+      && self.last_line != Some(pos.line)
+    {
+      self.line(format!(".loc 1 {}", pos.line));
+      self.last_line = Some(pos.line);
+    }
+  }
+
+  fn location(&self, id: MirInstId) -> Option<Operand> {
+    match self.allocation.location(id)? {
+      Location::Reg(reg) => Some(Operand::Reg(reg.name32())),
+      Location::Spill(slot) => Some(Operand::Mem(format!(
+        "{}(%rbp)",
+        -(self.spill_base + slot as i64 * 8)
+      ))),
+    }
+  }
+
+  fn operand(&self, value: &MirValue) -> Operand {
+    match value {
+      MirValue::Inst(id) => self.location(*id).unwrap(),
+      MirValue::Constant(constant) => Operand::Imm(match constant {
+        Constant::IntValue(_, value) => *value as i32 as u32,
+        Constant::FloatValue(_, value) => (*value as f32).to_bits(),
+        Constant::BoolValue(value) => *value as u32,
+        Constant::Null => 0,
+      }),
+    }
+  }
+
+  /// Loads an `i32` or `bool` value into the scratch register `reg`.
+  fn load_gpr(&mut self, value: &MirValue, reg: &str) {
+    match self.operand(value) {
+      Operand::Imm(imm) => self.line(format!("movl ${}, {}", imm as i32, reg)),
+      Operand::Reg(src) | Operand::Mem(src) => self.line(format!("movl {}, {}", src, reg)),
+    }
+  }
+
+  /// Loads an `f32` value into the scratch register `reg`, using `%eax`
+  /// for constants.
+  fn load_sse(&mut self, value: &MirValue, reg: &str) {
+    match self.operand(value) {
+      Operand::Imm(bits) => {
+        self.line(format!("movl ${:#x}, %eax", bits));
+        self.line(format!("movd %eax, {}", reg));
+      }
+      Operand::Reg(src) => self.line(format!("movaps {}, {}", src, reg)),
+      Operand::Mem(src) => self.line(format!("movss {}, {}", src, reg)),
+    }
+  }
+
+  /// Stores the result of `id` from `%eax` or `%xmm0`.
+  fn store_result(&mut self, id: MirInstId, float: bool) {
+    let Some(dest) = self.location(id) else {
+      return;
+    };
+    let dest = match dest {
+      Operand::Reg(dest) | Operand::Mem(dest) => dest,
+      Operand::Imm(_) => unreachable!(),
+    };
+    match (float, self.allocation.location(id)) {
+      (true, Some(Location::Reg(_))) => self.line(format!("movaps %xmm0, {}", dest)),
+      (true, _) => self.line(format!("movss %xmm0, {}", dest)),
+      (false, _) => self.line(format!("movl %eax, {}", dest)),
+    }
+  }
+
+  /// Stores the values the phis of the successors of `blk` take into
+  /// their slots.
+  fn emit_phi_copies(&mut self, blk: MirBlockId) {
+    for (phi, value) in outgoing_phi_values(self.func, blk) {
+      let slot = self.slots[&phi];
+      match self.func.get_inst(phi).and_then(Instruction::value_type) {
+        Some(ValType::F32) => {
+          self.load_sse(value, "%xmm0");
+          self.line(format!("movss %xmm0, {}(%rbp)", slot));
+        }
+        _ => {
+          self.load_gpr(value, "%eax");
+          self.line(format!("movl %eax, {}(%rbp)", slot));
+        }
+      }
+    }
+  }
+
+  /// Transfers control to `target`, falling through when it comes next.
+  fn goto(&mut self, target: MirBlockId) {
+    if self.order.get(self.current + 1) != Some(&target) {
+      self.line(format!("jmp {}", self.label(target)));
+    }
+  }
+
+  fn binary(&mut self, type_: &ValType, lhs: &MirValue, rhs: &MirValue, int: &str, float: &str) {
+    if is_float(type_) {
+      self.load_sse(lhs, "%xmm0");
+      self.load_sse(rhs, "%xmm1");
+      self.line(format!("{} %xmm1, %xmm0", float));
+    } else {
+      self.load_gpr(lhs, "%eax");
+      self.load_gpr(rhs, "%ecx");
+      self.line(format!("{} %ecx, %eax", int));
+    }
+  }
+
+  fn emit_inst(&mut self, id: MirInstId, inst: &Instruction) {
+    let float = inst.value_type().as_ref().is_some_and(is_float);
+    match inst {
+      Instruction::Alloca(_) => return,
+      Instruction::Store(store) => {
+        let slot = self.slots[&store.target()];
+        let Some(Instruction::Alloca(alloca)) = self.func.get_inst(store.target()) else {
+          unreachable!("store to a non-slot in verified MIR");
+        };
+        if is_float(&alloca.type_()) {
+          self.load_sse(store.value(), "%xmm0");
+          self.line(format!("movss %xmm0, {}(%rbp)", slot));
+        } else {
+          self.load_gpr(store.value(), "%eax");
+          self.line(format!("movl %eax, {}(%rbp)", slot));
+        }
+        return;
+      }
+      Instruction::Load(load) => {
+        let offset = match load.from() {
+          Place::Local(slot) => self.slots[slot],
+          Place::Param(idx) => self.params[*idx],
+        };
+        if float {
+          self.line(format!("movss {}(%rbp), %xmm0", offset));
+        } else {
+          self.line(format!("movl {}(%rbp), %eax", offset));
+        }
+      }
+      Instruction::Add(inst) => self.binary(&inst.type_(), inst.lhs(), inst.rhs(), "addl", "addss"),
+      Instruction::Sub(inst) => self.binary(&inst.type_(), inst.lhs(), inst.rhs(), "subl", "subss"),
+      Instruction::Mul(inst) => {
+        self.binary(&inst.type_(), inst.lhs(), inst.rhs(), "imull", "mulss")
+      }
+      Instruction::Div(inst) if is_float(&inst.type_()) => {
+        self.binary(&inst.type_(), inst.lhs(), inst.rhs(), "", "divss")
+      }
+      Instruction::Div(inst) => {
+        // `idiv` faults on `i32::MIN / -1`, which wraps everywhere else.
+        self.load_gpr(inst.lhs(), "%eax");
+        self.load_gpr(inst.rhs(), "%ecx");
+        self.line("testl %ecx, %ecx");
+        self.line("je .Lsable_div_by_zero");
+        self.line("cmpl $-1, %ecx");
+        self.line("jne 1f");
+        self.line("negl %eax");
+        self.line("jmp 2f");
+        writeln!(self.out, "1:").unwrap();
+        self.line("cltd");
+        self.line("idivl %ecx");
+        writeln!(self.out, "2:").unwrap();
+      }
+      Instruction::Cmp(cmp) if is_float(&cmp.type_()) => {
+        // `ucomiss` sets the flags like an unsigned comparison and sets the
+        // parity flag for NaN, which `a`/`ae` already treat as false.
+        let (first, second, cond) = match cmp.predicate() {
+          CmpPredicate::Gt => (cmp.lhs(), cmp.rhs(), "a"),
+          CmpPredicate::Ge => (cmp.lhs(), cmp.rhs(), "ae"),
+          CmpPredicate::Lt => (cmp.rhs(), cmp.lhs(), "a"),
+          CmpPredicate::Le => (cmp.rhs(), cmp.lhs(), "ae"),
+          CmpPredicate::Eq | CmpPredicate::Ne => (cmp.lhs(), cmp.rhs(), ""),
+        };
+        self.load_sse(first, "%xmm0");
+        self.load_sse(second, "%xmm1");
+        self.line("ucomiss %xmm1, %xmm0");
+        match cmp.predicate() {
+          CmpPredicate::Eq => {
+            self.line("sete %al");
+            self.line("setnp %cl");
+            self.line("andb %cl, %al");
+          }
+          CmpPredicate::Ne => {
+            self.line("setne %al");
+            self.line("setp %cl");
+            self.line("orb %cl, %al");
+          }
+          _ => self.line(format!("set{} %al", cond)),
+        }
+        self.line("movzbl %al, %eax");
+      }
+      Instruction::Cmp(cmp) => {
+        let cond = match cmp.predicate() {
+          CmpPredicate::Eq => "e",
+          CmpPredicate::Ne => "ne",
+          CmpPredicate::Lt => "l",
+          CmpPredicate::Le => "le",
+          CmpPredicate::Gt => "g",
+          CmpPredicate::Ge => "ge",
+        };
+        self.load_gpr(cmp.lhs(), "%eax");
+        self.load_gpr(cmp.rhs(), "%ecx");
+        self.line("cmpl %ecx, %eax");
+        self.line(format!("set{} %al", cond));
+        self.line("movzbl %al, %eax");
+      }
+      Instruction::And(and) => self.binary(&ValType::Bool, and.lhs(), and.rhs(), "andl", ""),
+      Instruction::Or(or) => self.binary(&ValType::Bool, or.lhs(), or.rhs(), "orl", ""),
+      Instruction::Not(not) => {
+        self.load_gpr(not.value(), "%eax");
+        self.line("xorl $1, %eax");
+      }
+      Instruction::Return(ret) => {
+        match ret.type_() {
+          ValType::Void => {}
+          ValType::F32 => self.load_sse(ret.ret_value(), "%xmm0"),
+          _ => self.load_gpr(ret.ret_value(), "%eax"),
+        }
+        // The epilogue directly follows the last block.
+        if self.current + 1 < self.order.len() {
+          self.line(format!("jmp .L{}_ret", self.func.name()));
+        }
+        return;
+      }
+      Instruction::Call(call) => {
+        let callee = self.module.get_func(call.callee()).unwrap();
+        let (mut gprs, mut sses) = (Vec::new(), Vec::new());
+        let mut stack = Vec::new();
+        for (arg, type_) in call.args().iter().zip(callee.get_params()) {
+          let float = is_float(type_);
+          if float && sses.len() < SSE_ARGS {
+            sses.push(arg);
+          } else if !float && gprs.len() < GPR_ARGS.len() {
+            gprs.push(arg);
+          } else {
+            stack.push((arg, float));
+          }
+        }
+
+        // Stack arguments are pushed last to first, keeping `%rsp`
+        // aligned at the call.
+        let pad = if stack.len() % 2 == 1 { 8 } else { 0 };
+        if pad != 0 {
+          self.line("subq $8, %rsp");
+        }
+        for (arg, float) in stack.iter().rev() {
+          if *float {
+            self.load_sse(arg, "%xmm0");
+            self.line("movd %xmm0, %eax");
+          } else {
+            self.load_gpr(arg, "%eax");
+          }
+          self.line("pushq %rax");
+        }
+        for (idx, arg) in sses.iter().enumerate() {
+          self.load_sse(arg, &format!("%xmm{}", idx));
+        }
+        for (idx, arg) in gprs.iter().enumerate() {
+          self.load_gpr(arg, GPR_ARGS[idx]);
+        }
+        self.line(format!("call sbl_{}", callee.name()));
+        if !stack.is_empty() {
+          self.line(format!("addq ${}, %rsp", stack.len() * 8 + pad));
+        }
+      }
+      Instruction::Branch(branch) => {
+        self.load_gpr(branch.cond(), "%eax");
+        self.line("testl %eax, %eax");
+        let next = self.order.get(self.current + 1).copied();
+        if next == Some(branch.then_blk()) {
+          self.line(format!("je {}", self.label(branch.else_blk())));
+        } else {
+          self.line(format!("jne {}", self.label(branch.then_blk())));
+          self.goto(branch.else_blk());
+        }
+        return;
+      }
+      Instruction::Jump(jump) => {
+        self.goto(jump.target());
+        return;
+      }
+      Instruction::Phi(_) => {
+        let slot = self.slots[&id];
+        if float {
+          self.line(format!("movss {}(%rbp), %xmm0", slot));
+        } else {
+          self.line(format!("movl {}(%rbp), %eax", slot));
+        }
+      }
+      Instruction::Unreachable => {
+        self.line("jmp .Lsable_unreachable");
+        return;
+      }
+    }
+    self.store_result(id, float);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+  use std::{
+    env,
+    process::{Command, Stdio},
+  };
+
+  #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+  use sable_driver::cc::{assemble, c_compiler};
+  use sable_driver::session::Session;
+  #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+  use sable_interp::{interpreter::Interpreter, value::Value};
+  #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+  use sable_mir::transform::pass::{OptLevel, PassManager};
+
+  use super::*;
+
+  fn emit(source: &str) -> String {
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    emit_module(&module.borrow())
+  }
+
+  #[test]
+  fn test_emit_function() {
+    let asm = emit("func f32 half(f32 x, bool b) { if b { return x / 2.0; } return x; }");
+    let expected = "\
+sbl_half:
+  pushq %rbp
+  movq %rsp, %rbp
+  subq $16, %rsp
+  movss %xmm0, -8(%rbp)
+  movl %edi, -16(%rbp)
+.Lhalf_0:
+  movl -16(%rbp), %eax
+  movl %eax, %r10d
+  movl %r10d, %eax
+  testl %eax, %eax
+  je .Lhalf_2
+.Lhalf_1:
+  movss -8(%rbp), %xmm0
+  movaps %xmm0, %xmm8
+  movaps %xmm8, %xmm0
+  movl $0x40000000, %eax
+  movd %eax, %xmm1
+  divss %xmm1, %xmm0
+  movaps %xmm0, %xmm9
+  movaps %xmm9, %xmm0
+  jmp .Lhalf_ret
+.Lhalf_2:
+  movss -8(%rbp), %xmm0
+  movaps %xmm0, %xmm10
+  movaps %xmm10, %xmm0
+.Lhalf_ret:
+  movq %rbp, %rsp
+  popq %rbp
+  ret
+";
+    assert!(asm.contains(expected), "{}", asm);
+    assert!(!asm.contains("\nmain:"));
+  }
+
+  #[test]
+  fn test_saves_registers_across_calls() {
+    let asm = emit(
+      "
+      func i32 id(i32 x) { return x; }
+      func i32 main() { let i32 a = id(1); let i32 b = id(2); return a + id(b); }
+      ",
+    );
+    assert!(asm.contains("  pushq %rbx\n"), "{}", asm);
+    assert!(
+      asm.contains("  leaq -8(%rbp), %rsp\n  popq %rbx\n"),
+      "{}",
+      asm
+    );
+    assert!(asm.contains("\nmain:\n"));
+  }
+
+  /// Assembles `source` and checks that the binary exits like the
+  /// interpreter says it should.
+  #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+  fn check_against_interpreter(name: &str, source: &str) {
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    let expected = match Interpreter::new(&module.borrow()).run_main() {
+      Ok(Value::I32(status)) => status as u8 as i32,
+      Ok(_) => 0,
+      Err(_) => 1,
+    };
+
+    // Once as lowered and once through the `-O2` pipeline.
+    for optimized in [false, true] {
+      if optimized {
+        PassManager::with_level(OptLevel::O2).run(&mut module.borrow_mut());
+      }
+      let exe = env::temp_dir().join(format!("sable-x86-test-{}-{}", name, std::process::id()));
+      assemble(&emit_module(&module.borrow()), &exe).unwrap();
+      let status = Command::new(&exe).stderr(Stdio::null()).status().unwrap();
+      std::fs::remove_file(&exe).unwrap();
+      assert_eq!(
+        status.code(),
+        Some(expected),
+        "{}, optimized: {}",
+        name,
+        optimized
+      );
+    }
+  }
+
+  #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+  #[test]
+  fn test_matches_interpreter() {
+    if Command::new(c_compiler())
+      .arg("--version")
+      .output()
+      .is_err()
+    {
+      return;
+    }
+
+    check_against_interpreter(
+      "loops",
+      "
+      func i32 collatz(i32 start) {
+        let i32 n = start;
+        let i32 steps = 0;
+        while n != 1 {
+          let i32 half = n / 2;
+          if half * 2 == n { n = half; } else { n = 3 * n + 1; }
+          steps = steps + 1;
+        }
+        return steps;
+      }
+      func i32 main() { return collatz(27); }
+      ",
+    );
+    check_against_interpreter(
+      "floats",
+      "
+      func bool close(f32 a, f32 b) { return a - b < 0.01 && b - a < 0.01; }
+      func i32 main() { if close(1.0 / 3.0 * 3.0, 1.0) || false { return 7; } return 3; }
+      ",
+    );
+    check_against_interpreter(
+      "stack_args",
+      "
+      func i32 many(i32 a, i32 b, i32 c, i32 d, i32 e, i32 f, i32 g, i32 h, f32 x) {
+        if x > 1.5 { return a + b * c - d + e + f + g * h; }
+        return h - g;
+      }
+      func f32 scale(f32 x, f32 y) { return x * y + 0.5; }
+      func i32 main() {
+        let i32 total = 0;
+        let i32 i = 0;
+        while i < 10 {
+          total = total + many(i, 2, 3, 4, 5, 6, 7, i, scale(2.0, 1.0));
+          i = i + 1;
+        }
+        if scale(1.0, 1.0) == 1.5 { total = total + 1; }
+        return total / 3;
+      }
+      ",
+    );
+    check_against_interpreter(
+      "spills",
+      "
+      func i32 id(i32 x) { return x; }
+      func i32 main() {
+        let i32 a = id(1); let i32 b = id(2); let i32 c = id(3); let i32 d = id(4);
+        let i32 e = id(5); let i32 f = id(6); let i32 g = id(7); let i32 h = id(8);
+        let f32 x = 1.5; let f32 y = 2.5;
+        let i32 s = a * b + (c * d + (e * f + (g * h + (a * h + (b * g + (c * f + (d * e
+          + (a * c + (b * d + (e * g + f * h))))))))));
+        if x * y > 3.0 { return s + id(a + b + c + d + e + f + g + h); }
+        return s;
+      }
+      ",
+    );
+    check_against_interpreter(
+      "wrapping",
+      "func i32 main() { let i32 big = 2147483647; return (big + 2) / (0 - 1) * 3; }",
+    );
+    check_against_interpreter(
+      "div_zero",
+      "func i32 main() { let i32 z = 0; return 5 / z; }",
+    );
+  }
+}
@@ -0,0 +1,375 @@
+//! Linear-scan register allocation (Poletto and Sarkar) over the values of
+//! a function.
+//!
+//! Instructions are numbered in layout order, gaps left by erased ones
+//! aside. Each value gets a single live interval from its definition to
+//! its last use, stretched over every block it is live through, so a value
+//! used inside a loop stays allocated for the whole loop. The values phis
+//! take are used at the end of the predecessor they come from. Values live
+//! across a call may only use callee-saved registers; there are no
+//! callee-saved SSE registers, so `f32` values live across a call spill.
+
+use std::collections::{HashMap, HashSet};
+
+use sable_mir::{
+  analysis::{cfg::Cfg, liveness::Liveness},
+  mir::{
+    function::MirFunction,
+    instruction::{Instruction, MirInstId},
+    value::MirValue,
+  },
+};
+use sable_parser::info::ValType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reg {
+  Rbx,
+  R12,
+  R13,
+  R14,
+  R15,
+  R10,
+  R11,
+  Xmm(u8),
+}
+
+impl Reg {
+  /// General purpose registers handed out by the allocator, callee-saved
+  /// ones first. `rax`, `rcx` and `rdx` are kept as scratch registers and
+  /// the argument registers are left alone so calls can be set up freely.
+  pub const GPRS: [Reg; 7] = [
+    Reg::Rbx,
+    Reg::R12,
+    Reg::R13,
+    Reg::R14,
+    Reg::R15,
+    Reg::R10,
+    Reg::R11,
+  ];
+
+  /// `xmm0` to `xmm7` pass arguments and serve as scratch registers.
+  pub const SSES: [Reg; 8] = [
+    Reg::Xmm(8),
+    Reg::Xmm(9),
+    Reg::Xmm(10),
+    Reg::Xmm(11),
+    Reg::Xmm(12),
+    Reg::Xmm(13),
+    Reg::Xmm(14),
+    Reg::Xmm(15),
+  ];
+
+  pub fn is_callee_saved(&self) -> bool {
+    matches!(self, Reg::Rbx | Reg::R12 | Reg::R13 | Reg::R14 | Reg::R15)
+  }
+
+  /// The name of the 64-bit register, or of the SSE register.
+  pub fn name64(&self) -> String {
+    match self {
+      Reg::Rbx => "%rbx".to_string(),
+      Reg::R12 => "%r12".to_string(),
+      Reg::R13 => "%r13".to_string(),
+      Reg::R14 => "%r14".to_string(),
+      Reg::R15 => "%r15".to_string(),
+      Reg::R10 => "%r10".to_string(),
+      Reg::R11 => "%r11".to_string(),
+      Reg::Xmm(idx) => format!("%xmm{}", idx),
+    }
+  }
+
+  /// The name of the low 32 bits of the register, or of the SSE register.
+  pub fn name32(&self) -> String {
+    match self {
+      Reg::Rbx => "%ebx".to_string(),
+      Reg::Xmm(_) => self.name64(),
+      _ => format!("{}d", self.name64()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+  Reg(Reg),
+  /// An index into the spill slots of the frame.
+  Spill(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interval {
+  pub value: MirInstId,
+  pub start: usize,
+  pub end: usize,
+  pub is_float: bool,
+  /// Whether a call happens while the value is live.
+  pub crosses_call: bool,
+}
+
+impl Interval {
+  fn allows(&self, reg: Reg) -> bool {
+    matches!(reg, Reg::Xmm(_)) == self.is_float && (!self.crosses_call || reg.is_callee_saved())
+  }
+}
+
+/// Where every value of a function lives.
+#[derive(Debug, Default)]
+pub struct Allocation {
+  locations: HashMap<MirInstId, Location>,
+  spill_slots: usize,
+}
+
+impl Allocation {
+  pub fn location(&self, value: MirInstId) -> Option<Location> {
+    self.locations.get(&value).copied()
+  }
+
+  pub fn spill_slots(&self) -> usize {
+    self.spill_slots
+  }
+
+  /// The callee-saved registers the function has to preserve.
+  pub fn callee_saved(&self) -> Vec<Reg> {
+    let used = self
+      .locations
+      .values()
+      .filter_map(|loc| match loc {
+        Location::Reg(reg) if reg.is_callee_saved() => Some(*reg),
+        _ => None,
+      })
+      .collect::<HashSet<_>>();
+    Reg::GPRS
+      .into_iter()
+      .filter(|reg| used.contains(reg))
+      .collect()
+  }
+}
+
+/// The values that need a register: everything producing a non-void value.
+fn value_type(inst: &Instruction) -> Option<ValType> {
+  inst.value_type().filter(|type_| *type_ != ValType::Void)
+}
+
+/// The values `inst` reads where it stands, which phis do not.
+fn used_values(inst: &Instruction) -> Vec<MirInstId> {
+  if matches!(inst, Instruction::Phi(_)) {
+    return Vec::new();
+  }
+  inst.operands().into_iter().filter_map(inst_value).collect()
+}
+
+fn inst_value(value: &MirValue) -> Option<MirInstId> {
+  match value {
+    MirValue::Inst(id) => Some(*id),
+    MirValue::Constant(_) => None,
+  }
+}
+
+/// The live interval of every value of `func`, ordered by start.
+pub fn live_intervals(func: &MirFunction) -> Vec<Interval> {
+  let mut bounds: HashMap<MirInstId, (usize, usize)> = HashMap::new();
+  let mut extend = |value: MirInstId, pos: usize| {
+    let bound = bounds.entry(value).or_insert((pos, pos));
+    bound.0 = bound.0.min(pos);
+    bound.1 = bound.1.max(pos);
+  };
+
+  let liveness = Liveness::new(func, &Cfg::new(func));
+  let mut calls = Vec::new();
+  let mut pos = 0;
+  for blk in func.get_layout() {
+    let start = pos;
+    for (id, inst) in func.get_insts(blk) {
+      if value_type(inst).is_some() {
+        extend(id, pos);
+      }
+      for used in used_values(inst) {
+        extend(used, pos);
+      }
+      if matches!(inst, Instruction::Call(_)) {
+        calls.push(pos);
+      }
+      pos += 1;
+    }
+    if pos == start {
+      continue;
+    }
+    for &value in liveness.live_in(blk) {
+      extend(value, start);
+    }
+    for &value in liveness.live_out(blk) {
+      extend(value, pos - 1);
+    }
+  }
+
+  let mut intervals = bounds
+    .into_iter()
+    .filter_map(|(value, (start, end))| {
+      let type_ = func.get_inst(value).and_then(value_type)?;
+      Some(Interval {
+        value,
+        start,
+        end,
+        is_float: type_ == ValType::F32,
+        crosses_call: calls.iter().any(|&call| start < call && call < end),
+      })
+    })
+    .collect::<Vec<_>>();
+  intervals.sort_by_key(|interval| (interval.start, interval.value.0));
+  intervals
+}
+
+/// Assigns every value of `func` a register or a spill slot.
+pub fn allocate(func: &MirFunction) -> Allocation {
+  let mut allocation = Allocation::default();
+  let mut free = Reg::GPRS.into_iter().chain(Reg::SSES).collect::<Vec<_>>();
+  // Intervals holding a register, with the register.
+  let mut active: Vec<(Interval, Reg)> = Vec::new();
+
+  for interval in live_intervals(func) {
+    // An operand's register can be reused for the result of the same
+    // instruction, operands are read before the result is written.
+    active.retain(|(other, reg)| {
+      let live = other.end > interval.start;
+      if !live {
+        free.push(*reg);
+      }
+      live
+    });
+
+    // Prefer caller-saved registers, they cost nothing to use.
+    let reg = free
+      .iter()
+      .copied()
+      .filter(|&reg| interval.allows(reg))
+      .min_by_key(|reg| reg.is_callee_saved());
+    if let Some(reg) = reg {
+      free.retain(|&other| other != reg);
+      allocation
+        .locations
+        .insert(interval.value, Location::Reg(reg));
+      active.push((interval, reg));
+      continue;
+    }
+
+    // Spill whichever interval that could hold this one's register ends
+    // last.
+    let victim = active
+      .iter()
+      .enumerate()
+      .filter(|(_, (other, reg))| interval.allows(*reg) && other.end > interval.end)
+      .max_by_key(|(_, (other, _))| other.end)
+      .map(|(idx, _)| idx);
+    let spilled = match victim {
+      Some(idx) => {
+        let (victim, reg) = active.remove(idx);
+        allocation
+          .locations
+          .insert(interval.value, Location::Reg(reg));
+        active.push((interval, reg));
+        victim.value
+      }
+      None => interval.value,
+    };
+    let slot = allocation.spill_slots;
+    allocation.spill_slots += 1;
+    allocation.locations.insert(spilled, Location::Spill(slot));
+  }
+  allocation
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_mir::mir::parser::parse_module;
+
+  use super::*;
+
+  #[test]
+  fn test_intervals_cover_loops() {
+    let module = parse_module(
+      "
+module m
+func f(i32 %arg0) -> i32 {
+entry.0:
+  %n = load i32 %arg0
+  %one = add i32 %n, 1
+  jmp header.1
+header.1:
+  %c = cmp lt i32 %one, 10
+  br %c, body.2, exit.3
+body.2:
+  %g = call i32 @f(%n)
+  jmp header.1
+exit.3:
+  ret i32 %one
+}
+",
+    )
+    .unwrap();
+    let func = &module.get_funcs()[0];
+    let intervals = live_intervals(func)
+      .into_iter()
+      .map(|iv| (iv.value.0, iv.start, iv.end, iv.crosses_call))
+      .collect::<Vec<_>>();
+    // `%n` and `%one` are live around the loop, and so across the call.
+    assert_eq!(
+      intervals,
+      [
+        (0, 0, 6, true),
+        (1, 1, 7, true),
+        (3, 3, 4, false),
+        (5, 5, 5, false)
+      ]
+    );
+
+    let allocation = allocate(func);
+    assert_eq!(
+      allocation.location(MirInstId(0)),
+      Some(Location::Reg(Reg::Rbx))
+    );
+    assert_eq!(
+      allocation.location(MirInstId(1)),
+      Some(Location::Reg(Reg::R12))
+    );
+    assert_eq!(
+      allocation.location(MirInstId(3)),
+      Some(Location::Reg(Reg::R10))
+    );
+    assert_eq!(allocation.callee_saved(), [Reg::Rbx, Reg::R12]);
+  }
+
+  #[test]
+  fn test_spills_under_pressure() {
+    // Twelve values live at once, only seven general purpose registers.
+    let mut source = String::from("module m\nfunc f(i32 %arg0) -> i32 {\nentry.0:\n");
+    for idx in 0..12 {
+      source.push_str(&format!("  %v{} = load i32 %arg0\n", idx));
+    }
+    source.push_str("  %s0 = add i32 %v0, %v1\n");
+    for idx in 2..12 {
+      source.push_str(&format!(
+        "  %s{} = add i32 %s{}, %v{}\n",
+        idx - 1,
+        idx - 2,
+        idx
+      ));
+    }
+    source.push_str("  ret i32 %s10\n}\n");
+    let module = parse_module(&source).unwrap();
+    let func = &module.get_funcs()[0];
+
+    let allocation = allocate(func);
+    assert_eq!(allocation.spill_slots(), 5);
+    // The values used last are the ones spilled.
+    for idx in 7..12 {
+      assert!(matches!(
+        allocation.location(MirInstId(idx)),
+        Some(Location::Spill(_))
+      ));
+    }
+    for idx in 12..23 {
+      assert!(matches!(
+        allocation.location(MirInstId(idx)),
+        Some(Location::Reg(_))
+      ));
+    }
+  }
+}
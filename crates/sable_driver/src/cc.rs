@@ -0,0 +1,71 @@
+use std::{
+  env,
+  fmt::Display,
+  io::{self, Write},
+  path::Path,
+  process::{Command, ExitStatus, Stdio},
+};
+
+/// Why the C compiler did not produce an executable.
+#[derive(Debug)]
+pub enum CcError {
+  Spawn(String, io::Error),
+  Failed(ExitStatus, String),
+}
+
+impl Display for CcError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CcError::Spawn(cc, err) => write!(f, "cannot run the C compiler `{}`: {}", cc, err),
+      CcError::Failed(status, stderr) => {
+        write!(f, "the C compiler failed with {}", status)?;
+        if !stderr.is_empty() {
+          write!(f, ":\n{}", stderr.trim_end())?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+/// The C compiler to use, `$CC` if set and `cc` otherwise.
+pub fn c_compiler() -> String {
+  env::var("CC").unwrap_or_else(|_| "cc".to_string())
+}
+
+/// Compiles the C99 translation unit `source` into the executable `output`.
+pub fn compile_c(source: &str, output: &Path) -> Result<(), CcError> {
+  run_cc(&["-std=c99", "-O2", "-x", "c"], source, output)
+}
+
+/// Assembles the GNU assembler `source` and links it into the executable
+/// `output`, with the C runtime providing the entry point.
+pub fn assemble(source: &str, output: &Path) -> Result<(), CcError> {
+  run_cc(&["-x", "assembler"], source, output)
+}
+
+/// Runs the C compiler on `source` piped through stdin.
+fn run_cc(flags: &[&str], source: &str, output: &Path) -> Result<(), CcError> {
+  let cc = c_compiler();
+  let mut child = Command::new(&cc)
+    .args(flags)
+    .args(["-", "-o"])
+    .arg(output)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|err| CcError::Spawn(cc.clone(), err))?;
+
+  let written = child.stdin.take().unwrap().write_all(source.as_bytes());
+  let result = child
+    .wait_with_output()
+    .map_err(|err| CcError::Spawn(cc.clone(), err))?;
+
+  // A compiler that gave up early also breaks the pipe, report why it did.
+  if !result.status.success() {
+    let stderr = String::from_utf8_lossy(&result.stderr).into_owned();
+    return Err(CcError::Failed(result.status, stderr));
+  }
+  written.map_err(|err| CcError::Spawn(cc, err))
+}
@@ -0,0 +1,3 @@
+pub mod cc;
+pub mod phase;
+pub mod session;
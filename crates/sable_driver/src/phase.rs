@@ -0,0 +1,42 @@
+use std::fmt::Display;
+
+use sable_parser::parser::error::ParseErrReport;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+  Lex,
+  Parse,
+  Analyze,
+  Lower,
+}
+
+impl Display for Phase {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Phase::Lex => write!(f, "lexing"),
+      Phase::Parse => write!(f, "parsing"),
+      Phase::Analyze => write!(f, "analysis"),
+      Phase::Lower => write!(f, "lowering"),
+    }
+  }
+}
+
+/// The diagnostics of the phase that stopped a compilation.
+pub struct PhaseFailure<'s> {
+  phase: Phase,
+  diagnostics: Vec<ParseErrReport<'s>>,
+}
+
+impl<'s> PhaseFailure<'s> {
+  pub fn new(phase: Phase, diagnostics: Vec<ParseErrReport<'s>>) -> Self {
+    Self { phase, diagnostics }
+  }
+
+  pub fn phase(&self) -> Phase {
+    self.phase
+  }
+
+  pub fn diagnostics(&self) -> &[ParseErrReport<'s>] {
+    &self.diagnostics
+  }
+}
@@ -0,0 +1,223 @@
+use std::{cell::RefCell, fs, io, path::Path, rc::Rc};
+
+use ariadne::Source;
+use sable_mir::{lowering::Lowerer, mir::module::MirModule};
+use sable_parser::{
+  ast::ast::AST,
+  lexer::{lexer::Lexer, token::TokenType},
+  parser::{
+    error::{ParseErrReport, ParserError, lexer_err::LexerError},
+    parser::Parser,
+  },
+};
+use sable_sema::{diagnostic::Diagnostic, lint::LintLevels, sema::Sema, types::TypeTable};
+
+use crate::phase::{Phase, PhaseFailure};
+
+/// A single source file going through the compiler.
+///
+/// Every phase method runs the phases before it first and stops at the
+/// first one that reports errors.
+pub struct Session {
+  filename: String,
+  module_name: String,
+  source: String,
+  lints: LintLevels,
+  /// Warnings of the last analysis.
+  warnings: RefCell<Vec<Diagnostic>>,
+}
+
+impl Session {
+  pub fn new(filename: impl Into<String>, source: impl Into<String>) -> Self {
+    let filename = filename.into();
+    let module_name = Path::new(&filename)
+      .file_stem()
+      .map(|stem| stem.to_string_lossy().into_owned())
+      .unwrap_or_else(|| filename.clone());
+    Self {
+      filename,
+      module_name,
+      source: source.into(),
+      lints: LintLevels::new(),
+      warnings: RefCell::new(Vec::new()),
+    }
+  }
+
+  pub fn from_path(path: &Path) -> io::Result<Self> {
+    let source = fs::read_to_string(path)?;
+    Ok(Self::new(path.display().to_string(), source))
+  }
+
+  pub fn filename(&self) -> &str {
+    &self.filename
+  }
+
+  pub fn module_name(&self) -> &str {
+    &self.module_name
+  }
+
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  pub fn set_lints(&mut self, lints: LintLevels) {
+    self.lints = lints;
+  }
+
+  pub fn lex(&self) -> Result<(), PhaseFailure<'_>> {
+    let mut lexer = Lexer::new(&self.source);
+    let mut diagnostics = Vec::new();
+    loop {
+      let token = lexer.lex();
+      match token.token_type {
+        TokenType::Eof => break,
+        TokenType::Err => {
+          let err = ParserError::LexerError(LexerError::new(token));
+          diagnostics.push(err.report(&self.filename));
+        }
+        _ => {}
+      }
+    }
+
+    if diagnostics.is_empty() {
+      Ok(())
+    } else {
+      Err(PhaseFailure::new(Phase::Lex, diagnostics))
+    }
+  }
+
+  pub fn parse(&self) -> Result<Rc<RefCell<AST<'_>>>, PhaseFailure<'_>> {
+    self.lex()?;
+
+    let mut lexer = Lexer::new(&self.source);
+    let mut parser = Parser::new(&mut lexer);
+    parser.parse().map_err(|errs| {
+      let diagnostics = errs.iter().map(|err| err.report(&self.filename)).collect();
+      PhaseFailure::new(Phase::Parse, diagnostics)
+    })
+  }
+
+  /// Analyzes the source, returning its AST along with the types sema
+  /// proved for its expressions.
+  pub fn analyze(&self) -> Result<(Rc<RefCell<AST<'_>>>, TypeTable), PhaseFailure<'_>> {
+    let ast = self.parse()?;
+
+    let mut sema = Sema::new(ast.clone());
+    sema.lints = self.lints.clone();
+    let result = sema.analyze().map_err(|errs| {
+      errs
+        .iter()
+        .map(|err| err.report(&self.filename))
+        .collect::<Vec<_>>()
+    });
+    *self.warnings.borrow_mut() = sema.warnings().to_vec();
+    if let Err(diagnostics) = result {
+      return Err(PhaseFailure::new(Phase::Analyze, diagnostics));
+    }
+
+    Ok((ast, sema.into_types()))
+  }
+
+  pub fn lower(&self) -> Result<Rc<RefCell<MirModule<'_>>>, PhaseFailure<'_>> {
+    let (ast, types) = self.analyze()?;
+
+    let mut lowerer = Lowerer::new(MirModule::new(&self.module_name), ast, types);
+    lowerer.lower().map_err(|errs| {
+      let diagnostics = errs.iter().map(|err| err.report(&self.filename)).collect();
+      PhaseFailure::new(Phase::Lower, diagnostics)
+    })
+  }
+
+  /// Renders every diagnostic of `failure` against this session's source.
+  pub fn render(&self, failure: &PhaseFailure<'_>) -> String {
+    let mut out = Vec::new();
+    for report in failure.diagnostics() {
+      self.write_report(report, &mut out).unwrap();
+    }
+    String::from_utf8_lossy(&out).into_owned()
+  }
+
+  pub fn emit(&self, failure: &PhaseFailure<'_>) {
+    for report in failure.diagnostics() {
+      self.write_report(report, io::stderr()).unwrap();
+    }
+  }
+
+  /// Renders the warnings of the last analysis.
+  pub fn render_warnings(&self) -> String {
+    let mut out = Vec::new();
+    for warning in self.warnings.borrow().iter() {
+      self
+        .write_report(&warning.report(&self.filename), &mut out)
+        .unwrap();
+    }
+    String::from_utf8_lossy(&out).into_owned()
+  }
+
+  pub fn emit_warnings(&self) {
+    for warning in self.warnings.borrow().iter() {
+      self
+        .write_report(&warning.report(&self.filename), io::stderr())
+        .unwrap();
+    }
+  }
+
+  fn write_report<W: io::Write>(&self, report: &ParseErrReport<'_>, out: W) -> io::Result<()> {
+    report.write(
+      (self.filename.as_str(), Source::from(self.source.as_str())),
+      out,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_stops_at_first_failing_phase() {
+    let session = Session::new("lex.sbl", "func i32 f() { return 1 $ 2; }");
+    let failure = session.lower().err().unwrap();
+    assert_eq!(failure.phase(), Phase::Lex);
+
+    let session = Session::new("sema.sbl", "func i32 f() { return g(); }");
+    let failure = session.lower().err().unwrap();
+    assert_eq!(failure.phase(), Phase::Analyze);
+    assert_eq!(failure.diagnostics().len(), 1);
+    assert!(session.render(&failure).contains("function `g` not found"));
+  }
+
+  #[test]
+  fn test_reports_lowering_errors_at_their_source() {
+    let session = Session::new("lower.sbl", "func i32 f() { return 99999999999999999999; }");
+    let failure = session.lower().err().unwrap();
+    assert_eq!(failure.phase(), Phase::Lower);
+    let rendered = session.render(&failure);
+    assert!(rendered.contains("invalid numeric value `99999999999999999999`"));
+    assert!(rendered.contains("lower.sbl:1:"), "{}", rendered);
+  }
+
+  #[test]
+  fn test_warnings_do_not_fail() {
+    let source = "func i32 main() { let i32 x = 1; return 0; }";
+    let session = Session::new("warn.sbl", source);
+    assert!(session.lower().is_ok());
+    assert!(session.render_warnings().contains("unused variable `x`"));
+
+    let mut session = Session::new("warn.sbl", source);
+    let mut lints = LintLevels::new();
+    lints.set_deny_warnings(true);
+    session.set_lints(lints);
+    let failure = session.lower().err().unwrap();
+    assert_eq!(failure.phase(), Phase::Analyze);
+    assert!(session.render(&failure).contains("unused variable `x`"));
+  }
+
+  #[test]
+  fn test_lowers_valid_program() {
+    let session = Session::new("examples/add.sbl", "func i32 one() { return 1; }");
+    let module = session.lower().ok().unwrap();
+    assert_eq!(module.borrow().name(), "add");
+    assert_eq!(module.borrow().get_funcs().len(), 1);
+  }
+}
@@ -0,0 +1,90 @@
+use std::fmt::Display;
+
+use sable_mir::mir::{function::MirFunctionId, instruction::MirInstId};
+use sable_parser::info::ValType;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeErrorKind {
+  NoMain,
+  /// `main` takes parameters or returns something other than `i32` or
+  /// `void`.
+  InvalidMain,
+  DivisionByZero,
+  StackOverflow(usize),
+  /// A load from a slot nothing was stored to. Sema rejects reading a
+  /// variable before it is assigned, so only hand-written MIR does this.
+  UninitializedRead(MirInstId),
+  /// A value the MIR refers to that was never computed, or has the wrong
+  /// type. Verified MIR never triggers these.
+  UndefinedValue(MirInstId),
+  UnknownFunction(MirFunctionId),
+  TypeMismatch(ValType),
+  FellOffBlock,
+  Unreachable,
+}
+
+impl Display for RuntimeErrorKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RuntimeErrorKind::NoMain => write!(f, "no `main` function"),
+      RuntimeErrorKind::InvalidMain => {
+        write!(
+          f,
+          "`main` must take no parameters and return `i32` or `void`"
+        )
+      }
+      RuntimeErrorKind::DivisionByZero => write!(f, "integer division by zero"),
+      RuntimeErrorKind::StackOverflow(depth) => {
+        write!(f, "stack overflow, more than {} nested calls", depth)
+      }
+      RuntimeErrorKind::UninitializedRead(slot) => {
+        write!(f, "read of uninitialized slot {}", slot)
+      }
+      RuntimeErrorKind::UndefinedValue(id) => write!(f, "use of undefined value {}", id),
+      RuntimeErrorKind::UnknownFunction(id) => write!(f, "call to unknown function @{}", id.0),
+      RuntimeErrorKind::TypeMismatch(type_) => write!(f, "operand is not of type `{}`", type_),
+      RuntimeErrorKind::FellOffBlock => write!(f, "control fell off the end of a block"),
+      RuntimeErrorKind::Unreachable => write!(f, "reached `unreachable`"),
+    }
+  }
+}
+
+/// A fault that stopped the program, in the function and at the
+/// instruction executing when it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeError {
+  kind: RuntimeErrorKind,
+  func: Option<String>,
+  inst: Option<MirInstId>,
+}
+
+impl RuntimeError {
+  pub fn new(kind: RuntimeErrorKind, func: Option<String>, inst: Option<MirInstId>) -> Self {
+    Self { kind, func, inst }
+  }
+
+  pub fn kind(&self) -> &RuntimeErrorKind {
+    &self.kind
+  }
+
+  pub fn func(&self) -> Option<&str> {
+    self.func.as_deref()
+  }
+
+  pub fn inst(&self) -> Option<MirInstId> {
+    self.inst
+  }
+}
+
+impl Display for RuntimeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.kind)?;
+    if let Some(func) = &self.func {
+      write!(f, " in function `{}`", func)?;
+    }
+    if let Some(inst) = self.inst {
+      write!(f, " at {}", inst)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,514 @@
+//! Executes a `MirModule` directly.
+//!
+//! Calls do not recurse on the host stack: every call pushes a frame on an
+//! explicit call stack, so deep recursion in the program is reported as a
+//! stack overflow once it exceeds the configured depth. `i32` arithmetic
+//! wraps on overflow, `f32` arithmetic follows IEEE 754.
+
+use std::collections::HashMap;
+
+use sable_mir::mir::{
+  function::{MirFunction, MirFunctionId, block::MirBlockId},
+  instruction::{CmpPredicate, Instruction, MirInstId},
+  module::MirModule,
+  place::Place,
+  value::MirValue,
+};
+use sable_parser::info::ValType;
+
+use crate::{
+  error::{RuntimeError, RuntimeErrorKind},
+  value::Value,
+};
+
+/// The number of nested calls allowed before reporting a stack overflow.
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+type Result<T> = std::result::Result<T, RuntimeErrorKind>;
+
+/// The activation of one function.
+struct Frame<'a, 'ctx> {
+  func: &'a MirFunction<'ctx>,
+  /// The block executing, its instructions and the position of the next
+  /// one to execute.
+  block: Option<MirBlockId>,
+  insts: &'a [MirInstId],
+  pc: usize,
+  params: Vec<Value>,
+  values: Vec<Option<Value>>,
+  /// Contents of the stack slots, by the `alloca` creating them.
+  slots: HashMap<MirInstId, Option<Value>>,
+}
+
+impl<'a, 'ctx> Frame<'a, 'ctx> {
+  fn new(func: &'a MirFunction<'ctx>, params: Vec<Value>) -> Self {
+    let mut frame = Self {
+      func,
+      block: None,
+      insts: &[],
+      pc: 0,
+      params,
+      values: vec![None; func.get_inst_count()],
+      slots: HashMap::new(),
+    };
+    // Verified functions always have an entry block; a missing one is
+    // reported as falling off it.
+    let _ = frame.jump(MirBlockId(0));
+    frame
+  }
+
+  /// Enters `target`, giving its phis the values for the block control
+  /// comes from all at once.
+  fn jump(&mut self, target: MirBlockId) -> Result<()> {
+    let Some(blk) = self.func.get_block(target) else {
+      return Err(RuntimeErrorKind::FellOffBlock);
+    };
+    self.insts = blk.insts();
+    self.pc = 0;
+
+    let mut incoming = Vec::new();
+    for (id, inst) in self.func.get_insts(target) {
+      let Instruction::Phi(phi) = inst else {
+        break;
+      };
+      let value = self
+        .block
+        .and_then(|pred| phi.get_incoming(pred))
+        .ok_or(RuntimeErrorKind::UndefinedValue(id))?;
+      incoming.push(self.value(value)?);
+    }
+    for value in incoming {
+      self.values[self.insts[self.pc].0] = Some(value);
+      self.pc += 1;
+    }
+    self.block = Some(target);
+    Ok(())
+  }
+
+  fn value(&self, value: &MirValue) -> Result<Value> {
+    match value {
+      MirValue::Constant(constant) => Ok(Value::from_constant(constant)),
+      MirValue::Inst(id) => self
+        .values
+        .get(id.0)
+        .copied()
+        .flatten()
+        .ok_or(RuntimeErrorKind::UndefinedValue(*id)),
+    }
+  }
+
+  fn bool(&self, value: &MirValue) -> Result<bool> {
+    match self.value(value)? {
+      Value::Bool(value) => Ok(value),
+      _ => Err(RuntimeErrorKind::TypeMismatch(ValType::Bool)),
+    }
+  }
+}
+
+/// What the interpreter does after an instruction.
+enum Step {
+  Next,
+  Call(MirFunctionId, Vec<Value>),
+  Return(Value),
+}
+
+pub struct Interpreter<'a, 'ctx> {
+  module: &'a MirModule<'ctx>,
+  max_depth: usize,
+}
+
+impl<'a, 'ctx> Interpreter<'a, 'ctx> {
+  pub fn new(module: &'a MirModule<'ctx>) -> Self {
+    Self {
+      module,
+      max_depth: DEFAULT_MAX_DEPTH,
+    }
+  }
+
+  pub fn set_max_depth(&mut self, max_depth: usize) {
+    self.max_depth = max_depth;
+  }
+
+  /// Runs the `main` function, which must take no parameters and return
+  /// `i32` or `void`.
+  pub fn run_main(&self) -> std::result::Result<Value, RuntimeError> {
+    let Some(idx) = self
+      .module
+      .get_funcs()
+      .iter()
+      .position(|func| func.name() == "main")
+    else {
+      return Err(RuntimeError::new(RuntimeErrorKind::NoMain, None, None));
+    };
+
+    let main = &self.module.get_funcs()[idx];
+    if !main.get_params().is_empty() || !matches!(main.ret_type(), ValType::I32 | ValType::Void) {
+      return Err(RuntimeError::new(
+        RuntimeErrorKind::InvalidMain,
+        Some(main.name().to_string()),
+        None,
+      ));
+    }
+    self.call(MirFunctionId(idx), Vec::new())
+  }
+
+  /// Calls the function `callee` with `args` and runs it to completion.
+  pub fn call(
+    &self,
+    callee: MirFunctionId,
+    args: Vec<Value>,
+  ) -> std::result::Result<Value, RuntimeError> {
+    let Some(func) = self.module.get_func(callee) else {
+      return Err(RuntimeError::new(
+        RuntimeErrorKind::UnknownFunction(callee),
+        None,
+        None,
+      ));
+    };
+
+    let mut stack = vec![Frame::new(func, args)];
+    loop {
+      let depth = stack.len();
+      let frame = stack.last_mut().unwrap();
+      let Some(&id) = frame.insts.get(frame.pc) else {
+        return Err(RuntimeError::new(
+          RuntimeErrorKind::FellOffBlock,
+          Some(frame.func.name().to_string()),
+          frame.insts.last().copied(),
+        ));
+      };
+      frame.pc += 1;
+      let inst = frame.func.get_inst(id).unwrap();
+      let step = self
+        .step(frame, id, inst)
+        .map_err(|kind| RuntimeError::new(kind, Some(frame.func.name().to_string()), Some(id)))?;
+
+      match step {
+        Step::Next => {}
+        Step::Call(callee, args) => {
+          let Some(func) = self.module.get_func(callee) else {
+            return Err(RuntimeError::new(
+              RuntimeErrorKind::UnknownFunction(callee),
+              Some(frame.func.name().to_string()),
+              Some(id),
+            ));
+          };
+          if depth >= self.max_depth {
+            return Err(RuntimeError::new(
+              RuntimeErrorKind::StackOverflow(self.max_depth),
+              Some(frame.func.name().to_string()),
+              Some(id),
+            ));
+          }
+          stack.push(Frame::new(func, args));
+        }
+        Step::Return(value) => {
+          stack.pop();
+          let Some(caller) = stack.last_mut() else {
+            return Ok(value);
+          };
+          // The caller stopped right after its call instruction.
+          caller.values[caller.insts[caller.pc - 1].0] = Some(value);
+        }
+      }
+    }
+  }
+
+  fn step(&self, frame: &mut Frame<'a, 'ctx>, id: MirInstId, inst: &Instruction) -> Result<Step> {
+    let value = match inst {
+      Instruction::Alloca(_) => {
+        frame.slots.insert(id, None);
+        return Ok(Step::Next);
+      }
+      Instruction::Store(store) => {
+        let value = frame.value(store.value())?;
+        match frame.slots.get_mut(&store.target()) {
+          Some(slot) => *slot = Some(value),
+          None => return Err(RuntimeErrorKind::UndefinedValue(store.target())),
+        }
+        return Ok(Step::Next);
+      }
+      Instruction::Load(load) => match load.from() {
+        Place::Param(idx) => *frame
+          .params
+          .get(*idx)
+          .ok_or(RuntimeErrorKind::TypeMismatch(load.by()))?,
+        Place::Local(slot) => match frame.slots.get(slot) {
+          Some(Some(value)) => *value,
+          Some(None) => return Err(RuntimeErrorKind::UninitializedRead(*slot)),
+          None => return Err(RuntimeErrorKind::UndefinedValue(*slot)),
+        },
+      },
+      Instruction::Add(add) => arith(
+        add.type_(),
+        frame.value(add.lhs())?,
+        frame.value(add.rhs())?,
+        i32::wrapping_add,
+        |a, b| a + b,
+      )?,
+      Instruction::Sub(sub) => arith(
+        sub.type_(),
+        frame.value(sub.lhs())?,
+        frame.value(sub.rhs())?,
+        i32::wrapping_sub,
+        |a, b| a - b,
+      )?,
+      Instruction::Mul(mul) => arith(
+        mul.type_(),
+        frame.value(mul.lhs())?,
+        frame.value(mul.rhs())?,
+        i32::wrapping_mul,
+        |a, b| a * b,
+      )?,
+      Instruction::Div(div) => {
+        let rhs = frame.value(div.rhs())?;
+        if rhs == Value::I32(0) {
+          return Err(RuntimeErrorKind::DivisionByZero);
+        }
+        arith(
+          div.type_(),
+          frame.value(div.lhs())?,
+          rhs,
+          i32::wrapping_div,
+          |a, b| a / b,
+        )?
+      }
+      Instruction::Cmp(cmp) => {
+        let lhs = frame.value(cmp.lhs())?;
+        let rhs = frame.value(cmp.rhs())?;
+        if lhs.type_() != cmp.type_() || rhs.type_() != cmp.type_() {
+          return Err(RuntimeErrorKind::TypeMismatch(cmp.type_()));
+        }
+        Value::Bool(compare(cmp.predicate(), lhs, rhs))
+      }
+      Instruction::And(and) => Value::Bool(frame.bool(and.lhs())? && frame.bool(and.rhs())?),
+      Instruction::Or(or) => Value::Bool(frame.bool(or.lhs())? || frame.bool(or.rhs())?),
+      Instruction::Not(not) => Value::Bool(!frame.bool(not.value())?),
+      Instruction::Return(ret) => return Ok(Step::Return(frame.value(ret.ret_value())?)),
+      Instruction::Call(call) => {
+        let args = call
+          .args()
+          .iter()
+          .map(|arg| frame.value(arg))
+          .collect::<Result<Vec<_>>>()?;
+        return Ok(Step::Call(call.callee(), args));
+      }
+      Instruction::Branch(branch) => {
+        let target = if frame.bool(branch.cond())? {
+          branch.then_blk()
+        } else {
+          branch.else_blk()
+        };
+        frame.jump(target)?;
+        return Ok(Step::Next);
+      }
+      Instruction::Jump(jump) => {
+        frame.jump(jump.target())?;
+        return Ok(Step::Next);
+      }
+      // Phis are evaluated when their block is entered.
+      Instruction::Phi(_) => return Err(RuntimeErrorKind::UndefinedValue(id)),
+      Instruction::Unreachable => return Err(RuntimeErrorKind::Unreachable),
+    };
+
+    frame.values[id.0] = Some(value);
+    Ok(Step::Next)
+  }
+}
+
+fn arith(
+  type_: ValType,
+  lhs: Value,
+  rhs: Value,
+  int: fn(i32, i32) -> i32,
+  float: fn(f32, f32) -> f32,
+) -> Result<Value> {
+  match (&type_, lhs, rhs) {
+    (ValType::I32, Value::I32(lhs), Value::I32(rhs)) => Ok(Value::I32(int(lhs, rhs))),
+    (ValType::F32, Value::F32(lhs), Value::F32(rhs)) => Ok(Value::F32(float(lhs, rhs))),
+    _ => Err(RuntimeErrorKind::TypeMismatch(type_)),
+  }
+}
+
+fn compare(predicate: CmpPredicate, lhs: Value, rhs: Value) -> bool {
+  let ordering = match (lhs, rhs) {
+    (Value::I32(lhs), Value::I32(rhs)) => lhs.partial_cmp(&rhs),
+    (Value::F32(lhs), Value::F32(rhs)) => lhs.partial_cmp(&rhs),
+    (Value::Bool(lhs), Value::Bool(rhs)) => lhs.partial_cmp(&rhs),
+    _ => None,
+  };
+  // Comparisons involving NaN are false, except `ne`.
+  let Some(ordering) = ordering else {
+    return predicate == CmpPredicate::Ne;
+  };
+  match predicate {
+    CmpPredicate::Eq => ordering.is_eq(),
+    CmpPredicate::Ne => ordering.is_ne(),
+    CmpPredicate::Lt => ordering.is_lt(),
+    CmpPredicate::Le => ordering.is_le(),
+    CmpPredicate::Gt => ordering.is_gt(),
+    CmpPredicate::Ge => ordering.is_ge(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_driver::session::Session;
+  use sable_mir::{mir::parser::parse_module, transform::mem2reg::mem2reg};
+
+  use super::*;
+
+  fn run(source: &str) -> std::result::Result<Value, RuntimeError> {
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    let module = module.borrow();
+    Interpreter::new(&module).run_main()
+  }
+
+  fn run_promoted(source: &str) -> std::result::Result<Value, RuntimeError> {
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    let mut module = module.borrow_mut();
+    mem2reg(&mut module);
+    Interpreter::new(&module).run_main()
+  }
+
+  #[test]
+  fn test_runs_programs() {
+    let source = "
+      func i32 fact(i32 n) { if n <= 1 { return 1; } return n * fact(n - 1); }
+      func i32 sum(i32 n) {
+        let i32 acc = 0;
+        let i32 i = 0;
+        while i < n { i = i + 1; if i == 3 { continue; } acc = acc + i; }
+        return acc;
+      }
+      func bool half(f32 x) { return x / 2.0 > 1.0 && !false; }
+      func i32 main() {
+        if half(3.0) { return fact(5) + sum(4); }
+        return 0;
+      }
+    ";
+    assert_eq!(run(source), Ok(Value::I32(127)));
+    assert_eq!(run_promoted(source), Ok(Value::I32(127)));
+    assert_eq!(run("func void main() { return; }"), Ok(Value::Void));
+    assert_eq!(
+      run("func i32 main() { return 2147483647 + 1; }"),
+      Ok(Value::I32(i32::MIN))
+    );
+  }
+
+  #[test]
+  fn test_logical_operators_short_circuit() {
+    // `side(0)` divides by zero, so evaluating it would trap.
+    let source = "
+      func i32 side(i32 d) { return 1 / d; }
+      func i32 main() {
+        let i32 n = 0;
+        if false && side(0) == 1 { n = n + 1; }
+        if true || side(0) == 1 { n = n + 10; }
+        if n > 5 && side(1) == 1 { n = n + 100; }
+        return n;
+      }
+    ";
+    assert_eq!(run(source), Ok(Value::I32(110)));
+    assert_eq!(run_promoted(source), Ok(Value::I32(110)));
+  }
+
+  #[test]
+  fn test_assignments_evaluate_to_their_value() {
+    let sources = [
+      ("func i32 main() { let i32 x = 1; return x = 5; }", 5),
+      ("func i32 main() { let i32 x = 1; let i32 y = x = 7; return x + y; }", 14),
+      (
+        "func i32 main() { let i32 x = 1; if (x = 2) == 2 { return x * 10; } return 0; }",
+        20,
+      ),
+      (
+        "func i32 id(i32 a) { return a; } func i32 main() { let i32 x = 1; return id(x = 5) + x; }",
+        10,
+      ),
+      ("func i32 f(i32 n) { return (n = n + 1) * n; } func i32 main() { return f(3); }", 16),
+    ];
+    for (source, expected) in sources {
+      assert_eq!(run(source), Ok(Value::I32(expected)), "{}", source);
+      assert_eq!(run_promoted(source), Ok(Value::I32(expected)), "{}", source);
+    }
+  }
+
+  #[test]
+  fn test_runtime_errors() {
+    let err = run("func i32 main() { let i32 z = 0; return 7 / z; }").unwrap_err();
+    assert_eq!(err.kind(), &RuntimeErrorKind::DivisionByZero);
+    assert_eq!(err.func(), Some("main"));
+    assert_eq!(err.inst(), Some(MirInstId(3)));
+
+    let source = "func i32 main() { return f(0); } func i32 f(i32 n) { return f(n + 1); }";
+    let session = Session::new("test.sbl", source);
+    let module = session.lower().ok().unwrap();
+    let module = module.borrow();
+    let mut interp = Interpreter::new(&module);
+    interp.set_max_depth(50);
+    let err = interp.run_main().unwrap_err();
+    assert_eq!(err.kind(), &RuntimeErrorKind::StackOverflow(50));
+    assert_eq!(err.func(), Some("f"));
+
+    let err = run("func i32 start() { return 0; }").unwrap_err();
+    assert_eq!(err.kind(), &RuntimeErrorKind::NoMain);
+    let err = run("func i32 main(i32 argc) { return argc; }").unwrap_err();
+    assert_eq!(err.kind(), &RuntimeErrorKind::InvalidMain);
+
+    // A read before the variable is assigned never gets to run, whatever
+    // the optimization level.
+    let source = "func i32 main() { let i32 x; if false { x = 1; } return x; }";
+    assert!(Session::new("test.sbl", source).lower().is_err());
+  }
+
+  #[test]
+  fn test_phis_take_values_in_parallel() {
+    // Swaps `a` and `b` three times around the loop.
+    let module = parse_module(
+      "
+module m
+func main() -> i32 {
+entry.0:
+  jmp loop.1
+loop.1:
+  %a = phi i32 [1, entry.0], [%b, loop.1]
+  %b = phi i32 [2, entry.0], [%a, loop.1]
+  %n = phi i32 [0, entry.0], [%m, loop.1]
+  %m = add i32 %n, 1
+  %c = cmp lt i32 %m, 4
+  br %c, loop.1, exit.2
+exit.2:
+  %r = mul i32 %a, 10
+  %s = add i32 %r, %b
+  ret i32 %s
+}
+",
+    )
+    .unwrap();
+    assert_eq!(Interpreter::new(&module).run_main(), Ok(Value::I32(21)));
+  }
+
+  #[test]
+  fn test_uninitialized_read() {
+    let module = parse_module(
+      "
+module m
+func main() -> i32 {
+entry.0:
+  %x = alloca i32
+  %v = load i32 %x
+  ret i32 %v
+}
+",
+    )
+    .unwrap();
+    let err = Interpreter::new(&module).run_main().unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "read of uninitialized slot %0 in function `main` at %1"
+    );
+  }
+}
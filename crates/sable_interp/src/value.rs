@@ -0,0 +1,44 @@
+use std::fmt::Display;
+
+use sable_mir::mir::value::Constant;
+use sable_parser::info::ValType;
+
+/// A value computed at runtime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+  I32(i32),
+  F32(f32),
+  Bool(bool),
+  Void,
+}
+
+impl Value {
+  pub fn from_constant(constant: &Constant) -> Self {
+    match constant {
+      Constant::Null => Value::Void,
+      Constant::IntValue(_, value) => Value::I32(*value as i32),
+      Constant::FloatValue(_, value) => Value::F32(*value as f32),
+      Constant::BoolValue(value) => Value::Bool(*value),
+    }
+  }
+
+  pub fn type_(&self) -> ValType {
+    match self {
+      Value::I32(_) => ValType::I32,
+      Value::F32(_) => ValType::F32,
+      Value::Bool(_) => ValType::Bool,
+      Value::Void => ValType::Void,
+    }
+  }
+}
+
+impl Display for Value {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Value::I32(value) => write!(f, "{}", value),
+      Value::F32(value) => write!(f, "{:?}", value),
+      Value::Bool(value) => write!(f, "{}", value),
+      Value::Void => write!(f, "void"),
+    }
+  }
+}
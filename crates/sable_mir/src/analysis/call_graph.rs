@@ -0,0 +1,130 @@
+//! Which functions of a module call which.
+
+use crate::mir::{
+  function::MirFunctionId,
+  instruction::{Instruction, MirInstId},
+  module::MirModule,
+};
+
+pub struct CallGraph {
+  /// The functions each function calls, without duplicates, in the order
+  /// of their first call.
+  callees: Vec<Vec<MirFunctionId>>,
+}
+
+impl CallGraph {
+  pub fn new(module: &MirModule) -> Self {
+    let callees = module
+      .get_funcs()
+      .iter()
+      .map(|func| {
+        let mut callees = Vec::new();
+        for id in 0..func.get_inst_count() {
+          if let Some(Instruction::Call(call)) = func.get_inst(MirInstId(id))
+            && !callees.contains(&call.callee())
+          {
+            callees.push(call.callee());
+          }
+        }
+        callees
+      })
+      .collect();
+    CallGraph { callees }
+  }
+
+  pub fn callees(&self, func: MirFunctionId) -> &[MirFunctionId] {
+    &self.callees[func.0]
+  }
+
+  /// Whether `from` can end up calling `to`, or is `to`.
+  pub fn reaches(&self, from: MirFunctionId, to: MirFunctionId) -> bool {
+    let mut visited = vec![false; self.callees.len()];
+    let mut worklist = vec![from];
+    while let Some(func) = worklist.pop() {
+      if func == to {
+        return true;
+      }
+      if !std::mem::replace(&mut visited[func.0], true) {
+        worklist.extend(self.callees(func));
+      }
+    }
+    false
+  }
+
+  /// Every function, each after the functions it calls unless they call
+  /// it back.
+  pub fn postorder(&self) -> Vec<MirFunctionId> {
+    let mut visited = vec![false; self.callees.len()];
+    let mut postorder = Vec::new();
+    for root in 0..self.callees.len() {
+      if visited[root] {
+        continue;
+      }
+      visited[root] = true;
+      // Functions paired with how many of their callees were visited.
+      let mut stack = vec![(MirFunctionId(root), 0)];
+      while let Some((func, next)) = stack.last_mut() {
+        match self.callees(*func).get(*next) {
+          Some(&callee) => {
+            *next += 1;
+            if !visited[callee.0] {
+              visited[callee.0] = true;
+              stack.push((callee, 0));
+            }
+          }
+          None => {
+            postorder.push(*func);
+            stack.pop();
+          }
+        }
+      }
+    }
+    postorder
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mir::parser::parse_module;
+
+  #[test]
+  fn test_cycles_and_order() {
+    let module = parse_module(
+      "
+      module test
+      func main() -> i32 {
+      entry.0:
+        %0 = call i32 @even(4)
+        %1 = call i32 @leaf()
+        ret i32 %0
+      }
+      func even(i32 %arg0) -> i32 {
+      entry.0:
+        %0 = load i32 %arg0
+        %1 = call i32 @odd(%0)
+        ret i32 %1
+      }
+      func odd(i32 %arg0) -> i32 {
+      entry.0:
+        %0 = load i32 %arg0
+        %1 = call i32 @even(%0)
+        %2 = call i32 @leaf()
+        ret i32 %1
+      }
+      func leaf() -> i32 {
+      entry.0:
+        ret i32 0
+      }
+      ",
+    )
+    .unwrap();
+    let graph = CallGraph::new(&module);
+    let [main, even, odd, leaf] = [0, 1, 2, 3].map(MirFunctionId);
+    assert_eq!(graph.callees(main), [even, leaf]);
+    assert!(graph.reaches(even, odd) && graph.reaches(odd, even));
+    assert!(graph.reaches(leaf, leaf));
+    assert!(!graph.reaches(even, main) && !graph.reaches(leaf, odd));
+    assert_eq!(graph.postorder(), [leaf, odd, even, main]);
+  }
+}
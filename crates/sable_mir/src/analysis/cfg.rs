@@ -0,0 +1,122 @@
+//! The control-flow graph formed by the blocks of a function and their
+//! terminators.
+
+use crate::{
+  analysis::manager::{Analysis, FunctionAnalyses},
+  mir::{
+    function::{MirFunction, block::MirBlockId},
+    instruction::{Instruction, MirInstId},
+    value::MirValue,
+  },
+};
+
+/// The blocks the terminator of `blk` transfers control to, without
+/// duplicates.
+pub fn successors(func: &MirFunction, blk: MirBlockId) -> Vec<MirBlockId> {
+  let mut succs = func
+    .get_terminator(blk)
+    .map(|term| term.successors())
+    .unwrap_or_default();
+  succs.dedup();
+  succs
+}
+
+/// The phis of the successors of `blk` with the value each takes when
+/// control comes from `blk`. Backends copy these values into the phis
+/// before the terminator of `blk` runs.
+pub fn outgoing_phi_values<'f>(
+  func: &'f MirFunction,
+  blk: MirBlockId,
+) -> Vec<(MirInstId, &'f MirValue)> {
+  let mut values = Vec::new();
+  for succ in successors(func, blk) {
+    if func.get_block(succ).is_none() {
+      continue;
+    }
+    for (id, inst) in func.get_insts(succ) {
+      let Instruction::Phi(phi) = inst else {
+        break;
+      };
+      if let Some(value) = phi.get_incoming(blk) {
+        values.push((id, value));
+      }
+    }
+  }
+  values
+}
+
+/// The predecessors of every block, each list ordered by block id.
+pub fn predecessors(func: &MirFunction) -> Vec<Vec<MirBlockId>> {
+  let mut preds = vec![Vec::new(); func.get_blocks().len()];
+  for blk in 0..func.get_blocks().len() {
+    for succ in successors(func, MirBlockId(blk)) {
+      if let Some(list) = preds.get_mut(succ.0) {
+        list.push(MirBlockId(blk));
+      }
+    }
+  }
+  preds
+}
+
+/// The blocks reachable from the entry block, in reverse postorder.
+pub fn reverse_postorder(func: &MirFunction) -> Vec<MirBlockId> {
+  let blocks = func.get_blocks().len();
+  if blocks == 0 {
+    return Vec::new();
+  }
+
+  let mut visited = vec![false; blocks];
+  let mut postorder = Vec::new();
+  // Blocks paired with the successors left to visit.
+  let mut stack = vec![(MirBlockId(0), successors(func, MirBlockId(0)))];
+  visited[0] = true;
+  while let Some((blk, succs)) = stack.last_mut() {
+    match succs.pop() {
+      Some(succ) if succ.0 < blocks && !visited[succ.0] => {
+        visited[succ.0] = true;
+        let succs = successors(func, succ);
+        stack.push((succ, succs));
+      }
+      Some(_) => {}
+      None => {
+        postorder.push(*blk);
+        stack.pop();
+      }
+    }
+  }
+  postorder.reverse();
+  postorder
+}
+
+/// The predecessors and successors of every block, computed once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+  preds: Vec<Vec<MirBlockId>>,
+  succs: Vec<Vec<MirBlockId>>,
+}
+
+impl Cfg {
+  pub fn new(func: &MirFunction) -> Self {
+    let succs = (0..func.get_blocks().len())
+      .map(|blk| successors(func, MirBlockId(blk)))
+      .collect();
+    Cfg {
+      preds: predecessors(func),
+      succs,
+    }
+  }
+
+  pub fn predecessors(&self, blk: MirBlockId) -> &[MirBlockId] {
+    &self.preds[blk.0]
+  }
+
+  pub fn successors(&self, blk: MirBlockId) -> &[MirBlockId] {
+    &self.succs[blk.0]
+  }
+}
+
+impl Analysis for Cfg {
+  fn compute(func: &MirFunction, _: &mut FunctionAnalyses) -> Self {
+    Cfg::new(func)
+  }
+}
@@ -0,0 +1,209 @@
+//! Dominator trees and dominance frontiers, computed with the iterative
+//! algorithm of Cooper, Harvey and Kennedy ("A Simple, Fast Dominance
+//! Algorithm").
+
+use crate::{
+  analysis::{
+    cfg::{predecessors, reverse_postorder},
+    manager::{Analysis, FunctionAnalyses},
+  },
+  mir::function::{MirFunction, block::MirBlockId},
+};
+
+/// The dominator tree of the blocks reachable from the entry block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DominatorTree {
+  idom: Vec<Option<MirBlockId>>,
+  children: Vec<Vec<MirBlockId>>,
+  /// The position of each reachable block in reverse postorder.
+  rpo_index: Vec<Option<usize>>,
+  frontiers: Vec<Vec<MirBlockId>>,
+}
+
+impl DominatorTree {
+  pub fn new(func: &MirFunction) -> Self {
+    let blocks = func.get_blocks().len();
+    let rpo = reverse_postorder(func);
+    let mut rpo_index = vec![None; blocks];
+    for (idx, blk) in rpo.iter().enumerate() {
+      rpo_index[blk.0] = Some(idx);
+    }
+    let preds = predecessors(func);
+
+    let mut idom = vec![None; blocks];
+    if let Some(entry) = rpo.first() {
+      idom[entry.0] = Some(*entry);
+    }
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for &blk in rpo.iter().skip(1) {
+        let mut new_idom = None;
+        for &pred in &preds[blk.0] {
+          if idom[pred.0].is_none() {
+            continue;
+          }
+          new_idom = Some(match new_idom {
+            None => pred,
+            Some(other) => intersect(&idom, &rpo_index, pred, other),
+          });
+        }
+        if new_idom.is_some() && idom[blk.0] != new_idom {
+          idom[blk.0] = new_idom;
+          changed = true;
+        }
+      }
+    }
+    // The entry block has no dominator other than itself.
+    if let Some(entry) = rpo.first() {
+      idom[entry.0] = None;
+    }
+
+    let mut children = vec![Vec::new(); blocks];
+    for &blk in &rpo {
+      if let Some(parent) = idom[blk.0] {
+        children[parent.0].push(blk);
+      }
+    }
+
+    // A join point is in the frontier of every block on the way from each
+    // of its predecessors up to its immediate dominator.
+    let mut frontiers = vec![Vec::new(); blocks];
+    for &blk in &rpo {
+      let preds = &preds[blk.0];
+      if preds.len() < 2 {
+        continue;
+      }
+      for &pred in preds {
+        let mut runner = Some(pred);
+        while let Some(node) = runner {
+          if rpo_index[node.0].is_none() || Some(node) == idom[blk.0] {
+            break;
+          }
+          if !frontiers[node.0].contains(&blk) {
+            frontiers[node.0].push(blk);
+          }
+          runner = idom[node.0];
+        }
+      }
+    }
+
+    Self {
+      idom,
+      children,
+      rpo_index,
+      frontiers,
+    }
+  }
+
+  /// The immediate dominator of `blk`, `None` for the entry block and
+  /// unreachable blocks.
+  pub fn idom(&self, blk: MirBlockId) -> Option<MirBlockId> {
+    self.idom.get(blk.0).copied().flatten()
+  }
+
+  /// The blocks `blk` immediately dominates.
+  pub fn children(&self, blk: MirBlockId) -> &[MirBlockId] {
+    &self.children[blk.0]
+  }
+
+  pub fn is_reachable(&self, blk: MirBlockId) -> bool {
+    self.rpo_index.get(blk.0).is_some_and(Option::is_some)
+  }
+
+  /// Whether every path from the entry block to `b` goes through `a`.
+  pub fn dominates(&self, a: MirBlockId, b: MirBlockId) -> bool {
+    if !self.is_reachable(b) {
+      return false;
+    }
+    let mut node = Some(b);
+    while let Some(blk) = node {
+      if blk == a {
+        return true;
+      }
+      node = self.idom(blk);
+    }
+    false
+  }
+
+  /// The blocks where the dominance of `blk` ends.
+  pub fn frontier(&self, blk: MirBlockId) -> &[MirBlockId] {
+    &self.frontiers[blk.0]
+  }
+}
+
+impl Analysis for DominatorTree {
+  fn compute(func: &MirFunction, _: &mut FunctionAnalyses) -> Self {
+    DominatorTree::new(func)
+  }
+}
+
+fn intersect(
+  idom: &[Option<MirBlockId>],
+  rpo_index: &[Option<usize>],
+  mut a: MirBlockId,
+  mut b: MirBlockId,
+) -> MirBlockId {
+  let index = |blk: MirBlockId| rpo_index[blk.0].unwrap();
+  while a != b {
+    while index(a) > index(b) {
+      a = idom[a.0].unwrap();
+    }
+    while index(b) > index(a) {
+      b = idom[b.0].unwrap();
+    }
+  }
+  a
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mir::parser::parse_module;
+
+  #[test]
+  fn test_loop_with_branch() {
+    // entry -> header <-> body, body branches to then/merge, header -> exit.
+    let module = parse_module(
+      "
+module m
+func f(bool %arg0) -> void {
+entry.0:
+  jmp header.1
+header.1:
+  %c = load bool %arg0
+  br %c, body.2, exit.3
+body.2:
+  br %c, then.4, merge.5
+then.4:
+  jmp merge.5
+merge.5:
+  jmp header.1
+exit.3:
+  ret void
+dead.6:
+  jmp merge.5
+}
+",
+    )
+    .unwrap();
+    let tree = DominatorTree::new(&module.get_funcs()[0]);
+    let idoms = (0..7)
+      .map(|blk| tree.idom(MirBlockId(blk)).map(|idom| idom.0))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      idoms,
+      [None, Some(0), Some(1), Some(1), Some(2), Some(2), None]
+    );
+    assert!(tree.dominates(MirBlockId(1), MirBlockId(5)));
+    assert!(!tree.dominates(MirBlockId(4), MirBlockId(5)));
+    assert!(!tree.is_reachable(MirBlockId(6)));
+
+    let frontier = |blk| tree.frontier(MirBlockId(blk)).to_vec();
+    assert_eq!(frontier(4), [MirBlockId(5)]);
+    assert_eq!(frontier(5), [MirBlockId(1)]);
+    assert_eq!(frontier(2), [MirBlockId(1)]);
+    assert_eq!(frontier(1), [MirBlockId(1)]);
+    assert_eq!(frontier(0), []);
+  }
+}
@@ -0,0 +1,190 @@
+//! Which values are live at each point of a function.
+//!
+//! A value is live from its definition to its last use. The values a phi
+//! takes are used at the end of the predecessor they come from rather than
+//! in the phi's block, and the phis of a block are defined on entry to it,
+//! so they are live-out of their predecessors but not live-in.
+
+use std::collections::HashSet;
+
+use crate::{
+  analysis::{
+    cfg::{Cfg, outgoing_phi_values},
+    manager::{Analysis, FunctionAnalyses},
+  },
+  mir::{
+    function::{MirFunction, block::MirBlockId},
+    instruction::{Instruction, MirInstId},
+    value::MirValue,
+  },
+};
+
+/// Live values, each set ordered by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liveness {
+  live_in: Vec<Vec<MirInstId>>,
+  live_out: Vec<Vec<MirInstId>>,
+  /// The values live right after each instruction.
+  live_after: Vec<Vec<MirInstId>>,
+}
+
+impl Liveness {
+  pub fn new(func: &MirFunction, cfg: &Cfg) -> Self {
+    let blocks = func.get_blocks().len();
+    let mut uses = vec![HashSet::new(); blocks];
+    let mut defs = vec![HashSet::new(); blocks];
+    let mut phi_uses = vec![HashSet::new(); blocks];
+    for blk in 0..blocks {
+      for (id, inst) in func.get_insts(MirBlockId(blk)) {
+        for used in used_values(inst) {
+          if !defs[blk].contains(&used) {
+            uses[blk].insert(used);
+          }
+        }
+        defs[blk].insert(id);
+      }
+      for (_, value) in outgoing_phi_values(func, MirBlockId(blk)) {
+        if let MirValue::Inst(id) = value {
+          phi_uses[blk].insert(*id);
+        }
+      }
+    }
+
+    let mut live_in = uses;
+    let mut live_out = phi_uses;
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for blk in (0..blocks).rev() {
+        for succ in cfg.successors(MirBlockId(blk)) {
+          let values = live_in[succ.0].clone();
+          live_out[blk].extend(values);
+        }
+        for &value in &live_out[blk] {
+          if !defs[blk].contains(&value) && live_in[blk].insert(value) {
+            changed = true;
+          }
+        }
+      }
+    }
+
+    let mut live_after = vec![Vec::new(); func.get_inst_count()];
+    for (blk, out) in live_out.iter().enumerate() {
+      let mut live = out.clone();
+      for (id, inst) in func.get_insts(MirBlockId(blk)).rev() {
+        live_after[id.0] = sorted(&live);
+        live.remove(&id);
+        live.extend(used_values(inst));
+      }
+    }
+
+    Liveness {
+      live_in: live_in.iter().map(sorted).collect(),
+      live_out: live_out.iter().map(sorted).collect(),
+      live_after,
+    }
+  }
+
+  /// The values live on entry to `blk`, not counting its phis.
+  pub fn live_in(&self, blk: MirBlockId) -> &[MirInstId] {
+    &self.live_in[blk.0]
+  }
+
+  /// The values live when `blk` is left, the values its successors' phis
+  /// take from it included.
+  pub fn live_out(&self, blk: MirBlockId) -> &[MirInstId] {
+    &self.live_out[blk.0]
+  }
+
+  /// The values live right after the instruction `id`.
+  pub fn live_after(&self, id: MirInstId) -> &[MirInstId] {
+    &self.live_after[id.0]
+  }
+
+  /// Whether `value` is still needed after the instruction `id`.
+  pub fn is_live_after(&self, value: MirInstId, id: MirInstId) -> bool {
+    self
+      .live_after(id)
+      .binary_search_by_key(&value.0, |live| live.0)
+      .is_ok()
+  }
+}
+
+impl Analysis for Liveness {
+  fn compute(func: &MirFunction, analyses: &mut FunctionAnalyses) -> Self {
+    let cfg = analyses.get::<Cfg>(func);
+    Liveness::new(func, &cfg)
+  }
+}
+
+/// The values `inst` reads where it stands, which phis do not.
+fn used_values(inst: &Instruction) -> Vec<MirInstId> {
+  if matches!(inst, Instruction::Phi(_)) {
+    return Vec::new();
+  }
+  inst
+    .operands()
+    .into_iter()
+    .filter_map(|value| match value {
+      MirValue::Inst(id) => Some(*id),
+      MirValue::Constant(_) => None,
+    })
+    .collect()
+}
+
+fn sorted(values: &HashSet<MirInstId>) -> Vec<MirInstId> {
+  let mut values = values.iter().copied().collect::<Vec<_>>();
+  values.sort_by_key(|value| value.0);
+  values
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mir::parser::parse_module;
+
+  #[test]
+  fn test_loop_liveness() {
+    let module = parse_module(
+      "
+      module test
+      func f(i32 %arg0) -> i32 {
+      entry.0:
+        %0 = load i32 %arg0
+        jmp header.1
+      header.1:
+        %2 = phi i32 [0, entry.0], [%6, body.2]
+        %3 = cmp lt i32 %2, %0
+        br %3, body.2, exit.3
+      body.2:
+        %5 = mul i32 %2, 2
+        %6 = add i32 %5, 1
+        jmp header.1
+      exit.3:
+        ret i32 %2
+      }
+      ",
+    )
+    .unwrap();
+    let func = &module.get_funcs()[0];
+    let liveness = Liveness::new(func, &Cfg::new(func));
+    let ids = |ids: &[usize]| ids.iter().copied().map(MirInstId).collect::<Vec<_>>();
+    let blk = MirBlockId;
+
+    assert_eq!(liveness.live_in(blk(0)), []);
+    assert_eq!(liveness.live_out(blk(0)), ids(&[0]));
+    // The phi is defined on entry, so only the bound comes around.
+    assert_eq!(liveness.live_in(blk(1)), ids(&[0]));
+    assert_eq!(liveness.live_out(blk(1)), ids(&[0, 2]));
+    assert_eq!(liveness.live_in(blk(2)), ids(&[0, 2]));
+    assert_eq!(liveness.live_out(blk(2)), ids(&[0, 6]));
+    assert_eq!(liveness.live_in(blk(3)), ids(&[2]));
+
+    assert_eq!(liveness.live_after(MirInstId(2)), ids(&[0, 2]));
+    assert_eq!(liveness.live_after(MirInstId(3)), ids(&[0, 2, 3]));
+    assert_eq!(liveness.live_after(MirInstId(5)), ids(&[0, 5]));
+    assert!(liveness.is_live_after(MirInstId(0), MirInstId(6)));
+    assert!(!liveness.is_live_after(MirInstId(5), MirInstId(6)));
+    assert_eq!(liveness.live_after(MirInstId(8)), []);
+  }
+}
@@ -0,0 +1,184 @@
+//! Natural loops.
+//!
+//! An edge from a block to one of its dominators is a back edge, and the
+//! dominator is the header of a loop. The loop holds the header and every
+//! block that reaches the source of a back edge to it without passing
+//! through the header. Back edges to the same header make a single loop.
+//! Loops with different headers are either disjoint or nested.
+
+use crate::{
+  analysis::{
+    cfg::{Cfg, reverse_postorder},
+    dominators::DominatorTree,
+    manager::{Analysis, FunctionAnalyses},
+  },
+  mir::function::{MirFunction, block::MirBlockId},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+  header: MirBlockId,
+  /// The sources of the back edges.
+  latches: Vec<MirBlockId>,
+  /// Every block of the loop, the header included, ordered by id.
+  blocks: Vec<MirBlockId>,
+  /// The index of the innermost loop containing this one.
+  parent: Option<usize>,
+}
+
+impl Loop {
+  pub fn header(&self) -> MirBlockId {
+    self.header
+  }
+
+  pub fn latches(&self) -> &[MirBlockId] {
+    &self.latches
+  }
+
+  pub fn blocks(&self) -> &[MirBlockId] {
+    &self.blocks
+  }
+
+  pub fn parent(&self) -> Option<usize> {
+    self.parent
+  }
+
+  pub fn contains(&self, blk: MirBlockId) -> bool {
+    self.blocks.contains(&blk)
+  }
+}
+
+/// The loops of a function, outer loops before the loops they contain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopInfo {
+  loops: Vec<Loop>,
+  /// The index of the innermost loop containing each block.
+  innermost: Vec<Option<usize>>,
+}
+
+impl LoopInfo {
+  pub fn new(func: &MirFunction, cfg: &Cfg, tree: &DominatorTree) -> Self {
+    let blocks = func.get_blocks().len();
+    let mut loops = Vec::new();
+    // Headers come before the blocks they dominate in reverse postorder,
+    // so an outer loop is found before the loops nested in it.
+    for header in reverse_postorder(func) {
+      let latches = cfg
+        .predecessors(header)
+        .iter()
+        .copied()
+        .filter(|pred| tree.dominates(header, *pred))
+        .collect::<Vec<_>>();
+      if latches.is_empty() {
+        continue;
+      }
+      let mut in_loop = vec![false; blocks];
+      in_loop[header.0] = true;
+      let mut worklist = latches.clone();
+      while let Some(blk) = worklist.pop() {
+        if tree.is_reachable(blk) && !std::mem::replace(&mut in_loop[blk.0], true) {
+          worklist.extend(cfg.predecessors(blk));
+        }
+      }
+      let blocks = (0..blocks)
+        .filter(|blk| in_loop[*blk])
+        .map(MirBlockId)
+        .collect();
+      loops.push(Loop {
+        header,
+        latches,
+        blocks,
+        parent: None,
+      });
+    }
+
+    // The loops containing a block are visited outermost first.
+    let mut innermost = vec![None; blocks];
+    for idx in 0..loops.len() {
+      loops[idx].parent = innermost[loops[idx].header.0];
+      for blk in &loops[idx].blocks {
+        innermost[blk.0] = Some(idx);
+      }
+    }
+    LoopInfo { loops, innermost }
+  }
+
+  pub fn loops(&self) -> &[Loop] {
+    &self.loops
+  }
+
+  /// The innermost loop containing `blk`.
+  pub fn loop_of(&self, blk: MirBlockId) -> Option<&Loop> {
+    self.innermost[blk.0].map(|idx| &self.loops[idx])
+  }
+
+  /// How many loops contain `blk`.
+  pub fn depth(&self, blk: MirBlockId) -> usize {
+    let mut depth = 0;
+    let mut current = self.innermost[blk.0];
+    while let Some(idx) = current {
+      depth += 1;
+      current = self.loops[idx].parent;
+    }
+    depth
+  }
+}
+
+impl Analysis for LoopInfo {
+  fn compute(func: &MirFunction, analyses: &mut FunctionAnalyses) -> Self {
+    let cfg = analyses.get::<Cfg>(func);
+    let tree = analyses.get::<DominatorTree>(func);
+    LoopInfo::new(func, &cfg, &tree)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mir::parser::parse_module;
+
+  #[test]
+  fn test_nested_loops() {
+    let module = parse_module(
+      "
+      module test
+      func f(bool %arg0) -> void {
+      entry.0:
+        %c = load bool %arg0
+        jmp outer.1
+      outer.1:
+        br %c, inner.2, exit.5
+      inner.2:
+        br %c, body.3, latch.4
+      body.3:
+        jmp inner.2
+      latch.4:
+        br %c, outer.1, outer.1
+      exit.5:
+        ret void
+      }
+      ",
+    )
+    .unwrap();
+    let func = &module.get_funcs()[0];
+    let info = LoopInfo::new(func, &Cfg::new(func), &DominatorTree::new(func));
+    let blk = MirBlockId;
+
+    let [outer, inner] = info.loops() else {
+      panic!("{:?}", info.loops());
+    };
+    assert_eq!(outer.header(), blk(1));
+    assert_eq!(outer.latches(), [blk(4)]);
+    assert_eq!(outer.blocks(), [blk(1), blk(2), blk(3), blk(4)]);
+    assert_eq!(outer.parent(), None);
+    assert_eq!(inner.header(), blk(2));
+    assert_eq!(inner.latches(), [blk(3)]);
+    assert_eq!(inner.blocks(), [blk(2), blk(3)]);
+    assert_eq!(inner.parent(), Some(0));
+
+    let depths = (0..6).map(|b| info.depth(blk(b))).collect::<Vec<_>>();
+    assert_eq!(depths, [0, 1, 2, 2, 1, 0]);
+    assert_eq!(info.loop_of(blk(4)).map(Loop::header), Some(blk(1)));
+    assert!(info.loop_of(blk(5)).is_none());
+  }
+}
@@ -0,0 +1,128 @@
+//! Caching analyses between passes.
+//!
+//! An [`Analysis`] is computed from a function on first request and kept
+//! until a pass mutating the function invalidates it, so passes that do not
+//! change the control flow or the values of a function share the results.
+//! Analyses may build on each other: computing one requests the others
+//! from the same cache.
+
+use std::{
+  any::{Any, TypeId},
+  collections::HashMap,
+  rc::Rc,
+};
+
+use crate::mir::function::{MirFunction, MirFunctionId};
+
+pub trait Analysis: Sized + 'static {
+  fn compute(func: &MirFunction, analyses: &mut FunctionAnalyses) -> Self;
+}
+
+/// The analyses computed so far for one function.
+#[derive(Default)]
+pub struct FunctionAnalyses {
+  results: HashMap<TypeId, Rc<dyn Any>>,
+}
+
+impl FunctionAnalyses {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The analysis `A` of `func`, computed unless it is cached.
+  pub fn get<A: Analysis>(&mut self, func: &MirFunction) -> Rc<A> {
+    if let Some(result) = self.results.get(&TypeId::of::<A>()) {
+      return result.clone().downcast().unwrap();
+    }
+    let result = Rc::new(A::compute(func, self));
+    self.results.insert(TypeId::of::<A>(), result.clone());
+    result
+  }
+
+  pub fn is_cached<A: Analysis>(&self) -> bool {
+    self.results.contains_key(&TypeId::of::<A>())
+  }
+
+  /// Drops every result, after the function was changed.
+  pub fn invalidate(&mut self) {
+    self.results.clear();
+  }
+}
+
+/// The analyses of the functions of a module.
+#[derive(Default)]
+pub struct AnalysisManager {
+  funcs: HashMap<MirFunctionId, FunctionAnalyses>,
+}
+
+impl AnalysisManager {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The cache of the function `id`.
+  pub fn function(&mut self, id: MirFunctionId) -> &mut FunctionAnalyses {
+    self.funcs.entry(id).or_default()
+  }
+
+  /// The analysis `A` of `func`, which is the function `id`.
+  pub fn get<A: Analysis>(&mut self, id: MirFunctionId, func: &MirFunction) -> Rc<A> {
+    self.function(id).get(func)
+  }
+
+  pub fn invalidate(&mut self, id: MirFunctionId) {
+    self.funcs.remove(&id);
+  }
+
+  pub fn invalidate_all(&mut self) {
+    self.funcs.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    analysis::{cfg::Cfg, dominators::DominatorTree, loops::LoopInfo},
+    mir::{function::block::MirBlockId, parser::parse_module},
+  };
+
+  #[test]
+  fn test_caches_until_invalidated() {
+    let module = parse_module(
+      "
+      module test
+      func f() -> void {
+      entry.0:
+        jmp loop.1
+      loop.1:
+        br true, loop.1, exit.2
+      exit.2:
+        ret void
+      }
+      func g() -> void {
+      entry.0:
+        ret void
+      }
+      ",
+    )
+    .unwrap();
+    let (f, g) = (MirFunctionId(0), MirFunctionId(1));
+    let mut analyses = AnalysisManager::new();
+    let loops = analyses.get::<LoopInfo>(f, &module.get_funcs()[0]);
+    assert_eq!(loops.loops()[0].header(), MirBlockId(1));
+    // Loops are found with the dominator tree and the CFG, which stay
+    // cached.
+    assert!(analyses.function(f).is_cached::<DominatorTree>());
+    assert!(analyses.function(f).is_cached::<Cfg>());
+    let again = analyses.get::<LoopInfo>(f, &module.get_funcs()[0]);
+    assert!(Rc::ptr_eq(&loops, &again));
+
+    analyses.get::<DominatorTree>(g, &module.get_funcs()[1]);
+    analyses.invalidate(f);
+    assert!(!analyses.function(f).is_cached::<LoopInfo>());
+    assert!(analyses.function(g).is_cached::<DominatorTree>());
+    analyses.invalidate_all();
+    assert!(!analyses.function(g).is_cached::<DominatorTree>());
+  }
+}
@@ -0,0 +1,6 @@
+pub mod call_graph;
+pub mod cfg;
+pub mod dominators;
+pub mod liveness;
+pub mod loops;
+pub mod manager;
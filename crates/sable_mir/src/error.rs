@@ -1,9 +1,237 @@
-use sable_parser::info::ValType;
+use std::{fmt::Display, ops::Range};
 
+use ariadne::{Color, Label, Report, ReportKind};
+use sable_parser::{info::ValType, parser::error::ParseErrReport, position::Position};
+
+use crate::mir::{
+  function::{MirFunctionId, block::MirBlockId},
+  instruction::MirInstId,
+};
+
+/// What lowering could not translate.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum LoweringError<'ctx> {
+pub enum LoweringErrorKind<'ctx> {
   InvalidNumericValue(&'ctx str),
   IllegalType(ValType),
   VariableNotFound(&'ctx str),
   FunctionNotFound(&'ctx str),
-}
\ No newline at end of file
+  /// An expression without a value where one is needed.
+  MissingValue,
+}
+
+impl Display for LoweringErrorKind<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LoweringErrorKind::InvalidNumericValue(value) => {
+        write!(f, "invalid numeric value `{}`", value)
+      }
+      LoweringErrorKind::IllegalType(type_) => write!(f, "illegal type `{}` in lowering", type_),
+      LoweringErrorKind::VariableNotFound(name) => write!(f, "variable `{}` not found", name),
+      LoweringErrorKind::FunctionNotFound(name) => write!(f, "function `{}` not found", name),
+      LoweringErrorKind::MissingValue => write!(f, "expression has no value"),
+    }
+  }
+}
+
+/// A lowering failure at `pos` in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoweringError<'ctx> {
+  kind: LoweringErrorKind<'ctx>,
+  pos: Position,
+}
+
+impl<'ctx> LoweringError<'ctx> {
+  pub fn new(kind: LoweringErrorKind<'ctx>, pos: Position) -> Self {
+    Self { kind, pos }
+  }
+
+  pub fn kind(&self) -> &LoweringErrorKind<'ctx> {
+    &self.kind
+  }
+
+  pub fn pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn report<'f>(&self, filename: &'f str) -> ParseErrReport<'f> {
+    Report::build(ReportKind::Error, (filename, self.pos.range.clone()))
+      .with_message(self.kind.to_string())
+      .with_label(
+        Label::new((filename, self.pos.range.clone()))
+          .with_message("cannot be lowered")
+          .with_color(Color::Red),
+      )
+      .finish()
+  }
+}
+
+impl Display for LoweringError<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "line {}: {}", self.pos.line, self.kind)
+  }
+}
+
+/// A malformed line of textual MIR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirParseError {
+  message: String,
+  span: Range<usize>,
+}
+
+impl MirParseError {
+  pub fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+    Self {
+      message: message.into(),
+      span,
+    }
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  pub fn span(&self) -> Range<usize> {
+    self.span.clone()
+  }
+
+  pub fn report<'f>(&self, filename: &'f str) -> ParseErrReport<'f> {
+    Report::build(ReportKind::Error, (filename, self.span.clone()))
+      .with_message(&self.message)
+      .with_label(
+        Label::new((filename, self.span.clone()))
+          .with_message("here")
+          .with_color(Color::Yellow),
+      )
+      .finish()
+  }
+}
+
+impl Display for MirParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} at {}..{}",
+      self.message, self.span.start, self.span.end
+    )
+  }
+}
+
+/// What is wrong with an instruction or block found by the verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyErrorKind {
+  NoBlocks,
+  MissingTerminator,
+  TerminatorNotLast,
+  UndefinedValue(MirInstId),
+  /// A reference to an instruction that does not produce a value.
+  NotAValue(MirInstId),
+  /// A stack slot used where a value is expected.
+  SlotAsValue(MirInstId),
+  /// A store or load through something that is not an `alloca`.
+  NotASlot(MirInstId),
+  UnknownParam(usize),
+  UnknownBlock(MirBlockId),
+  UnknownFunction(MirFunctionId),
+  TypeMismatch {
+    expected: ValType,
+    found: ValType,
+  },
+  IllegalType(ValType),
+  ArgumentCount {
+    expected: usize,
+    found: usize,
+  },
+  /// A phi after an instruction that is not one.
+  MisplacedPhi,
+  /// A predecessor a phi has no incoming value for.
+  MissingIncoming(MirBlockId),
+  /// An incoming value for a block that is not a predecessor, or for one
+  /// that already has one.
+  UnexpectedIncoming(MirBlockId),
+}
+
+impl Display for VerifyErrorKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      VerifyErrorKind::NoBlocks => write!(f, "function has no blocks"),
+      VerifyErrorKind::MissingTerminator => write!(f, "block does not end with a terminator"),
+      VerifyErrorKind::TerminatorNotLast => write!(f, "terminator in the middle of a block"),
+      VerifyErrorKind::UndefinedValue(id) => write!(f, "use of undefined value {}", id),
+      VerifyErrorKind::NotAValue(id) => write!(f, "{} does not produce a value", id),
+      VerifyErrorKind::SlotAsValue(id) => write!(f, "stack slot {} used as a value", id),
+      VerifyErrorKind::NotASlot(id) => write!(f, "{} is not a stack slot", id),
+      VerifyErrorKind::UnknownParam(idx) => write!(f, "unknown parameter %arg{}", idx),
+      VerifyErrorKind::UnknownBlock(id) => write!(f, "unknown block {}", id.0),
+      VerifyErrorKind::UnknownFunction(id) => write!(f, "unknown function @{}", id.0),
+      VerifyErrorKind::TypeMismatch { expected, found } => {
+        write!(f, "expected type `{}`, found `{}`", expected, found)
+      }
+      VerifyErrorKind::IllegalType(type_) => write!(f, "illegal type `{}`", type_),
+      VerifyErrorKind::ArgumentCount { expected, found } => {
+        write!(f, "expected {} arguments, found {}", expected, found)
+      }
+      VerifyErrorKind::MisplacedPhi => write!(f, "phi after the start of the block"),
+      VerifyErrorKind::MissingIncoming(id) => {
+        write!(f, "phi has no incoming value for predecessor {}", id.0)
+      }
+      VerifyErrorKind::UnexpectedIncoming(id) => {
+        write!(f, "unexpected incoming value for block {}", id.0)
+      }
+    }
+  }
+}
+
+/// An invariant of the MIR broken in `func`, at `block` and `inst` if the
+/// violation is local to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+  func: String,
+  block: Option<String>,
+  inst: Option<MirInstId>,
+  kind: VerifyErrorKind,
+}
+
+impl VerifyError {
+  pub fn new(
+    func: impl Into<String>,
+    block: Option<String>,
+    inst: Option<MirInstId>,
+    kind: VerifyErrorKind,
+  ) -> Self {
+    Self {
+      func: func.into(),
+      block,
+      inst,
+      kind,
+    }
+  }
+
+  pub fn func(&self) -> &str {
+    &self.func
+  }
+
+  pub fn block(&self) -> Option<&str> {
+    self.block.as_deref()
+  }
+
+  pub fn inst(&self) -> Option<MirInstId> {
+    self.inst
+  }
+
+  pub fn kind(&self) -> &VerifyErrorKind {
+    &self.kind
+  }
+}
+
+impl Display for VerifyError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "in function `{}`", self.func)?;
+    if let Some(block) = &self.block {
+      write!(f, ", block `{}`", block)?;
+    }
+    if let Some(inst) = self.inst {
+      write!(f, ", instruction {}", inst)?;
+    }
+    write!(f, ": {}", self.kind)
+  }
+}
@@ -1,3 +1,6 @@
 pub mod mir;
 pub mod lowering;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod verify;
+pub mod analysis;
+pub mod transform;
@@ -4,72 +4,74 @@ use sable_parser::{
   ast::{
     ast::AST,
     expression::{
-      AssignExpression, BinaryExpression, CallExpression, Expression, LiteralExpression,
+      AssignExpression, BinaryExpression, BlockExpression, CallExpression, Expression,
+      LiteralExpression, UnaryExpression,
     },
     function::Function,
-    statement::{LetStatement, ReturnStatement, Statement},
+    statement::{
+      ElseBranch, IfStatement, LetStatement, ReturnStatement, Statement, WhileStatement,
+    },
   },
   info::{OperatorType, ValType},
+  position::Position,
 };
+use sable_sema::types::TypeTable;
 use smallvec::SmallVec;
 
 use crate::{
-  error::LoweringError,
+  error::{LoweringError, LoweringErrorKind},
   mir::{
     builder::Builder,
-    function::{MirFunction, MirFunctionId, block::MirBlock},
-    instruction::MirInstId,
+    function::{
+      MirFunction, MirFunctionId,
+      block::{MirBlock, MirBlockId},
+    },
+    instruction::{CmpPredicate, MirInstId},
     module::MirModule,
+    place::Place,
     value::{Constant, MirValue},
   },
 };
 
 const MAX_INLINE_FUNCS: usize = 20;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum NamendPlace {
-  Inst(MirInstId),
-  Param(usize),
-}
-
 pub struct Lowerer<'ctx> {
   mir_mod: Rc<RefCell<MirModule<'ctx>>>,
   ast: Rc<RefCell<AST<'ctx>>>,
+  types: TypeTable,
   errors: Vec<LoweringError<'ctx>>,
-  namend: HashMap<&'ctx str, (ValType, NamendPlace)>,
+  namend: HashMap<&'ctx str, (ValType, Place)>,
+  /// The slots the parameters assigned in the current function were
+  /// spilled to.
+  spilled: HashMap<usize, MirInstId>,
   funcs: HashMap<&'ctx str, MirFunctionId>,
+  /// `(header, exit)` blocks of the loops enclosing the current statement.
+  loops: Vec<(MirBlockId, MirBlockId)>,
 }
 
 impl<'ctx> Lowerer<'ctx> {
-  pub fn new(mir_mod: MirModule<'ctx>, ast: Rc<RefCell<AST<'ctx>>>) -> Self {
+  pub fn new(mir_mod: MirModule<'ctx>, ast: Rc<RefCell<AST<'ctx>>>, types: TypeTable) -> Self {
     Self {
       mir_mod: Rc::new(RefCell::new(mir_mod)),
       ast,
+      types,
       errors: Vec::new(),
       namend: HashMap::new(),
+      spilled: HashMap::new(),
       funcs: HashMap::new(),
+      loops: Vec::new(),
     }
   }
 
-  fn get_last_inst(&self, func: MirFunctionId) -> MirInstId {
-    let module = self.mir_mod.borrow();
-
-    let func = match module.get_func(func) {
-      Some(f) => f,
-      None => return MirInstId(0),
-    };
-
-    let lst_blk_id = match func.get_last_blk() {
-      Some(b) => b,
-      None => return MirInstId(0),
-    };
-
-    let lst_blk = match func.get_block(lst_blk_id) {
-      Some(b) => b,
-      None => return MirInstId(0),
-    };
-
-    return MirInstId(lst_blk.range().end);
+  /// The type sema proved for the expression at `pos`.
+  fn type_at(&self, pos: &Position) -> Result<ValType, LoweringError<'ctx>> {
+    match self.types.get(pos) {
+      Some(ValType::Untyped) | None => Err(LoweringError::new(
+        LoweringErrorKind::IllegalType(ValType::Untyped),
+        pos.clone(),
+      )),
+      Some(type_) => Ok(type_.clone()),
+    }
   }
 
   fn lower_literal_expression(
@@ -80,8 +82,9 @@ impl<'ctx> Lowerer<'ctx> {
       ValType::I32 => {
         let value = literal_expression.get_value().parse::<u64>();
         if value.is_err() {
-          return Err(LoweringError::InvalidNumericValue(
-            literal_expression.get_value(),
+          return Err(LoweringError::new(
+            LoweringErrorKind::InvalidNumericValue(literal_expression.get_value()),
+            literal_expression.get_pos(),
           ));
         }
         let value = value.unwrap();
@@ -91,46 +94,70 @@ impl<'ctx> Lowerer<'ctx> {
       ValType::F32 => {
         let value = literal_expression.get_value().parse::<f64>();
         if value.is_err() {
-          return Err(LoweringError::InvalidNumericValue(
-            literal_expression.get_value(),
+          return Err(LoweringError::new(
+            LoweringErrorKind::InvalidNumericValue(literal_expression.get_value()),
+            literal_expression.get_pos(),
           ));
         }
         let value = value.unwrap();
         let value = MirValue::Constant(Constant::FloatValue(literal_expression.get_type(), value));
         Ok(value)
       }
-      ValType::Untyped => Err(LoweringError::IllegalType(literal_expression.get_type())),
+      ValType::Bool => match literal_expression.get_value() {
+        "true" => Ok(MirValue::Constant(Constant::BoolValue(true))),
+        "false" => Ok(MirValue::Constant(Constant::BoolValue(false))),
+        _ => unreachable!(),
+      },
+      ValType::Untyped => Err(LoweringError::new(
+        LoweringErrorKind::IllegalType(literal_expression.get_type()),
+        literal_expression.get_pos(),
+      )),
       ValType::Void => Ok(MirValue::Constant(Constant::Null)),
     }
   }
 
+  /// The place the variable `name` lives in.
+  fn lower_place(&self, name: &'ctx str, pos: Position) -> Result<Place, LoweringError<'ctx>> {
+    match self.namend.get(name) {
+      Some((_, Place::Param(idx))) => Ok(
+        self
+          .spilled
+          .get(idx)
+          .map_or(Place::Param(*idx), |slot| Place::Local(*slot)),
+      ),
+      Some((_, place)) => Ok(*place),
+      None => Err(LoweringError::new(
+        LoweringErrorKind::VariableNotFound(name),
+        pos,
+      )),
+    }
+  }
+
+  /// Stores `value` into `place`, spilling a parameter to a slot the first
+  /// time it is assigned.
+  fn store(&mut self, place: Place, value: MirValue, builder: &mut Builder<'ctx>) {
+    let slot = match place {
+      Place::Local(slot) => slot,
+      Place::Param(idx) => *self
+        .spilled
+        .entry(idx)
+        .or_insert_with(|| builder.spill_param(idx)),
+    };
+    builder.build_store(slot, value);
+  }
+
+  /// Lowers an assignment, which evaluates to the value assigned.
   fn lower_assign_expression(
     &mut self,
     assign_expression: &AssignExpression<'ctx>,
     builder: &mut Builder<'ctx>,
-  ) -> Result<Option<MirValue>, LoweringError<'ctx>> {
-    match assign_expression.get_asignee() {
-      Some(assign_to) => {
-        let (type_, inst) = match self.namend.get(assign_to).cloned() {
-          Some(v) => v,
-          None => return Err(LoweringError::VariableNotFound(assign_to)),
-        };
-
-        let value = self
-          .lower_expression(assign_expression.get_value(), builder)?
-          .unwrap();
-        let loaded_value = builder.build_load(type_.clone(), inst.clone());
-
-        builder.build_store(loaded_value, value);
-        Ok(None)
-      }
-      None => {
-        let value = self
-          .lower_expression(assign_expression.get_value(), builder)?
-          .unwrap();
-        Ok(Some(value))
-      }
+  ) -> Result<MirValue, LoweringError<'ctx>> {
+    let value = self.lower_value(assign_expression.get_value(), builder)?;
+    if let Some(assign_to) = assign_expression.get_asignee() {
+      let place = self.lower_place(assign_to, assign_expression.get_pos())?;
+      self.store(place, value.clone(), builder);
     }
+    Ok(value)
   }
 
   fn lower_binary_expression(
@@ -138,94 +165,170 @@ impl<'ctx> Lowerer<'ctx> {
     binary_expression: &BinaryExpression<'ctx>,
     builder: &mut Builder<'ctx>,
   ) -> Result<MirValue, LoweringError<'ctx>> {
-    let left = self
-      .lower_expression(binary_expression.get_left(), builder)?
-      .unwrap();
-    let right = self
-      .lower_expression(binary_expression.get_right(), builder)?
-      .unwrap();
-
+    let left = self.lower_value(binary_expression.get_left(), builder)?;
     let op = binary_expression.get_operator();
+    if op.is_logical() {
+      return self.lower_short_circuit(binary_expression, left, builder);
+    }
+    let right = self.lower_value(binary_expression.get_right(), builder)?;
+
+    let type_ = self.type_at(&binary_expression.get_left().get_pos())?;
     let res = match op {
       OperatorType::Add => {
-        let inst = builder.build_add(left, right);
+        let inst = builder.build_add(type_, left, right);
         MirValue::Inst(inst)
       }
       OperatorType::Sub => {
-        let inst = builder.build_sub(left, right);
+        let inst = builder.build_sub(type_, left, right);
         MirValue::Inst(inst)
       }
       OperatorType::Mul => {
-        let inst = builder.build_mul(left, right);
+        let inst = builder.build_mul(type_, left, right);
         MirValue::Inst(inst)
       }
       OperatorType::Div => {
-        let inst = builder.build_div(left, right);
+        let inst = builder.build_div(type_, left, right);
+        MirValue::Inst(inst)
+      }
+      OperatorType::Eq
+      | OperatorType::NotEq
+      | OperatorType::Lt
+      | OperatorType::Le
+      | OperatorType::Gt
+      | OperatorType::Ge => {
+        let predicate = CmpPredicate::from_operator(op).unwrap();
+        let inst = builder.build_cmp(predicate, type_, left, right);
         MirValue::Inst(inst)
       }
+      OperatorType::And | OperatorType::Or | OperatorType::Not => unreachable!(),
     };
 
     Ok(res)
   }
 
+  /// Lowers `&&` and `||`, whose right operand is only evaluated when the
+  /// left one, already lowered to `left`, does not decide the result.
+  fn lower_short_circuit(
+    &mut self,
+    binary_expression: &BinaryExpression<'ctx>,
+    left: MirValue,
+    builder: &mut Builder<'ctx>,
+  ) -> Result<MirValue, LoweringError<'ctx>> {
+    let is_and = *binary_expression.get_operator() == OperatorType::And;
+    let left_blk = builder.selected().unwrap();
+    let right_blk = builder.append_block("rhs");
+    let merge = builder.append_block("merge");
+    if is_and {
+      builder.build_branch(left, right_blk, merge);
+    } else {
+      builder.build_branch(left, merge, right_blk);
+    }
+
+    builder.set_selected(right_blk);
+    let right = self.lower_value(binary_expression.get_right(), builder)?;
+    // The right operand may have branched itself.
+    let right_end = builder.selected().unwrap();
+    builder.build_jump(merge);
+
+    builder.set_selected(merge);
+    let decided = MirValue::Constant(Constant::BoolValue(!is_and));
+    let phi = builder.build_phi(ValType::Bool, vec![(left_blk, decided), (right_end, right)]);
+    Ok(MirValue::Inst(phi))
+  }
+
+  fn lower_unary_expression(
+    &mut self,
+    unary_expression: &UnaryExpression<'ctx>,
+    builder: &mut Builder<'ctx>,
+  ) -> Result<MirValue, LoweringError<'ctx>> {
+    let operand = self.lower_value(unary_expression.get_operand(), builder)?;
+
+    match unary_expression.get_operator() {
+      OperatorType::Not => Ok(MirValue::Inst(builder.build_not(operand))),
+      _ => unreachable!(),
+    }
+  }
+
   fn lower_call_expression(
     &mut self,
     call_expression: &CallExpression<'ctx>,
     builder: &mut Builder<'ctx>,
   ) -> Result<MirValue, LoweringError<'ctx>> {
     let callee = call_expression.get_callee();
+    let not_found = || {
+      LoweringError::new(
+        LoweringErrorKind::FunctionNotFound(callee),
+        call_expression.get_pos(),
+      )
+    };
     let func_id = match self.funcs.get(callee) {
-      Some(v) => v.clone(),
-      None => return Err(LoweringError::FunctionNotFound(callee)),
+      Some(v) => *v,
+      None => return Err(not_found()),
     };
 
     self
       .mir_mod
       .borrow()
-      .get_func(func_id.clone())
-      .ok_or(LoweringError::FunctionNotFound(callee))?;
+      .get_func(func_id)
+      .ok_or_else(not_found)?;
 
     let mut args = Vec::new();
     for arg in call_expression.get_args() {
-      let arg = self.lower_expression(arg, builder)?;
-      if let Some(arg) = arg {
-        args.push(arg);
-      }
+      args.push(self.lower_value(arg, builder)?);
     }
 
-    let call_inst = builder.build_call(func_id, args);
+    let type_ = self.type_at(&call_expression.get_pos())?;
+    let call_inst = builder.build_call(func_id, type_, args);
     let call_value = MirValue::Inst(call_inst);
     Ok(call_value)
   }
 
+  /// Lowers `expr`, giving the instructions built for it its position.
   fn lower_expression(
     &mut self,
     expr: &Expression<'ctx>,
     builder: &mut Builder<'ctx>,
+  ) -> Result<Option<MirValue>, LoweringError<'ctx>> {
+    let outer = builder.pos();
+    builder.set_pos(Some(expr.get_pos()));
+    let result = self.lower_expression_inner(expr, builder);
+    builder.set_pos(outer);
+    result
+  }
+
+  /// Lowers `expr`, which has to produce a value.
+  fn lower_value(
+    &mut self,
+    expr: &Expression<'ctx>,
+    builder: &mut Builder<'ctx>,
+  ) -> Result<MirValue, LoweringError<'ctx>> {
+    self
+      .lower_expression(expr, builder)?
+      .ok_or_else(|| LoweringError::new(LoweringErrorKind::MissingValue, expr.get_pos()))
+  }
+
+  fn lower_expression_inner(
+    &mut self,
+    expr: &Expression<'ctx>,
+    builder: &mut Builder<'ctx>,
   ) -> Result<Option<MirValue>, LoweringError<'ctx>> {
     match expr {
       Expression::LiteralExpression(literal_expression) => {
         Ok(Some(self.lower_literal_expression(literal_expression)?))
       }
       Expression::BlockExpression(block_expression) => {
-        let stmts = block_expression.get_stmts();
-        for stmt in stmts {
-          self.lower_statement(stmt, builder)?;
-        }
-        Ok(None)
-      }
-      Expression::AssignExpression(assign_expression) => {
-        self.lower_assign_expression(assign_expression, builder)?;
+        self.lower_block(block_expression, builder)?;
         Ok(None)
       }
+      Expression::AssignExpression(assign_expression) => Ok(Some(
+        self.lower_assign_expression(assign_expression, builder)?,
+      )),
       Expression::VariableExpression(variable_expression) => {
         let name = variable_expression.get_name();
-        if let Some((type_, inst)) = self.namend.get(name) {
-          let value = builder.build_load(type_.clone(), inst.clone());
-          return Ok(Some(MirValue::Inst(value)));
-        } else {
-          return Err(LoweringError::VariableNotFound(name));
-        }
+        let place = self.lower_place(name, variable_expression.get_pos())?;
+        let type_ = self.namend[name].0.clone();
+        let value = builder.build_load(type_, place);
+        Ok(Some(MirValue::Inst(value)))
       }
       Expression::BinaryExpression(binary_expression) => Ok(Some(
         self.lower_binary_expression(binary_expression, builder)?,
@@ -234,6 +337,9 @@ impl<'ctx> Lowerer<'ctx> {
       Expression::CallExpression(call_expression) => {
         Ok(Some(self.lower_call_expression(call_expression, builder)?))
       }
+      Expression::UnaryExpression(unary_expression) => Ok(Some(
+        self.lower_unary_expression(unary_expression, builder)?,
+      )),
     }
   }
 
@@ -243,22 +349,14 @@ impl<'ctx> Lowerer<'ctx> {
     builder: &mut Builder<'ctx>,
   ) -> Result<(), LoweringError<'ctx>> {
     let store_loc = builder.build_alloca(let_statement.get_type().clone());
-    let value = match let_statement.get_assignee() {
-      Some(v) => self.lower_assign_expression(v, builder),
-      None => Ok(Some(MirValue::Constant(Constant::Null))),
-    };
-
-    if let Err(err) = value {
-      return Err(err);
+    // Without an initializer the slot stays uninitialized until assigned.
+    if let Some(assignee) = let_statement.get_assignee() {
+      let value = self.lower_assign_expression(assignee, builder)?;
+      builder.build_store(store_loc, value);
     }
-    let value = value.unwrap().unwrap();
-    builder.build_store(store_loc, value);
     self.namend.insert(
       let_statement.get_name(),
-      (
-        let_statement.get_type().clone(),
-        NamendPlace::Inst(store_loc),
-      ),
+      (let_statement.get_type().clone(), Place::Local(store_loc)),
     );
 
     Ok(())
@@ -269,77 +367,195 @@ impl<'ctx> Lowerer<'ctx> {
     return_statement: &ReturnStatement<'ctx>,
     builder: &mut Builder<'ctx>,
   ) -> Result<(), LoweringError<'ctx>> {
-    let val = self
-      .lower_expression(return_statement.get_value(), builder)?
-      .unwrap();
+    let value = match return_statement.get_value() {
+      Some(value) => value,
+      None => {
+        builder.build_return(ValType::Void, MirValue::Constant(Constant::Null));
+        return Ok(());
+      }
+    };
 
-    builder.build_return(return_statement.get_type(), val);
+    let val = self.lower_value(value, builder)?;
+    let type_ = self.type_at(&value.get_pos())?;
+    builder.build_return(type_, val);
     Ok(())
   }
 
+  /// Lowers `stmt`, giving the instructions built for it its position.
   fn lower_statement(
     &mut self,
     stmt: &Statement<'ctx>,
     builder: &mut Builder<'ctx>,
+  ) -> Result<(), LoweringError<'ctx>> {
+    let outer = builder.pos();
+    builder.set_pos(Some(stmt.get_pos()));
+    let result = self.lower_statement_inner(stmt, builder);
+    builder.set_pos(outer);
+    result
+  }
+
+  fn lower_statement_inner(
+    &mut self,
+    stmt: &Statement<'ctx>,
+    builder: &mut Builder<'ctx>,
   ) -> Result<(), LoweringError<'ctx>> {
     match stmt {
       Statement::Expression(expression) => {
-        let value = self.lower_expression(expression, builder);
-        if let Err(err) = value {
-          return Err(err);
-        }
+        self.lower_expression(expression, builder)?;
         Ok(())
       }
       Statement::ReturnStatement(return_statement) => {
-        let res = self.lower_ret_inst(return_statement, builder);
-        if let Err(err) = res {
-          return Err(err);
-        }
+        self.lower_ret_inst(return_statement, builder)
+      }
+      Statement::LetStatement(let_statement) => self.lower_let_stmt(let_statement, builder),
+      Statement::IfStatement(if_statement) => self.lower_if_stmt(if_statement, builder),
+      Statement::WhileStatement(while_statement) => self.lower_while_stmt(while_statement, builder),
+      Statement::BreakStatement(_) => {
+        let (_, exit) = *self.loops.last().unwrap();
+        builder.build_jump(exit);
         Ok(())
       }
-      Statement::LetStatement(let_statement) => {
-        let res = self.lower_let_stmt(let_statement, builder);
-        if let Err(err) = res {
-          return Err(err);
-        }
-
+      Statement::ContinueStatement(_) => {
+        let (header, _) = *self.loops.last().unwrap();
+        builder.build_jump(header);
         Ok(())
       }
     }
   }
 
+  /// Lowers the statements of `block` in a scope of their own. Statements
+  /// following a terminator are unreachable and not lowered.
+  fn lower_block(
+    &mut self,
+    block: &BlockExpression<'ctx>,
+    builder: &mut Builder<'ctx>,
+  ) -> Result<(), LoweringError<'ctx>> {
+    let outer = self.namend.clone();
+    let mut result = Ok(());
+    for stmt in block.get_stmts() {
+      if builder.is_terminated() {
+        break;
+      }
+      result = self.lower_statement(stmt, builder);
+      if result.is_err() {
+        break;
+      }
+    }
+    self.namend = outer;
+    result
+  }
+
+  fn lower_if_stmt(
+    &mut self,
+    if_statement: &IfStatement<'ctx>,
+    builder: &mut Builder<'ctx>,
+  ) -> Result<(), LoweringError<'ctx>> {
+    let cond = self.lower_value(if_statement.get_condition(), builder)?;
+
+    let then_blk = builder.append_block("then");
+    let else_blk = match if_statement.get_else_branch() {
+      Some(_) => builder.append_block("else"),
+      None => builder.append_block("merge"),
+    };
+    builder.build_branch(cond, then_blk, else_blk);
+
+    let else_branch = match if_statement.get_else_branch() {
+      Some(else_branch) => else_branch,
+      None => {
+        builder.set_selected(then_blk);
+        self.lower_block(if_statement.get_then_block(), builder)?;
+        if !builder.is_terminated() {
+          builder.build_jump(else_blk);
+        }
+        builder.set_selected(else_blk);
+        return Ok(());
+      }
+    };
+
+    // The merge block is only created once a branch falls through, so an
+    // if/else whose branches all return leaves no empty block behind.
+    let mut merge_blk = None;
+
+    builder.set_selected(then_blk);
+    self.lower_block(if_statement.get_then_block(), builder)?;
+    if !builder.is_terminated() {
+      let merge = *merge_blk.get_or_insert_with(|| builder.append_block("merge"));
+      builder.build_jump(merge);
+    }
+
+    builder.set_selected(else_blk);
+    match else_branch {
+      ElseBranch::Block(block) => self.lower_block(block, builder)?,
+      ElseBranch::If(else_if) => self.lower_if_stmt(else_if, builder)?,
+    }
+    if !builder.is_terminated() {
+      let merge = *merge_blk.get_or_insert_with(|| builder.append_block("merge"));
+      builder.build_jump(merge);
+    }
+
+    if let Some(merge) = merge_blk {
+      builder.set_selected(merge);
+    }
+    Ok(())
+  }
+
+  fn lower_while_stmt(
+    &mut self,
+    while_statement: &WhileStatement<'ctx>,
+    builder: &mut Builder<'ctx>,
+  ) -> Result<(), LoweringError<'ctx>> {
+    let header = builder.append_block("header");
+    builder.build_jump(header);
+
+    builder.set_selected(header);
+    let cond = self.lower_value(while_statement.get_condition(), builder)?;
+    let body = builder.append_block("body");
+    let exit = builder.append_block("exit");
+    builder.build_branch(cond, body, exit);
+
+    builder.set_selected(body);
+    self.loops.push((header, exit));
+    let result = self.lower_block(while_statement.get_body(), builder);
+    self.loops.pop();
+    result?;
+    if !builder.is_terminated() {
+      builder.build_jump(header);
+    }
+
+    builder.set_selected(exit);
+    Ok(())
+  }
+
+  /// Adds the signature of `func` to the module so calls can be lowered
+  /// before its body is.
+  fn declare_func(&mut self, func: &Function<'ctx>) -> MirFunctionId {
+    let mut mir_func = MirFunction::new(func.get_name(), func.get_ret_type());
+    mir_func.set_inline(func.get_inline());
+    for param in func.get_params() {
+      mir_func.add_param(param.get_val_type());
+    }
+
+    let func_id = self.mir_mod.borrow_mut().add_func(mir_func);
+    self.funcs.insert(func.get_name(), func_id);
+    func_id
+  }
+
   fn lower_func(
     &mut self,
     func: Rc<RefCell<Function<'ctx>>>,
+    func_id: MirFunctionId,
   ) -> Result<(), Vec<LoweringError<'ctx>>> {
     self.namend.clear();
+    self.spilled.clear();
     let mut errors = Vec::new();
 
-    let mir_func = MirFunction::new(func.borrow().get_name());
-    let func_id = self.mir_mod.borrow_mut().add_func(mir_func);
-
-    self.funcs.insert(func.borrow().get_name(), func_id);
-
     for (i, param) in func.borrow().get_params().iter().enumerate() {
-      let param_type = param.get_val_type();
-      let param_name = param.get_name();
       self
-        .mir_mod
-        .borrow_mut()
-        .get_func_mut(func_id)
-        .unwrap()
-        .add_param(param_type.clone());
-
-      self.namend.insert(
-        param_name,
-        (
-          param_type.clone(),
-          NamendPlace::Param(func.borrow().get_params().len() - i - 1),
-        ),
-      );
+        .namend
+        .insert(param.get_name(), (param.get_val_type(), Place::Param(i)));
     }
 
-    let entry_block = MirBlock::new("entry", self.get_last_inst(func_id));
+    let entry_block = MirBlock::new("entry");
     let entry_block_id = self
       .mir_mod
       .borrow_mut()
@@ -354,12 +570,25 @@ impl<'ctx> Lowerer<'ctx> {
     let stmts = binding.get_body().get_stmts();
 
     for stmt in stmts {
-      let res = self.lower_statement(&stmt, &mut builder);
+      if builder.is_terminated() {
+        break;
+      }
+      let res = self.lower_statement(stmt, &mut builder);
       if let Err(errs) = res {
         errors.push(errs);
       }
     }
 
+    // Sema rejects non-void functions that can fall off their end, so only
+    // void functions get an implicit return here.
+    if !builder.is_terminated() {
+      if binding.get_ret_type() == ValType::Void {
+        builder.build_return(ValType::Void, MirValue::Constant(Constant::Null));
+      } else {
+        builder.build_unreachable();
+      }
+    }
+
     if errors.is_empty() {
       Ok(())
     } else {
@@ -367,28 +596,279 @@ impl<'ctx> Lowerer<'ctx> {
     }
   }
 
-  pub fn lower(&mut self) -> Result<Rc<RefCell<MirModule<'ctx>>>, &[LoweringError]> {
+  pub fn lower(&mut self) -> Result<Rc<RefCell<MirModule<'ctx>>>, &[LoweringError<'_>]> {
     let funcs = {
       let ast = self.ast.borrow();
-      let funcs = ast
+      ast
         .get_funcs()
         .iter()
-        .map(|f| f.clone())
-        .collect::<SmallVec<[_; MAX_INLINE_FUNCS]>>();
-      funcs
+        .cloned()
+        .collect::<SmallVec<[_; MAX_INLINE_FUNCS]>>()
     };
 
-    for func in funcs {
-      let res = self.lower_func(func.clone());
+    let func_ids = funcs
+      .iter()
+      .map(|func| self.declare_func(&func.borrow()))
+      .collect::<SmallVec<[_; MAX_INLINE_FUNCS]>>();
+
+    for (func, func_id) in funcs.into_iter().zip(func_ids) {
+      let res = self.lower_func(func, func_id);
       if let Err(errs) = res {
         self.errors.extend(errs);
       }
     }
 
     if self.errors.is_empty() {
+      #[cfg(debug_assertions)]
+      if let Err(errs) = crate::verify::verify(&self.mir_mod.borrow()) {
+        let errs = errs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        panic!("lowering produced invalid MIR:\n{}", errs.join("\n"));
+      }
       Ok(self.mir_mod.clone())
     } else {
       Err(&self.errors)
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser};
+  use sable_sema::sema::Sema;
+
+  use super::*;
+  use crate::mir::instruction::Instruction;
+
+  fn lower(source: &str, check: impl FnOnce(&MirModule)) {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast.clone());
+    assert!(sema.analyze().is_ok());
+    let mut lowerer = Lowerer::new(MirModule::new("test"), ast, sema.into_types());
+    let module = lowerer.lower().unwrap();
+    check(&module.borrow());
+  }
+
+  fn block_names<'a>(func: &MirFunction<'a>) -> Vec<&'a str> {
+    func.get_blocks().iter().map(|blk| blk.name()).collect()
+  }
+
+  fn entry_insts<'f>(func: &'f MirFunction) -> Vec<&'f Instruction> {
+    func
+      .get_insts(MirBlockId(0))
+      .map(|(_, inst)| inst)
+      .collect()
+  }
+
+  #[test]
+  fn test_if_without_else_branches_to_merge() {
+    lower(
+      "func i32 f(i32 x) { let i32 y = 0; if x > 0 { y = 1; } return y; }",
+      |module| {
+        let func = &module.get_funcs()[0];
+        assert_eq!(block_names(func), ["entry", "then", "merge"]);
+        assert!(matches!(
+          func.get_terminator(MirBlockId(0)),
+          Some(Instruction::Branch(br)) if br.then_blk() == MirBlockId(1) && br.else_blk() == MirBlockId(2)
+        ));
+        assert!(matches!(
+          func.get_terminator(MirBlockId(1)),
+          Some(Instruction::Jump(jmp)) if jmp.target() == MirBlockId(2)
+        ));
+        assert!(matches!(
+          func.get_terminator(MirBlockId(2)),
+          Some(Instruction::Return(_))
+        ));
+      },
+    );
+  }
+
+  #[test]
+  fn test_if_else_chain_blocks() {
+    lower(
+      "func i32 sign(i32 x) { if x < 0 { return 0 - 1; } else if x == 0 { return 0; } else { return 1; } }",
+      |module| {
+        let func = &module.get_funcs()[0];
+        assert_eq!(block_names(func), ["entry", "then", "else", "then", "else"]);
+        for (id, _) in func.get_blocks().iter().enumerate() {
+          assert!(func.get_terminator(MirBlockId(id)).is_some());
+        }
+      },
+    );
+  }
+
+  #[test]
+  fn test_statements_after_return_are_not_lowered() {
+    lower(
+      "func i32 f(bool c) { if c { return 1; return 2; } else { return 3; } return 4; }",
+      |module| {
+        let func = &module.get_funcs()[0];
+        assert_eq!(block_names(func), ["entry", "then", "else"]);
+        assert_eq!(func.get_insts(MirBlockId(1)).len(), 1);
+      },
+    );
+  }
+
+  #[test]
+  fn test_while_loop_blocks() {
+    lower(
+      "func i32 f(i32 n) { let i32 i = 0; while i < n { if i == 5 { break; } i = i + 1; } return i; }",
+      |module| {
+        let func = &module.get_funcs()[0];
+        assert_eq!(
+          block_names(func),
+          ["entry", "header", "body", "exit", "then", "merge"]
+        );
+        assert!(matches!(
+          func.get_terminator(MirBlockId(1)),
+          Some(Instruction::Branch(br)) if br.then_blk() == MirBlockId(2) && br.else_blk() == MirBlockId(3)
+        ));
+        // `break` leaves the loop, the end of the body jumps back to the header.
+        assert!(matches!(
+          func.get_terminator(MirBlockId(4)),
+          Some(Instruction::Jump(jmp)) if jmp.target() == MirBlockId(3)
+        ));
+        assert!(matches!(
+          func.get_terminator(MirBlockId(5)),
+          Some(Instruction::Jump(jmp)) if jmp.target() == MirBlockId(1)
+        ));
+      },
+    );
+  }
+
+  #[test]
+  fn test_assignment_stores_into_slot() {
+    lower(
+      "func i32 f() { let i32 x = 1; x = 2; return x; }",
+      |module| {
+        let func = &module.get_funcs()[0];
+        let insts = entry_insts(func);
+        assert!(matches!(insts[0], Instruction::Alloca(_)));
+        assert!(matches!(insts[1], Instruction::Store(_)));
+        assert!(matches!(insts[2], Instruction::Store(_)));
+        assert!(
+          !insts[..3]
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Load(_)))
+        );
+      },
+    );
+  }
+
+  #[test]
+  fn test_let_without_initializer_only_allocates() {
+    lower(
+      "func i32 f() { let i32 x; let bool b; x = 1; return x; }",
+      |module| {
+        let func = &module.get_funcs()[0];
+        let insts = entry_insts(func);
+        assert!(matches!(insts[0], Instruction::Alloca(_)));
+        assert!(matches!(insts[1], Instruction::Alloca(_)));
+        assert!(matches!(insts[2], Instruction::Store(_)));
+        assert_eq!(insts.len(), 5);
+      },
+    );
+  }
+
+  #[test]
+  fn test_assigned_parameters_are_spilled() {
+    lower(
+      "func i32 f(i32 n, i32 k) { let i32 x = n; while x > k { n = n - 1; x = x - 1; } return n; }",
+      |module| {
+        let func = &module.get_funcs()[0];
+        let insts = entry_insts(func);
+        assert!(matches!(insts[0], Instruction::Alloca(_)));
+        assert!(matches!(insts[1], Instruction::Load(load) if *load.from() == Place::Param(0)));
+        assert!(matches!(insts[2], Instruction::Store(_)));
+        // The load of `n` lowered before the assignment reads the slot too,
+        // `k` is never assigned and is read directly.
+        let params = (0..func.get_blocks().len())
+          .flat_map(|blk| func.get_insts(MirBlockId(blk)))
+          .filter_map(|(_, inst)| match inst {
+            Instruction::Load(load) => match load.from() {
+              Place::Param(idx) => Some(*idx),
+              Place::Local(_) => None,
+            },
+            _ => None,
+          })
+          .collect::<Vec<_>>();
+        assert_eq!(params, [0, 1]);
+      },
+    );
+  }
+
+  #[test]
+  fn test_instructions_carry_sema_types() {
+    lower(
+      "func f32 half(f32 x) { return x / 2.0; } func bool small(f32 x) { return half(x) < 1.0; }",
+      |module| {
+        let half = &module.get_funcs()[0];
+        assert_eq!(half.ret_type(), ValType::F32);
+        let insts = entry_insts(half);
+        assert!(matches!(&insts[1], Instruction::Div(div) if div.type_() == ValType::F32));
+        assert!(matches!(&insts[2], Instruction::Return(ret) if ret.type_() == ValType::F32));
+
+        let small = &module.get_funcs()[1];
+        let insts = entry_insts(small);
+        assert!(matches!(&insts[1], Instruction::Call(call) if call.type_() == ValType::F32));
+        assert!(matches!(&insts[2], Instruction::Cmp(cmp) if cmp.type_() == ValType::F32));
+        assert!(matches!(&insts[3], Instruction::Return(ret) if ret.type_() == ValType::Bool));
+      },
+    );
+  }
+
+  #[test]
+  fn test_call_to_later_function() {
+    lower(
+      "func i32 main() { return twice(4); } func i32 twice(i32 x) { return x * 2; }",
+      |module| {
+        let main = &module.get_funcs()[0];
+        assert!(matches!(
+          entry_insts(main)[0],
+          Instruction::Call(call) if call.callee() == MirFunctionId(1)
+        ));
+        assert_eq!(module.get_funcs()[1].get_params(), [ValType::I32]);
+      },
+    );
+  }
+
+  #[test]
+  fn test_implicit_function_end() {
+    lower(
+      "func void f(bool c) { if c { return; } } func i32 g() { while true { } }",
+      |module| {
+        let f = &module.get_funcs()[0];
+        assert_eq!(block_names(f), ["entry", "then", "merge"]);
+        for id in [MirBlockId(1), MirBlockId(2)] {
+          assert!(matches!(
+            f.get_terminator(id),
+            Some(Instruction::Return(ret)) if ret.type_() == ValType::Void
+          ));
+        }
+
+        let g = &module.get_funcs()[1];
+        assert_eq!(block_names(g), ["entry", "header", "body", "exit"]);
+        assert!(matches!(
+          g.get_terminator(MirBlockId(3)),
+          Some(Instruction::Unreachable)
+        ));
+      },
+    );
+  }
+
+  #[test]
+  fn test_instructions_carry_source_lines() {
+    lower(
+      "func i32 f(i32 x) {\n  let i32 y = x;\n  return y * 2;\n}",
+      |module| {
+        let func = &module.get_funcs()[0];
+        let lines = func
+          .get_insts(MirBlockId(0))
+          .map(|(id, _)| func.get_inst_pos(id).map(|pos| pos.line))
+          .collect::<Vec<_>>();
+        assert_eq!(lines, [2, 2, 2, 3, 3, 3].map(Some));
+      },
+    );
+  }
+}
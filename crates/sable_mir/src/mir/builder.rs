@@ -1,18 +1,21 @@
 use std::{cell::RefCell, rc::Rc};
 
-use sable_parser::info::ValType;
-
-use crate::lowering::NamendPlace;
+use sable_parser::{info::ValType, position::Position};
 
 use super::{
-  function::{MirFunctionId, block::MirBlockId},
+  function::{
+    MirFunctionId,
+    block::{MirBlock, MirBlockId},
+  },
   instruction::{
-    AddInst, CallInst, Instruction, LoadInst, MirInstId, MulInst, StoreInst,
+    AddInst, BranchInst, CallInst, CmpInst, CmpPredicate, Instruction, JumpInst, LoadInst,
+    MirInstId, MulInst, NotInst, PhiInst, StoreInst,
     alloca::AllocaInst,
     binary::{DivInst, SubInst},
     ret::ReturnInst,
   },
   module::MirModule,
+  place::Place,
   value::MirValue,
 };
 
@@ -20,6 +23,8 @@ pub struct Builder<'ctx> {
   selected: Option<MirBlockId>,
   selected_fn: MirFunctionId,
   module: Rc<RefCell<MirModule<'ctx>>>,
+  /// The source position given to the instructions built.
+  pos: Option<Position>,
 }
 
 impl<'ctx> Builder<'ctx> {
@@ -28,6 +33,7 @@ impl<'ctx> Builder<'ctx> {
       selected: None,
       selected_fn,
       module,
+      pos: None,
     }
   }
 
@@ -35,91 +41,150 @@ impl<'ctx> Builder<'ctx> {
     self.selected = Some(block_id);
   }
 
-  pub fn build_alloca(&mut self, type_: ValType) -> MirInstId {
+  pub fn selected(&self) -> Option<MirBlockId> {
+    self.selected
+  }
+
+  pub fn set_pos(&mut self, pos: Option<Position>) {
+    self.pos = pos;
+  }
+
+  pub fn pos(&self) -> Option<Position> {
+    self.pos.clone()
+  }
+
+  pub fn append_block(&mut self, name: &'ctx str) -> MirBlockId {
     let mut module = self.module.borrow_mut();
     let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = AllocaInst::new(type_);
-    let inst_id = func.add_inst(Instruction::Alloca(inst));
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
-    inst_id
+    func.add_block(MirBlock::new(name))
+  }
+
+  /// Whether the selected block already ends in a terminator.
+  pub fn is_terminated(&self) -> bool {
+    let module = self.module.borrow();
+    let func = module.get_func(self.selected_fn).unwrap();
+    self
+      .selected
+      .is_some_and(|block_id| func.get_terminator(block_id).is_some())
+  }
+
+  pub fn build_alloca(&mut self, type_: ValType) -> MirInstId {
+    self.append(Instruction::Alloca(AllocaInst::new(type_)))
   }
 
   pub fn build_store(&mut self, dest: MirInstId, value: MirValue) {
-    let mut module = self.module.borrow_mut();
-    let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Store(StoreInst::new(dest, value));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
+    self.append(Instruction::Store(StoreInst::new(dest, value)));
   }
 
-  pub fn build_load(&mut self, by: ValType, from: NamendPlace) -> MirInstId {
-    let mut module = self.module.borrow_mut();
-    let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Load(LoadInst::new(by, from));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
-    inst_id
+  pub fn build_load(&mut self, by: ValType, from: Place) -> MirInstId {
+    self.append(Instruction::Load(LoadInst::new(by, from)))
   }
 
-  pub fn build_add(&mut self, lhs: MirValue, rhs: MirValue) -> MirInstId {
-    let mut module = self.module.borrow_mut();
-    let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Add(AddInst::new(lhs, rhs));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
-    inst_id
+  pub fn build_add(&mut self, type_: ValType, lhs: MirValue, rhs: MirValue) -> MirInstId {
+    self.append(Instruction::Add(AddInst::new(type_, lhs, rhs)))
   }
 
-  pub fn build_sub(&mut self, lhs: MirValue, rhs: MirValue) -> MirInstId {
-    let mut module = self.module.borrow_mut();
-    let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Sub(SubInst::new(lhs, rhs));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
-    inst_id
+  pub fn build_sub(&mut self, type_: ValType, lhs: MirValue, rhs: MirValue) -> MirInstId {
+    self.append(Instruction::Sub(SubInst::new(type_, lhs, rhs)))
   }
 
-  pub fn build_mul(&mut self, lhs: MirValue, rhs: MirValue) -> MirInstId {
-    let mut module = self.module.borrow_mut();
-    let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Mul(MulInst::new(lhs, rhs));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
-    inst_id
+  pub fn build_mul(&mut self, type_: ValType, lhs: MirValue, rhs: MirValue) -> MirInstId {
+    self.append(Instruction::Mul(MulInst::new(type_, lhs, rhs)))
   }
 
-  pub fn build_div(&mut self, lhs: MirValue, rhs: MirValue) -> MirInstId {
-    let mut module = self.module.borrow_mut();
-    let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Div(DivInst::new(lhs, rhs));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
-    inst_id
+  pub fn build_div(&mut self, type_: ValType, lhs: MirValue, rhs: MirValue) -> MirInstId {
+    self.append(Instruction::Div(DivInst::new(type_, lhs, rhs)))
+  }
+
+  pub fn build_cmp(
+    &mut self,
+    predicate: CmpPredicate,
+    type_: ValType,
+    lhs: MirValue,
+    rhs: MirValue,
+  ) -> MirInstId {
+    self.append(Instruction::Cmp(CmpInst::new(predicate, type_, lhs, rhs)))
+  }
+
+  pub fn build_not(&mut self, value: MirValue) -> MirInstId {
+    self.append(Instruction::Not(NotInst::new(value)))
   }
 
   pub fn build_return(&mut self, type_: ValType, value: MirValue) {
+    self.append(Instruction::Return(ReturnInst::new(value, type_)));
+  }
+
+  pub fn build_branch(&mut self, cond: MirValue, then_blk: MirBlockId, else_blk: MirBlockId) {
+    self.append(Instruction::Branch(BranchInst::new(
+      cond, then_blk, else_blk,
+    )));
+  }
+
+  pub fn build_jump(&mut self, target: MirBlockId) {
+    self.append(Instruction::Jump(JumpInst::new(target)));
+  }
+
+  pub fn build_call(
+    &mut self,
+    callee: MirFunctionId,
+    type_: ValType,
+    args: Vec<MirValue>,
+  ) -> MirInstId {
+    self.append(Instruction::Call(CallInst::new(callee, type_, args)))
+  }
+
+  pub fn build_phi(&mut self, type_: ValType, incoming: Vec<(MirBlockId, MirValue)>) -> MirInstId {
+    self.append(Instruction::Phi(PhiInst::new(type_, incoming)))
+  }
+
+  pub fn build_unreachable(&mut self) {
+    self.append(Instruction::Unreachable);
+  }
+
+  /// Copies the parameter `idx` into a new slot at the start of the entry
+  /// block and points the loads of the parameter built so far at the slot,
+  /// so it can be assigned like a local. Returns the slot.
+  pub fn spill_param(&mut self, idx: usize) -> MirInstId {
     let mut module = self.module.borrow_mut();
     let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Return(ReturnInst::new(value, type_));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
+    let type_ = func.get_params()[idx].clone();
+
+    let loads = (0..func.get_blocks().len())
+      .flat_map(|blk| func.get_insts(MirBlockId(blk)))
+      .filter(
+        |(_, inst)| matches!(inst, Instruction::Load(load) if *load.from() == Place::Param(idx)),
+      )
+      .map(|(id, _)| id)
+      .collect::<Vec<_>>();
+    let slot = func.prepend_inst(
+      MirBlockId(0),
+      Instruction::Alloca(AllocaInst::new(type_.clone())),
+    );
+    for load in loads {
+      let mut inst = func.get_inst(load).unwrap().clone();
+      if let Instruction::Load(load_inst) = &mut inst {
+        load_inst.set_from(Place::Local(slot));
+      }
+      func.replace_inst(load, inst);
+    }
+
+    let value = func.insert_after(
+      slot,
+      Instruction::Load(LoadInst::new(type_, Place::Param(idx))),
+    );
+    func.insert_after(
+      value,
+      Instruction::Store(StoreInst::new(slot, MirValue::Inst(value))),
+    );
+    slot
   }
 
-  pub fn build_call(&mut self, callee: MirFunctionId, args: Vec<MirValue>) -> MirInstId {
+  /// Adds `inst` at the end of the selected block.
+  fn append(&mut self, inst: Instruction) -> MirInstId {
     let mut module = self.module.borrow_mut();
     let func = module.get_func_mut(self.selected_fn).unwrap();
-    let inst = Instruction::Call(CallInst::new(callee, args));
-    let inst_id = func.add_inst(inst);
-    let block_id = self.selected.unwrap();
-    func.get_block_mut(block_id).unwrap().expand(inst_id);
-    inst_id
+    let id = func.append_inst(self.selected.unwrap(), inst);
+    func.set_inst_pos(id, self.pos.clone());
+    id
   }
 }
@@ -0,0 +1,132 @@
+//! Graphviz renderings of the MIR, to be turned into pictures with `dot`.
+//!
+//! [`function_dot`] draws the control-flow graph of a function, a node per
+//! block listing its instructions as they are printed, with the edges of
+//! a `br` labeled by the condition they are taken on. [`call_graph_dot`]
+//! draws which functions of a module call which.
+
+use std::fmt::Write;
+
+use crate::{
+  analysis::call_graph::CallGraph,
+  mir::{
+    function::{MirFunction, MirFunctionId},
+    instruction::Instruction,
+    module::MirModule,
+    printer::{block_label, inst_text},
+  },
+};
+
+pub fn function_dot(module: &MirModule, func: &MirFunction) -> String {
+  let mut dot = format!("digraph \"{}\" {{\n", escape(func.name()));
+  dot.push_str("  node [shape=box, fontname=monospace];\n");
+  let order = func.get_layout();
+  for &blk in &order {
+    // `\l` ends a left-justified line.
+    let mut label = format!("{}:\\l", escape(&block_label(func, blk)));
+    for (id, inst) in func.get_insts(blk) {
+      let text = inst_text(Some(module), func, id, inst);
+      write!(label, "  {}\\l", escape(&text)).unwrap();
+    }
+    writeln!(dot, "  bb{} [label=\"{}\"];", blk.0, label).unwrap();
+  }
+  for &blk in &order {
+    match func.get_terminator(blk) {
+      Some(Instruction::Branch(br)) => {
+        writeln!(
+          dot,
+          "  bb{} -> bb{} [label=\"true\"];",
+          blk.0,
+          br.then_blk().0
+        )
+        .unwrap();
+        writeln!(
+          dot,
+          "  bb{} -> bb{} [label=\"false\"];",
+          blk.0,
+          br.else_blk().0
+        )
+        .unwrap();
+      }
+      Some(Instruction::Jump(jmp)) => {
+        writeln!(dot, "  bb{} -> bb{};", blk.0, jmp.target().0).unwrap();
+      }
+      _ => {}
+    }
+  }
+  dot.push_str("}\n");
+  dot
+}
+
+pub fn call_graph_dot(module: &MirModule) -> String {
+  let graph = CallGraph::new(module);
+  let mut dot = format!("digraph \"{}\" {{\n", escape(module.name()));
+  dot.push_str("  node [shape=box, fontname=monospace];\n");
+  for (idx, func) in module.get_funcs().iter().enumerate() {
+    writeln!(dot, "  f{} [label=\"{}\"];", idx, escape(func.name())).unwrap();
+  }
+  for idx in 0..module.get_funcs().len() {
+    for callee in graph.callees(MirFunctionId(idx)) {
+      writeln!(dot, "  f{} -> f{};", idx, callee.0).unwrap();
+    }
+  }
+  dot.push_str("}\n");
+  dot
+}
+
+/// `text` as the contents of a quoted DOT string.
+fn escape(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mir::parser::parse_module;
+
+  #[test]
+  fn test_renders_graphs() {
+    let module = parse_module(
+      "
+      module test
+      func main() -> i32 {
+      entry.0:
+        %0 = call i32 @pick(true)
+        ret i32 %0
+      }
+      func pick(bool %arg0) -> i32 {
+      entry.0:
+        %0 = load bool %arg0
+        br %0, then.1, merge.2
+      then.1:
+        jmp merge.2
+      merge.2:
+        %3 = phi i32 [1, entry.0], [2, then.1]
+        ret i32 %3
+      }
+      ",
+    )
+    .unwrap();
+    let pick = function_dot(&module, &module.get_funcs()[1]);
+    let expected = "\
+digraph \"pick\" {
+  node [shape=box, fontname=monospace];
+  bb0 [label=\"entry.0:\\l  %0 = load bool %arg0\\l  br %0, then.1, merge.2\\l\"];
+  bb1 [label=\"then.1:\\l  jmp merge.2\\l\"];
+  bb2 [label=\"merge.2:\\l  %3 = phi i32 [1, entry.0], [2, then.1]\\l  ret i32 %3\\l\"];
+  bb0 -> bb1 [label=\"true\"];
+  bb0 -> bb2 [label=\"false\"];
+  bb1 -> bb2;
+}
+";
+    assert_eq!(pick, expected);
+
+    let calls = call_graph_dot(&module);
+    assert!(
+      calls.contains("  f1 [label=\"pick\"];\n  f0 -> f1;\n}\n"),
+      "{}",
+      calls
+    );
+    assert!(function_dot(&module, &module.get_funcs()[0]).contains("call i32 @pick(true)"));
+  }
+}
@@ -1,28 +1,67 @@
+//! Functions and the bodies they own.
+//!
+//! Instructions are identified by a `MirInstId` that stays the same while
+//! other instructions are inserted, moved or erased around it. Each block
+//! lists the ids of its instructions in order, and the function records for
+//! every id the instructions referring to it, as an operand or as the slot
+//! they access, so the uses of a value can be found and replaced without
+//! scanning the body. Ids of erased instructions are not reused until
+//! [`MirFunction::compact`] renumbers the body.
+//!
+//! An instruction may also record the source position it was lowered from.
+//! The position stays with the id, so it survives the instruction being
+//! moved or replaced.
+
 use block::{MirBlock, MirBlockId};
-use sable_parser::info::ValType;
+use sable_parser::{
+  info::{InlineHint, ValType},
+  position::Position,
+};
 
-use super::instruction::{Instruction, MirInstId};
+use super::{
+  instruction::{Instruction, MirInstId},
+  value::MirValue,
+};
 
 pub mod block;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MirFunctionId(pub usize);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+struct InstNode {
+  inst: Instruction,
+  block: MirBlockId,
+  pos: Option<Position>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct MirFunction<'ctx> {
   name: &'ctx str,
-  instructions: Vec<Instruction>,
+  ret_type: ValType,
+  /// Every instruction by id, `None` once it is erased.
+  insts: Vec<Option<InstNode>>,
+  /// The instructions referring to each id, once per reference.
+  users: Vec<Vec<MirInstId>>,
   blocks: Vec<MirBlock<'ctx>>,
+  /// The blocks in the order they are laid out in. A block is placed after
+  /// the others when it gets its first instruction.
+  layout: Vec<MirBlockId>,
   params: Vec<ValType>,
+  inline: InlineHint,
 }
 
 impl<'ctx> MirFunction<'ctx> {
-  pub fn new(name: &'ctx str) -> Self {
+  pub fn new(name: &'ctx str, ret_type: ValType) -> Self {
     Self {
       name,
-      instructions: Vec::new(),
+      ret_type,
+      insts: Vec::new(),
+      users: Vec::new(),
       blocks: Vec::new(),
+      layout: Vec::new(),
       params: Vec::new(),
+      inline: InlineHint::Auto,
     }
   }
 
@@ -30,10 +69,20 @@ impl<'ctx> MirFunction<'ctx> {
     self.name
   }
 
-  pub fn add_inst(&mut self, inst: Instruction) -> MirInstId {
-    let id = MirInstId(self.instructions.len());
-    self.instructions.push(inst);
-    id
+  pub fn ret_type(&self) -> ValType {
+    self.ret_type.clone()
+  }
+
+  pub fn get_params(&self) -> &[ValType] {
+    &self.params
+  }
+
+  pub fn get_inline(&self) -> InlineHint {
+    self.inline
+  }
+
+  pub fn set_inline(&mut self, inline: InlineHint) {
+    self.inline = inline;
   }
 
   pub fn add_block(&mut self, block: MirBlock<'ctx>) -> MirBlockId {
@@ -46,14 +95,168 @@ impl<'ctx> MirFunction<'ctx> {
     self.params.push(param);
   }
 
-  pub fn get_insts(&self, blk: MirBlockId) -> &[Instruction] {
-    let start = blk.0;
-    let end = if blk.0 + 1 < self.blocks.len() {
-      self.blocks[blk.0 + 1].range().start
-    } else {
-      self.instructions.len()
-    };
-    &self.instructions[start..end]
+  /// Adds `inst` at the end of `blk`.
+  pub fn append_inst(&mut self, blk: MirBlockId, inst: Instruction) -> MirInstId {
+    let id = self.new_inst(blk, inst);
+    self.blocks[blk.0].insts_mut().push(id);
+    id
+  }
+
+  /// Adds `inst` at the start of `blk`.
+  pub fn prepend_inst(&mut self, blk: MirBlockId, inst: Instruction) -> MirInstId {
+    let id = self.new_inst(blk, inst);
+    self.blocks[blk.0].insts_mut().insert(0, id);
+    id
+  }
+
+  /// Adds `inst` right before the instruction `before`.
+  pub fn insert_before(&mut self, before: MirInstId, inst: Instruction) -> MirInstId {
+    let (blk, pos) = self.locate(before);
+    let id = self.new_inst(blk, inst);
+    self.blocks[blk.0].insts_mut().insert(pos, id);
+    id
+  }
+
+  /// Adds `inst` right after the instruction `after`.
+  pub fn insert_after(&mut self, after: MirInstId, inst: Instruction) -> MirInstId {
+    let (blk, pos) = self.locate(after);
+    let id = self.new_inst(blk, inst);
+    self.blocks[blk.0].insts_mut().insert(pos + 1, id);
+    id
+  }
+
+  /// Removes the instruction `id` from the function and returns it. Its
+  /// users, if any are left, still refer to it.
+  pub fn erase_inst(&mut self, id: MirInstId) -> Instruction {
+    let (blk, pos) = self.locate(id);
+    self.blocks[blk.0].insts_mut().remove(pos);
+    let node = self.insts[id.0].take().unwrap();
+    self.remove_users(id, &node.inst);
+    node.inst
+  }
+
+  /// Moves the instruction `id` right before the instruction `before`,
+  /// which may be in another block.
+  pub fn move_before(&mut self, id: MirInstId, before: MirInstId) {
+    self.detach(id);
+    let (blk, pos) = self.locate(before);
+    self.blocks[blk.0].insts_mut().insert(pos, id);
+    self.node_mut(id).block = blk;
+  }
+
+  /// Moves the instruction `id` to the end of `blk`.
+  pub fn move_to_end(&mut self, id: MirInstId, blk: MirBlockId) {
+    self.detach(id);
+    self.place(blk);
+    self.blocks[blk.0].insts_mut().push(id);
+    self.node_mut(id).block = blk;
+  }
+
+  /// Puts `inst` in place of the instruction `id`, returning the old one.
+  pub fn replace_inst(&mut self, id: MirInstId, inst: Instruction) -> Instruction {
+    self.add_users(id, &inst);
+    let old = std::mem::replace(&mut self.node_mut(id).inst, inst);
+    self.remove_users(id, &old);
+    old
+  }
+
+  /// Makes every instruction reading the value `id` read `value` instead.
+  /// Slot accesses are not reads of a value and keep referring to `id`.
+  pub fn replace_all_uses_with(&mut self, id: MirInstId, value: MirValue) {
+    let mut users = self.get_users(id).to_vec();
+    users.dedup();
+    for user in users {
+      let mut inst = self.get_inst(user).unwrap().clone();
+      for operand in inst.operands_mut() {
+        if *operand == MirValue::Inst(id) {
+          *operand = value.clone();
+        }
+      }
+      self.replace_inst(user, inst);
+    }
+  }
+
+  /// The instructions referring to `id`, in no particular order, once for
+  /// each of their references.
+  pub fn get_users(&self, id: MirInstId) -> &[MirInstId] {
+    self.users.get(id.0).map_or(&[], Vec::as_slice)
+  }
+
+  /// The instructions of `blk` in order, with their ids.
+  pub fn get_insts(
+    &self,
+    blk: MirBlockId,
+  ) -> impl DoubleEndedIterator<Item = (MirInstId, &Instruction)> + ExactSizeIterator {
+    self.blocks[blk.0]
+      .insts()
+      .iter()
+      .map(|id| (*id, &self.node(*id).inst))
+  }
+
+  pub fn get_inst(&self, id: MirInstId) -> Option<&Instruction> {
+    self.insts.get(id.0)?.as_ref().map(|node| &node.inst)
+  }
+
+  /// The block the instruction `id` is in.
+  pub fn get_inst_block(&self, id: MirInstId) -> Option<MirBlockId> {
+    self.insts.get(id.0)?.as_ref().map(|node| node.block)
+  }
+
+  /// The source position the instruction `id` comes from, if known.
+  pub fn get_inst_pos(&self, id: MirInstId) -> Option<&Position> {
+    self.insts.get(id.0)?.as_ref()?.pos.as_ref()
+  }
+
+  pub fn set_inst_pos(&mut self, id: MirInstId, pos: Option<Position>) {
+    self.node_mut(id).pos = pos;
+  }
+
+  /// How many instructions the body holds.
+  pub fn get_size(&self) -> usize {
+    self.blocks.iter().map(|blk| blk.insts().len()).sum()
+  }
+
+  /// How many ids were handed out, those of erased instructions included.
+  /// Every id is below it, so tables indexed by id can be sized with it.
+  pub fn get_inst_count(&self) -> usize {
+    self.insts.len()
+  }
+
+  pub fn get_blocks(&self) -> &[MirBlock<'ctx>] {
+    &self.blocks
+  }
+
+  /// Every block in the order it is laid out in, blocks that never had an
+  /// instruction last.
+  pub fn get_layout(&self) -> Vec<MirBlockId> {
+    let mut layout = self.layout.clone();
+    layout.extend(
+      (0..self.blocks.len())
+        .map(MirBlockId)
+        .filter(|blk| !self.layout.contains(blk)),
+    );
+    layout
+  }
+
+  /// Lays `blk` out right after `after`.
+  pub fn place_block_after(&mut self, blk: MirBlockId, after: MirBlockId) {
+    self.layout.retain(|other| *other != blk);
+    self.place(after);
+    let pos = self
+      .layout
+      .iter()
+      .position(|other| *other == after)
+      .unwrap();
+    self.layout.insert(pos + 1, blk);
+  }
+
+  /// The terminator of `blk`, if its last instruction is one.
+  pub fn get_terminator(&self, blk: MirBlockId) -> Option<&Instruction> {
+    self
+      .get_insts(blk)
+      .last()
+      .map(|(_, inst)| inst)
+      .filter(|inst| inst.is_terminator())
   }
 
   pub fn get_last_blk(&self) -> Option<MirBlockId> {
@@ -68,7 +271,175 @@ impl<'ctx> MirFunction<'ctx> {
     self.blocks.get(id.0)
   }
 
-  pub fn get_block_mut(&mut self, id: MirBlockId) -> Option<&mut MirBlock<'ctx>> {
-    self.blocks.get_mut(id.0)
+  /// Renumbers the instructions from 0 in layout order, so ids are dense
+  /// again after instructions were erased or inserted. References to ids
+  /// that no longer exist are left alone.
+  pub fn compact(&mut self) {
+    let mut remap = vec![None; self.insts.len()];
+    let mut order = Vec::new();
+    for blk in self.get_layout() {
+      for &id in self.blocks[blk.0].insts() {
+        remap[id.0] = Some(MirInstId(order.len()));
+        order.push(id);
+      }
+    }
+    let new_id = |id: MirInstId| remap.get(id.0).copied().flatten().unwrap_or(id);
+
+    let mut old = std::mem::take(&mut self.insts);
+    self.users.clear();
+    for (idx, id) in order.into_iter().enumerate() {
+      let mut node = old[id.0].take().unwrap();
+      for operand in node.inst.operands_mut() {
+        if let MirValue::Inst(id) = operand {
+          *id = new_id(*id);
+        }
+      }
+      if let Some(slot) = node.inst.slot() {
+        node.inst.set_slot(new_id(slot));
+      }
+      self.add_users(MirInstId(idx), &node.inst);
+      self.insts.push(Some(node));
+    }
+    for block in &mut self.blocks {
+      for id in block.insts_mut() {
+        *id = new_id(*id);
+      }
+    }
+  }
+
+  fn new_inst(&mut self, blk: MirBlockId, inst: Instruction) -> MirInstId {
+    let id = MirInstId(self.insts.len());
+    self.add_users(id, &inst);
+    self.insts.push(Some(InstNode {
+      inst,
+      block: blk,
+      pos: None,
+    }));
+    self.place(blk);
+    id
+  }
+
+  fn node(&self, id: MirInstId) -> &InstNode {
+    self.insts[id.0].as_ref().unwrap()
+  }
+
+  fn node_mut(&mut self, id: MirInstId) -> &mut InstNode {
+    self.insts[id.0].as_mut().unwrap()
+  }
+
+  /// The block of the instruction `id` and its index there.
+  fn locate(&self, id: MirInstId) -> (MirBlockId, usize) {
+    let blk = self.node(id).block;
+    let pos = self.blocks[blk.0]
+      .insts()
+      .iter()
+      .position(|inst| *inst == id);
+    (blk, pos.unwrap())
+  }
+
+  /// Takes the instruction `id` out of its block, to put it somewhere else.
+  fn detach(&mut self, id: MirInstId) {
+    let (blk, pos) = self.locate(id);
+    self.blocks[blk.0].insts_mut().remove(pos);
+  }
+
+  fn place(&mut self, blk: MirBlockId) {
+    if !self.layout.contains(&blk) {
+      self.layout.push(blk);
+    }
+  }
+
+  /// Records `user`, which holds `inst`, as a user of what `inst` refers to.
+  fn add_users(&mut self, user: MirInstId, inst: &Instruction) {
+    for used in inst.references() {
+      if self.users.len() <= used.0 {
+        self.users.resize(used.0 + 1, Vec::new());
+      }
+      self.users[used.0].push(user);
+    }
+  }
+
+  fn remove_users(&mut self, user: MirInstId, inst: &Instruction) {
+    for used in inst.references() {
+      let users = &mut self.users[used.0];
+      let pos = users.iter().position(|other| *other == user).unwrap();
+      users.swap_remove(pos);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mir::{
+    instruction::{AddInst, ReturnInst},
+    parser::parse_module,
+    value::Constant,
+  };
+
+  fn int(value: u64) -> MirValue {
+    MirValue::Constant(Constant::IntValue(ValType::I32, value))
+  }
+
+  #[test]
+  fn test_edits_keep_ids_and_uses() {
+    let mut module = parse_module(
+      "
+      module test
+      func f(i32 %arg0) -> i32 {
+      entry.0:
+        %x = load i32 %arg0
+        %y = add i32 %x, %x
+        jmp exit.1
+      exit.1:
+        %z = mul i32 %y, 2
+        ret i32 %z
+      }
+      ",
+    )
+    .unwrap();
+    let func = module.get_func_mut(MirFunctionId(0)).unwrap();
+    let [x, y, jmp, z, ret] = [0, 1, 2, 3, 4].map(MirInstId);
+    assert_eq!(func.get_users(x), [y, y]);
+
+    let add = Instruction::Add(AddInst::new(ValType::I32, MirValue::Inst(x), int(1)));
+    let w = func.insert_before(jmp, add);
+    func.replace_all_uses_with(y, MirValue::Inst(w));
+    assert_eq!(func.get_users(y), []);
+    assert_eq!(func.get_users(w), [z]);
+    func.erase_inst(y);
+    assert_eq!(func.get_users(x), [w]);
+    func.move_before(w, z);
+    assert_eq!(func.get_inst_block(w), Some(MirBlockId(1)));
+    assert_eq!(
+      func.to_string(),
+      "\
+func f(i32 %arg0) -> i32 {
+entry.0:
+  %0 = load i32 %arg0
+  jmp exit.1
+exit.1:
+  %5 = add i32 %0, 1
+  %3 = mul i32 %5, 2
+  ret i32 %3
+}"
+    );
+
+    func.set_inst_pos(ret, Some(Position::new(3, 1, 20..29)));
+    func.replace_inst(
+      ret,
+      Instruction::Return(ReturnInst::new(int(7), ValType::I32)),
+    );
+    assert_eq!(func.get_users(z), []);
+    func.compact();
+    // The position stays with the instruction through both.
+    assert_eq!(func.get_inst_pos(MirInstId(4)).map(|pos| pos.line), Some(3));
+    assert_eq!(func.get_inst_count(), 5);
+    assert_eq!(func.get_users(MirInstId(2)), [MirInstId(3)]);
+    assert!(
+      func
+        .to_string()
+        .ends_with("exit.1:\n  %2 = add i32 %0, 1\n  %3 = mul i32 %2, 2\n  ret i32 7\n}")
+    );
   }
 }
@@ -1,25 +1,22 @@
-use std::ops::Range;
-
 use crate::mir::instruction::MirInstId;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MirBlockId(pub usize);
 
-#[derive(Debug)]
+/// A basic block: the ids of its instructions in execution order. The
+/// instructions themselves are owned by the function, which keeps this
+/// list in sync as they are inserted, moved and erased.
+#[derive(Debug, Clone, PartialEq)]
 pub struct MirBlock<'ctx> {
   name: &'ctx str,
-  range: Range<usize>,
+  insts: Vec<MirInstId>,
 }
 
 impl<'ctx> MirBlock<'ctx> {
-  pub fn new(name: &'ctx str, start: MirInstId) -> Self {
-    let rng = start.0..start.0;
-    Self { name, range: rng }
-  }
-
-  pub fn expand(&mut self, inst: MirInstId) {
-    if self.range.end < inst.0 {
-      self.range.end = inst.0 + 1;
+  pub fn new(name: &'ctx str) -> Self {
+    Self {
+      name,
+      insts: Vec::new(),
     }
   }
 
@@ -27,7 +24,15 @@ impl<'ctx> MirBlock<'ctx> {
     self.name
   }
 
-  pub fn range(&self) -> Range<usize> {
-    self.range.clone()
+  pub fn insts(&self) -> &[MirInstId] {
+    &self.insts
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.insts.is_empty()
+  }
+
+  pub(super) fn insts_mut(&mut self) -> &mut Vec<MirInstId> {
+    &mut self.insts
   }
 }
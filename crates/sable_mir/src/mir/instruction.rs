@@ -1,19 +1,35 @@
 pub mod alloca;
 pub mod binary;
+pub mod branch;
+pub mod call;
+pub mod cmp;
 pub mod load;
+pub mod logic;
+pub mod phi;
 pub mod ret;
 pub mod store;
-pub mod call;
 
 pub use alloca::AllocaInst;
 pub use binary::AddInst;
 pub use binary::DivInst;
 pub use binary::MulInst;
 pub use binary::SubInst;
+pub use branch::BranchInst;
+pub use branch::JumpInst;
+pub use call::CallInst;
+pub use cmp::CmpInst;
+pub use cmp::CmpPredicate;
 pub use load::LoadInst;
+pub use logic::AndInst;
+pub use logic::NotInst;
+pub use logic::OrInst;
+pub use phi::PhiInst;
 pub use ret::ReturnInst;
 pub use store::StoreInst;
-pub use call::CallInst;
+
+use sable_parser::info::ValType;
+
+use super::{function::block::MirBlockId, place::Place, value::MirValue};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Instruction {
@@ -24,8 +40,147 @@ pub enum Instruction {
   Sub(SubInst),
   Mul(MulInst),
   Div(DivInst),
+  Cmp(CmpInst),
+  /// Logical and of two `bool` values, both always evaluated. Lowering
+  /// short-circuits `&&` through branches and a phi instead, so these only
+  /// come from textual MIR; the backends, interpreter and folder still
+  /// handle them.
+  And(AndInst),
+  /// Logical or, kept for textual MIR like `And`.
+  Or(OrInst),
+  Not(NotInst),
   Return(ReturnInst),
   Call(CallInst),
+  Branch(BranchInst),
+  Jump(JumpInst),
+  Phi(PhiInst),
+  /// Ends a block that control can never reach the end of.
+  Unreachable,
+}
+
+impl Instruction {
+  /// The type of the value the instruction produces. `alloca` produces a
+  /// slot rather than a value, so it has none.
+  pub fn value_type(&self) -> Option<ValType> {
+    match self {
+      Instruction::Load(load) => Some(load.by()),
+      Instruction::Add(inst) => Some(inst.type_()),
+      Instruction::Sub(inst) => Some(inst.type_()),
+      Instruction::Mul(inst) => Some(inst.type_()),
+      Instruction::Div(inst) => Some(inst.type_()),
+      Instruction::Call(call) => Some(call.type_()),
+      Instruction::Phi(phi) => Some(phi.type_()),
+      Instruction::Cmp(_) | Instruction::And(_) | Instruction::Or(_) | Instruction::Not(_) => {
+        Some(ValType::Bool)
+      }
+      _ => None,
+    }
+  }
+
+  /// The values the instruction reads. The slot a `store` writes to and the
+  /// place a `load` reads from are not values.
+  pub fn operands(&self) -> Vec<&MirValue> {
+    match self {
+      Instruction::Store(store) => vec![store.value()],
+      Instruction::Add(inst) => vec![inst.lhs(), inst.rhs()],
+      Instruction::Sub(inst) => vec![inst.lhs(), inst.rhs()],
+      Instruction::Mul(inst) => vec![inst.lhs(), inst.rhs()],
+      Instruction::Div(inst) => vec![inst.lhs(), inst.rhs()],
+      Instruction::Cmp(cmp) => vec![cmp.lhs(), cmp.rhs()],
+      Instruction::And(and) => vec![and.lhs(), and.rhs()],
+      Instruction::Or(or) => vec![or.lhs(), or.rhs()],
+      Instruction::Not(not) => vec![not.value()],
+      Instruction::Return(ret) => vec![ret.ret_value()],
+      Instruction::Call(call) => call.args().iter().collect(),
+      Instruction::Branch(branch) => vec![branch.cond()],
+      Instruction::Phi(phi) => phi.incoming().iter().map(|(_, value)| value).collect(),
+      Instruction::Alloca(_)
+      | Instruction::Load(_)
+      | Instruction::Jump(_)
+      | Instruction::Unreachable => Vec::new(),
+    }
+  }
+
+  /// The values the instruction reads, for rewriting them in place.
+  pub fn operands_mut(&mut self) -> Vec<&mut MirValue> {
+    match self {
+      Instruction::Store(store) => vec![store.value_mut()],
+      Instruction::Add(inst) => vec![&mut inst.lhs, &mut inst.rhs],
+      Instruction::Sub(inst) => vec![&mut inst.lhs, &mut inst.rhs],
+      Instruction::Mul(inst) => vec![&mut inst.lhs, &mut inst.rhs],
+      Instruction::Div(inst) => vec![&mut inst.lhs, &mut inst.rhs],
+      Instruction::Cmp(cmp) => vec![&mut cmp.lhs, &mut cmp.rhs],
+      Instruction::And(and) => vec![&mut and.lhs, &mut and.rhs],
+      Instruction::Or(or) => vec![&mut or.lhs, &mut or.rhs],
+      Instruction::Not(not) => vec![&mut not.value],
+      Instruction::Return(ret) => vec![ret.ret_value_mut()],
+      Instruction::Call(call) => call.args_mut().iter_mut().collect(),
+      Instruction::Branch(branch) => vec![branch.cond_mut()],
+      Instruction::Phi(phi) => phi
+        .incoming_mut()
+        .iter_mut()
+        .map(|(_, value)| value)
+        .collect(),
+      Instruction::Alloca(_)
+      | Instruction::Load(_)
+      | Instruction::Jump(_)
+      | Instruction::Unreachable => Vec::new(),
+    }
+  }
+
+  /// The stack slot a `store` writes to or a `load` reads from.
+  pub fn slot(&self) -> Option<MirInstId> {
+    match self {
+      Instruction::Store(store) => Some(store.target()),
+      Instruction::Load(load) => match load.from() {
+        Place::Local(slot) => Some(*slot),
+        Place::Param(_) => None,
+      },
+      _ => None,
+    }
+  }
+
+  /// Points the `store` or `load` of a slot at `slot` instead.
+  pub fn set_slot(&mut self, slot: MirInstId) {
+    match self {
+      Instruction::Store(store) => store.set_target(slot),
+      Instruction::Load(load) => {
+        if let Place::Local(_) = load.from() {
+          load.set_from(Place::Local(slot));
+        }
+      }
+      _ => {}
+    }
+  }
+
+  /// The instructions this one refers to: the values it reads and the slot
+  /// it accesses.
+  pub fn references(&self) -> Vec<MirInstId> {
+    let values = self.operands().into_iter().filter_map(|value| match value {
+      MirValue::Inst(id) => Some(*id),
+      MirValue::Constant(_) => None,
+    });
+    values.chain(self.slot()).collect()
+  }
+
+  /// The blocks a terminator transfers control to.
+  pub fn successors(&self) -> Vec<MirBlockId> {
+    match self {
+      Instruction::Branch(branch) => vec![branch.then_blk(), branch.else_blk()],
+      Instruction::Jump(jump) => vec![jump.target()],
+      _ => Vec::new(),
+    }
+  }
+
+  pub fn is_terminator(&self) -> bool {
+    matches!(
+      self,
+      Instruction::Return(_)
+        | Instruction::Branch(_)
+        | Instruction::Jump(_)
+        | Instruction::Unreachable
+    )
+  }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1,14 +1,20 @@
+use sable_parser::info::ValType;
+
 use crate::mir::value::MirValue;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AddInst {
+  pub type_: ValType,
   pub lhs: MirValue,
   pub rhs: MirValue,
 }
 
 impl AddInst {
-  pub fn new(lhs: MirValue, rhs: MirValue) -> Self {
-    AddInst { lhs, rhs }
+  pub fn new(type_: ValType, lhs: MirValue, rhs: MirValue) -> Self {
+    AddInst { type_, lhs, rhs }
+  }
+  pub fn type_(&self) -> ValType {
+    self.type_.clone()
   }
   pub fn lhs(&self) -> &MirValue {
     &self.lhs
@@ -20,13 +26,17 @@ impl AddInst {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SubInst {
+  pub type_: ValType,
   pub lhs: MirValue,
   pub rhs: MirValue,
 }
 
 impl SubInst {
-  pub fn new(lhs: MirValue, rhs: MirValue) -> Self {
-    SubInst { lhs, rhs }
+  pub fn new(type_: ValType, lhs: MirValue, rhs: MirValue) -> Self {
+    SubInst { type_, lhs, rhs }
+  }
+  pub fn type_(&self) -> ValType {
+    self.type_.clone()
   }
   pub fn lhs(&self) -> &MirValue {
     &self.lhs
@@ -38,13 +48,17 @@ impl SubInst {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MulInst {
+  pub type_: ValType,
   pub lhs: MirValue,
   pub rhs: MirValue,
 }
 
 impl MulInst {
-  pub fn new(lhs: MirValue, rhs: MirValue) -> Self {
-    MulInst { lhs, rhs }
+  pub fn new(type_: ValType, lhs: MirValue, rhs: MirValue) -> Self {
+    MulInst { type_, lhs, rhs }
+  }
+  pub fn type_(&self) -> ValType {
+    self.type_.clone()
   }
   pub fn lhs(&self) -> &MirValue {
     &self.lhs
@@ -56,13 +70,17 @@ impl MulInst {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DivInst {
+  pub type_: ValType,
   pub lhs: MirValue,
   pub rhs: MirValue,
 }
 
 impl DivInst {
-  pub fn new(lhs: MirValue, rhs: MirValue) -> Self {
-    DivInst { lhs, rhs }
+  pub fn new(type_: ValType, lhs: MirValue, rhs: MirValue) -> Self {
+    DivInst { type_, lhs, rhs }
+  }
+  pub fn type_(&self) -> ValType {
+    self.type_.clone()
   }
   pub fn lhs(&self) -> &MirValue {
     &self.lhs
@@ -70,4 +88,4 @@ impl DivInst {
   pub fn rhs(&self) -> &MirValue {
     &self.rhs
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,49 @@
+use crate::mir::{function::block::MirBlockId, value::MirValue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchInst {
+  cond: MirValue,
+  then_blk: MirBlockId,
+  else_blk: MirBlockId,
+}
+
+impl BranchInst {
+  pub fn new(cond: MirValue, then_blk: MirBlockId, else_blk: MirBlockId) -> Self {
+    BranchInst {
+      cond,
+      then_blk,
+      else_blk,
+    }
+  }
+
+  pub fn cond(&self) -> &MirValue {
+    &self.cond
+  }
+
+  pub fn cond_mut(&mut self) -> &mut MirValue {
+    &mut self.cond
+  }
+
+  pub fn then_blk(&self) -> MirBlockId {
+    self.then_blk
+  }
+
+  pub fn else_blk(&self) -> MirBlockId {
+    self.else_blk
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpInst {
+  target: MirBlockId,
+}
+
+impl JumpInst {
+  pub fn new(target: MirBlockId) -> Self {
+    JumpInst { target }
+  }
+
+  pub fn target(&self) -> MirBlockId {
+    self.target
+  }
+}
@@ -1,21 +1,36 @@
+use sable_parser::info::ValType;
+
 use crate::mir::{function::MirFunctionId, value::MirValue};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallInst {
   callee: MirFunctionId,
+  type_: ValType,
   args: Vec<MirValue>,
 }
 
 impl CallInst {
-  pub fn new(callee: MirFunctionId, args: Vec<MirValue>) -> Self {
-    Self { callee, args }
+  pub fn new(callee: MirFunctionId, type_: ValType, args: Vec<MirValue>) -> Self {
+    Self {
+      callee,
+      type_,
+      args,
+    }
   }
 
   pub fn callee(&self) -> MirFunctionId {
     self.callee
   }
 
+  pub fn type_(&self) -> ValType {
+    self.type_.clone()
+  }
+
   pub fn args(&self) -> &[MirValue] {
     &self.args
   }
+
+  pub fn args_mut(&mut self) -> &mut [MirValue] {
+    &mut self.args
+  }
 }
@@ -0,0 +1,59 @@
+use sable_parser::info::{OperatorType, ValType};
+
+use crate::mir::value::MirValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpPredicate {
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+}
+
+impl CmpPredicate {
+  pub fn from_operator(operator: &OperatorType) -> Option<Self> {
+    match operator {
+      OperatorType::Eq => Some(CmpPredicate::Eq),
+      OperatorType::NotEq => Some(CmpPredicate::Ne),
+      OperatorType::Lt => Some(CmpPredicate::Lt),
+      OperatorType::Le => Some(CmpPredicate::Le),
+      OperatorType::Gt => Some(CmpPredicate::Gt),
+      OperatorType::Ge => Some(CmpPredicate::Ge),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CmpInst {
+  pub predicate: CmpPredicate,
+  /// Type of the compared operands; the result is always `bool`.
+  pub type_: ValType,
+  pub lhs: MirValue,
+  pub rhs: MirValue,
+}
+
+impl CmpInst {
+  pub fn new(predicate: CmpPredicate, type_: ValType, lhs: MirValue, rhs: MirValue) -> Self {
+    CmpInst {
+      predicate,
+      type_,
+      lhs,
+      rhs,
+    }
+  }
+  pub fn predicate(&self) -> CmpPredicate {
+    self.predicate
+  }
+  pub fn type_(&self) -> ValType {
+    self.type_.clone()
+  }
+  pub fn lhs(&self) -> &MirValue {
+    &self.lhs
+  }
+  pub fn rhs(&self) -> &MirValue {
+    &self.rhs
+  }
+}
@@ -1,16 +1,15 @@
 use sable_parser::info::ValType;
 
-use crate::lowering::NamendPlace;
-
+use crate::mir::place::Place;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LoadInst {
   by: ValType,
-  from: NamendPlace,
+  from: Place,
 }
 
 impl LoadInst {
-  pub fn new(by: ValType, from: NamendPlace) -> Self {
+  pub fn new(by: ValType, from: Place) -> Self {
     LoadInst { by, from }
   }
 
@@ -18,7 +17,11 @@ impl LoadInst {
     self.by.clone()
   }
 
-  pub fn from(&self) -> &NamendPlace {
+  pub fn from(&self) -> &Place {
     &self.from
   }
+
+  pub fn set_from(&mut self, from: Place) {
+    self.from = from;
+  }
 }
@@ -0,0 +1,51 @@
+use crate::mir::value::MirValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AndInst {
+  pub lhs: MirValue,
+  pub rhs: MirValue,
+}
+
+impl AndInst {
+  pub fn new(lhs: MirValue, rhs: MirValue) -> Self {
+    AndInst { lhs, rhs }
+  }
+  pub fn lhs(&self) -> &MirValue {
+    &self.lhs
+  }
+  pub fn rhs(&self) -> &MirValue {
+    &self.rhs
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrInst {
+  pub lhs: MirValue,
+  pub rhs: MirValue,
+}
+
+impl OrInst {
+  pub fn new(lhs: MirValue, rhs: MirValue) -> Self {
+    OrInst { lhs, rhs }
+  }
+  pub fn lhs(&self) -> &MirValue {
+    &self.lhs
+  }
+  pub fn rhs(&self) -> &MirValue {
+    &self.rhs
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotInst {
+  pub value: MirValue,
+}
+
+impl NotInst {
+  pub fn new(value: MirValue) -> Self {
+    NotInst { value }
+  }
+  pub fn value(&self) -> &MirValue {
+    &self.value
+  }
+}
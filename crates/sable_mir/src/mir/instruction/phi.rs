@@ -0,0 +1,39 @@
+use sable_parser::info::ValType;
+
+use crate::mir::{function::block::MirBlockId, value::MirValue};
+
+/// Selects a value depending on the block control came from. Phis are
+/// grouped at the start of their block and take their values in parallel
+/// on entry, with one incoming value per predecessor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhiInst {
+  type_: ValType,
+  incoming: Vec<(MirBlockId, MirValue)>,
+}
+
+impl PhiInst {
+  pub fn new(type_: ValType, incoming: Vec<(MirBlockId, MirValue)>) -> Self {
+    PhiInst { type_, incoming }
+  }
+
+  pub fn type_(&self) -> ValType {
+    self.type_.clone()
+  }
+
+  pub fn incoming(&self) -> &[(MirBlockId, MirValue)] {
+    &self.incoming
+  }
+
+  pub fn incoming_mut(&mut self) -> &mut Vec<(MirBlockId, MirValue)> {
+    &mut self.incoming
+  }
+
+  /// The value taken when control comes from `pred`.
+  pub fn get_incoming(&self, pred: MirBlockId) -> Option<&MirValue> {
+    self
+      .incoming
+      .iter()
+      .find(|(blk, _)| *blk == pred)
+      .map(|(_, value)| value)
+  }
+}
@@ -5,7 +5,7 @@ use crate::mir::value::MirValue;
 #[derive(Debug, Clone, PartialEq)]
 pub struct ReturnInst {
   ret_value: MirValue,
-  type_: ValType
+  type_: ValType,
 }
 
 impl ReturnInst {
@@ -17,6 +17,10 @@ impl ReturnInst {
     &self.ret_value
   }
 
+  pub fn ret_value_mut(&mut self) -> &mut MirValue {
+    &mut self.ret_value
+  }
+
   pub fn type_(&self) -> ValType {
     self.type_.clone()
   }
@@ -15,7 +15,13 @@ impl StoreInst {
   pub fn target(&self) -> MirInstId {
     self.target
   }
+  pub fn set_target(&mut self, target: MirInstId) {
+    self.target = target;
+  }
   pub fn value(&self) -> &MirValue {
     &self.value
   }
+  pub fn value_mut(&mut self) -> &mut MirValue {
+    &mut self.value
+  }
 }
@@ -2,4 +2,8 @@ pub mod module;
 pub mod function;
 pub mod instruction;
 pub mod builder;
-pub mod value;
\ No newline at end of file
+pub mod value;
+pub mod place;
+pub mod parser;
+pub mod printer;
+pub mod dot;
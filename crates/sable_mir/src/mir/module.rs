@@ -1,8 +1,11 @@
 use super::function::{MirFunction, MirFunctionId};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct MirModule<'ctx> {
   name: &'ctx str,
+  /// The file the positions of the instructions refer to. Backends emit
+  /// line tables only when it is set.
+  source_file: Option<&'ctx str>,
   funcs: Vec<MirFunction<'ctx>>,
 }
 
@@ -10,6 +13,7 @@ impl<'ctx> MirModule<'ctx> {
   pub fn new(name: &'ctx str) -> Self {
     Self {
       name,
+      source_file: None,
       funcs: Vec::new(),
     }
   }
@@ -18,6 +22,14 @@ impl<'ctx> MirModule<'ctx> {
     self.name
   }
 
+  pub fn source_file(&self) -> Option<&'ctx str> {
+    self.source_file
+  }
+
+  pub fn set_source_file(&mut self, source_file: Option<&'ctx str>) {
+    self.source_file = source_file;
+  }
+
   pub fn add_func(&mut self, func: MirFunction<'ctx>) -> MirFunctionId {
     let id = MirFunctionId(self.funcs.len());
     self.funcs.push(func);
@@ -35,4 +47,12 @@ impl<'ctx> MirModule<'ctx> {
   pub fn get_func_mut(&mut self, id: MirFunctionId) -> Option<&mut MirFunction<'ctx>> {
     self.funcs.get_mut(id.0)
   }
+
+  /// Renumbers the instructions of every function, see
+  /// [`MirFunction::compact`].
+  pub fn compact(&mut self) {
+    for func in &mut self.funcs {
+      func.compact();
+    }
+  }
 }
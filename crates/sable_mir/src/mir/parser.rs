@@ -0,0 +1,746 @@
+//! Reads the textual MIR written by the printer back into a [`MirModule`].
+//!
+//! The format is line based, `;` starts a comment that runs to the end of
+//! the line:
+//!
+//! ```text
+//! module   := "module" name function*
+//! function := ["inline" | "noinline"] "func" name "(" [type param ("," type param)*] ")" "->" type "{"
+//!             block*
+//!             "}"
+//! param    := "%arg0" | "%arg1" | ...      ; numbered in order
+//! block    := name "." id ":" inst*        ; ids are 0, 1, ... in any order
+//! inst     := ["%" name "="] opcode operands
+//! value    := "%" name | integer | float | "true" | "false" | "null"
+//! ```
+//!
+//! The opcodes and their operands are the ones the printer emits:
+//!
+//! ```text
+//! %a = alloca i32            store %a, 1           %v = load i32 %a
+//! %v = add i32 %x, 1         (sub, mul, div)       %c = cmp lt i32 %x, %y
+//! %c = and %x, %y            (or)                  %c = not %x
+//! %r = call i32 @f(%x, 2)    br %c, then.1, else.2 jmp merge.3
+//! ret i32 %v                 ret void              unreachable
+//! %p = phi i32 [%x, entry.0], [%y, body.2]
+//! ```
+//!
+//! Instructions are numbered in the order they appear, so `%` names are
+//! only labels; printing a parsed module renumbers them. Integer literals
+//! are `i32` and float literals `f32` constants, either may be negative.
+
+use std::{collections::HashMap, ops::Range};
+
+use sable_parser::info::{InlineHint, ValType};
+
+use crate::{
+  error::MirParseError,
+  mir::{
+    function::{
+      MirFunction, MirFunctionId,
+      block::{MirBlock, MirBlockId},
+    },
+    instruction::{
+      AddInst, AllocaInst, AndInst, BranchInst, CallInst, CmpInst, CmpPredicate, DivInst,
+      Instruction, JumpInst, LoadInst, MirInstId, MulInst, NotInst, OrInst, PhiInst, ReturnInst,
+      StoreInst, SubInst,
+    },
+    module::MirModule,
+    place::Place,
+    printer::produces_value,
+    value::{Constant, MirValue},
+  },
+};
+
+type Result<T> = std::result::Result<T, MirParseError>;
+
+/// Parses a whole module.
+pub fn parse_module(source: &str) -> Result<MirModule<'_>> {
+  let lines = significant_lines(source);
+  let mut lines = lines.into_iter().peekable();
+
+  let mut cursor = match lines.next() {
+    Some(line) => line,
+    None => {
+      return Err(MirParseError::new(
+        "expected `module`",
+        source.len()..source.len(),
+      ));
+    }
+  };
+  cursor.expect("module")?;
+  let (name, _) = cursor.name()?;
+  cursor.expect_end()?;
+
+  let funcs = lines.clone().filter_map(|mut line| {
+    line.inline_hint();
+    line.eat("func").then(|| line.name().ok()).flatten()
+  });
+  let mut func_ids = HashMap::new();
+  for (idx, (name, span)) in funcs.enumerate() {
+    if func_ids.insert(name, MirFunctionId(idx)).is_some() {
+      return Err(MirParseError::new(
+        format!("function `{}` is already defined", name),
+        span,
+      ));
+    }
+  }
+
+  let mut module = MirModule::new(name);
+  while let Some(header) = lines.next() {
+    let mut body = Vec::new();
+    loop {
+      match lines.next() {
+        Some(line) if line.rest() == "}" => break,
+        Some(line) => body.push(line),
+        None => {
+          return Err(MirParseError::new(
+            "expected `}` closing the function",
+            source.len()..source.len(),
+          ));
+        }
+      }
+    }
+    let func = FunctionParser::new(&func_ids).parse(header, body)?;
+    module.add_func(func);
+  }
+
+  Ok(module)
+}
+
+/// The non-empty lines of `source` with comments removed.
+fn significant_lines(source: &str) -> Vec<Cursor<'_>> {
+  let mut lines = Vec::new();
+  let mut offset = 0;
+  for line in source.split_inclusive('\n') {
+    let code = line.split(';').next().unwrap().trim_end();
+    if !code.trim().is_empty() {
+      lines.push(Cursor::new(code, offset));
+    }
+    offset += line.len();
+  }
+  lines
+}
+
+/// A position within one line of the source.
+#[derive(Clone)]
+struct Cursor<'s> {
+  text: &'s str,
+  base: usize,
+  pos: usize,
+}
+
+impl<'s> Cursor<'s> {
+  fn new(text: &'s str, base: usize) -> Self {
+    Self { text, base, pos: 0 }
+  }
+
+  fn skip_ws(&mut self) {
+    let rest = &self.text[self.pos..];
+    self.pos += rest.len() - rest.trim_start().len();
+  }
+
+  fn rest(&self) -> &'s str {
+    self.text[self.pos..].trim()
+  }
+
+  fn span(&self, start: usize, end: usize) -> Range<usize> {
+    self.base + start..self.base + end
+  }
+
+  /// The span of the next token, or of the end of the line.
+  fn next_span(&mut self) -> Range<usize> {
+    self.skip_ws();
+    let len = self.text[self.pos..]
+      .find(char::is_whitespace)
+      .unwrap_or(self.text.len() - self.pos)
+      .max(1);
+    self.span(self.pos, self.pos + len)
+  }
+
+  fn error<T>(&mut self, message: impl Into<String>) -> Result<T> {
+    let span = self.next_span();
+    Err(MirParseError::new(message, span))
+  }
+
+  fn eat(&mut self, token: &str) -> bool {
+    self.skip_ws();
+    if self.text[self.pos..].starts_with(token) {
+      self.pos += token.len();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, token: &str) -> Result<()> {
+    if self.eat(token) {
+      Ok(())
+    } else {
+      self.error(format!("expected `{}`", token))
+    }
+  }
+
+  fn expect_end(&mut self) -> Result<()> {
+    self.skip_ws();
+    if self.pos == self.text.len() {
+      Ok(())
+    } else {
+      self.error(format!("unexpected `{}`", self.rest()))
+    }
+  }
+
+  /// A run of name characters: letters, digits, `_`, and the `.`, `-` and
+  /// `+` of labels and float literals.
+  /// Reads the annotation that may start a function header.
+  fn inline_hint(&mut self) -> InlineHint {
+    if self.eat("inline ") {
+      InlineHint::Always
+    } else if self.eat("noinline ") {
+      InlineHint::Never
+    } else {
+      InlineHint::Auto
+    }
+  }
+
+  fn word(&mut self) -> Option<(&'s str, Range<usize>)> {
+    self.skip_ws();
+    let start = self.pos;
+    let rest = &self.text[start..];
+    let len = rest
+      .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+')))
+      .unwrap_or(rest.len());
+    if len == 0 {
+      return None;
+    }
+    self.pos += len;
+    Some((&rest[..len], self.span(start, start + len)))
+  }
+
+  fn name(&mut self) -> Result<(&'s str, Range<usize>)> {
+    match self.word() {
+      Some(word) => Ok(word),
+      None => self.error("expected a name"),
+    }
+  }
+
+  fn type_(&mut self) -> Result<ValType> {
+    let (word, span) = self.name()?;
+    match word {
+      "i32" => Ok(ValType::I32),
+      "f32" => Ok(ValType::F32),
+      "bool" => Ok(ValType::Bool),
+      "void" => Ok(ValType::Void),
+      _ => Err(MirParseError::new(format!("unknown type `{}`", word), span)),
+    }
+  }
+
+  /// A `%` name, returned without the `%`.
+  fn local(&mut self) -> Result<(&'s str, Range<usize>)> {
+    self.skip_ws();
+    let start = self.pos;
+    self.expect("%")?;
+    let (name, span) = self.name()?;
+    Ok((name, self.base + start..span.end))
+  }
+}
+
+fn parse_label(cursor: &mut Cursor<'_>) -> Result<(usize, Range<usize>)> {
+  let (label, span) = cursor.name()?;
+  label
+    .rsplit_once('.')
+    .and_then(|(_, id)| id.parse().ok())
+    .map(|id| (id, span.clone()))
+    .ok_or_else(|| {
+      MirParseError::new(
+        format!("expected a label like `entry.0`, found `{}`", label),
+        span,
+      )
+    })
+}
+
+struct FunctionParser<'m, 's> {
+  func_ids: &'m HashMap<&'s str, MirFunctionId>,
+  params: usize,
+  values: HashMap<&'s str, MirInstId>,
+  blocks: Vec<&'s str>,
+}
+
+impl<'m, 's> FunctionParser<'m, 's> {
+  fn new(func_ids: &'m HashMap<&'s str, MirFunctionId>) -> Self {
+    Self {
+      func_ids,
+      params: 0,
+      values: HashMap::new(),
+      blocks: Vec::new(),
+    }
+  }
+
+  fn parse(mut self, mut header: Cursor<'s>, body: Vec<Cursor<'s>>) -> Result<MirFunction<'s>> {
+    let inline = header.inline_hint();
+    header.expect("func")?;
+    let (name, _) = header.name()?;
+    header.expect("(")?;
+    let mut params = Vec::new();
+    if !header.eat(")") {
+      loop {
+        let type_ = header.type_()?;
+        let (param, span) = header.local()?;
+        if param != format!("arg{}", params.len()) {
+          return Err(MirParseError::new(
+            format!("expected parameter `%arg{}`", params.len()),
+            span,
+          ));
+        }
+        params.push(type_);
+        if header.eat(")") {
+          break;
+        }
+        header.expect(",")?;
+      }
+    }
+    header.expect("->")?;
+    let ret_type = header.type_()?;
+    header.expect("{")?;
+    header.expect_end()?;
+
+    let mut func = MirFunction::new(name, ret_type);
+    func.set_inline(inline);
+    self.params = params.len();
+    for param in params {
+      func.add_param(param);
+    }
+
+    let insts = self.collect_names(&body)?;
+    for blk in &self.blocks {
+      func.add_block(MirBlock::new(blk));
+    }
+    for (blk, mut line) in insts {
+      let inst = self.parse_inst(&mut line)?;
+      func.append_inst(blk, inst);
+    }
+
+    Ok(func)
+  }
+
+  /// Numbers the instructions of the body and records the blocks and value
+  /// names it defines, so instructions can refer to later ones.
+  fn collect_names(&mut self, body: &[Cursor<'s>]) -> Result<Vec<(MirBlockId, Cursor<'s>)>> {
+    let mut labels: Vec<(usize, &'s str, Range<usize>)> = Vec::new();
+    let mut insts = Vec::new();
+    for line in body {
+      let mut cursor = line.clone();
+      if let Some(label) = line.rest().strip_suffix(':') {
+        let (id, span) = parse_label(&mut Cursor::new(label, cursor.base + cursor.pos))?;
+        if labels.iter().any(|(other, ..)| *other == id) {
+          return Err(MirParseError::new(
+            format!("block `{}` is already defined", label),
+            span,
+          ));
+        }
+        let name = label.rsplit_once('.').unwrap().0;
+        labels.push((id, name, span));
+        continue;
+      }
+
+      let Some((blk, ..)) = labels.last() else {
+        return cursor.error("expected a block label before the first instruction");
+      };
+      if cursor.rest().starts_with('%') {
+        let (name, span) = cursor.local()?;
+        let id = MirInstId(insts.len());
+        if self.values.insert(name, id).is_some() {
+          return Err(MirParseError::new(
+            format!("value `%{}` is already defined", name),
+            span,
+          ));
+        }
+      }
+      insts.push((MirBlockId(*blk), line.clone()));
+    }
+
+    labels.sort_by_key(|(id, ..)| *id);
+    for (idx, (id, name, span)) in labels.into_iter().enumerate() {
+      if id != idx {
+        return Err(MirParseError::new(
+          format!(
+            "expected block id {}, blocks are numbered from 0 without gaps",
+            idx
+          ),
+          span,
+        ));
+      }
+      self.blocks.push(name);
+    }
+    Ok(insts)
+  }
+
+  fn block(&self, cursor: &mut Cursor<'s>) -> Result<MirBlockId> {
+    let (id, span) = parse_label(cursor)?;
+    let label = &cursor.text[span.start - cursor.base..span.end - cursor.base];
+    match self.blocks.get(id) {
+      Some(name) if label.rsplit_once('.').unwrap().0 == *name => Ok(MirBlockId(id)),
+      _ => Err(MirParseError::new(
+        format!("unknown block `{}`", label),
+        span,
+      )),
+    }
+  }
+
+  fn inst_id(&self, cursor: &mut Cursor<'s>) -> Result<MirInstId> {
+    let (name, span) = cursor.local()?;
+    match self.values.get(name) {
+      Some(id) => Ok(*id),
+      None if self.param(name).is_some() => Err(MirParseError::new(
+        "parameters can only be used by `load`",
+        span,
+      )),
+      None => Err(MirParseError::new(
+        format!("undefined value `%{}`", name),
+        span,
+      )),
+    }
+  }
+
+  fn param(&self, name: &str) -> Option<usize> {
+    name
+      .strip_prefix("arg")
+      .and_then(|idx| idx.parse().ok())
+      .filter(|idx| *idx < self.params)
+  }
+
+  fn place(&self, cursor: &mut Cursor<'s>) -> Result<Place> {
+    let mut lookahead = cursor.clone();
+    let (name, _) = lookahead.local()?;
+    match self.param(name) {
+      Some(idx) => {
+        *cursor = lookahead;
+        Ok(Place::Param(idx))
+      }
+      None => Ok(Place::Local(self.inst_id(cursor)?)),
+    }
+  }
+
+  fn value(&self, cursor: &mut Cursor<'s>) -> Result<MirValue> {
+    cursor.skip_ws();
+    if cursor.rest().starts_with('%') {
+      return Ok(MirValue::Inst(self.inst_id(cursor)?));
+    }
+
+    let Some((word, span)) = cursor.word() else {
+      return cursor.error("expected a value");
+    };
+    let digits = word.strip_prefix('-').unwrap_or(word);
+    let constant = match word {
+      "true" => Constant::BoolValue(true),
+      "false" => Constant::BoolValue(false),
+      "null" => Constant::Null,
+      _ if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+        // Negative literals are stored as the bits of the `i32`.
+        let value = match word.parse::<i32>() {
+          Ok(value) if word.starts_with('-') => Ok(value as u32 as u64),
+          _ => word.parse(),
+        };
+        match value {
+          Ok(value) => Constant::IntValue(ValType::I32, value),
+          Err(_) => return Err(MirParseError::new("integer literal out of range", span)),
+        }
+      }
+      _ => match word.parse() {
+        Ok(value) if digits.starts_with(|c: char| c.is_ascii_digit()) => {
+          Constant::FloatValue(ValType::F32, value)
+        }
+        _ => {
+          return Err(MirParseError::new(
+            format!("expected a value, found `{}`", word),
+            span,
+          ));
+        }
+      },
+    };
+    Ok(MirValue::Constant(constant))
+  }
+
+  fn operands(&self, cursor: &mut Cursor<'s>) -> Result<(MirValue, MirValue)> {
+    let lhs = self.value(cursor)?;
+    cursor.expect(",")?;
+    let rhs = self.value(cursor)?;
+    Ok((lhs, rhs))
+  }
+
+  fn parse_inst(&self, cursor: &mut Cursor<'s>) -> Result<Instruction> {
+    let named = cursor.rest().starts_with('%');
+    if named {
+      cursor.local()?;
+      cursor.expect("=")?;
+    }
+
+    let (opcode, opcode_span) = cursor.name()?;
+    let inst = match opcode {
+      "alloca" => Instruction::Alloca(AllocaInst::new(cursor.type_()?)),
+      "store" => {
+        let target = self.inst_id(cursor)?;
+        cursor.expect(",")?;
+        Instruction::Store(StoreInst::new(target, self.value(cursor)?))
+      }
+      "load" => {
+        let type_ = cursor.type_()?;
+        Instruction::Load(LoadInst::new(type_, self.place(cursor)?))
+      }
+      "add" | "sub" | "mul" | "div" => {
+        let type_ = cursor.type_()?;
+        let (lhs, rhs) = self.operands(cursor)?;
+        match opcode {
+          "add" => Instruction::Add(AddInst::new(type_, lhs, rhs)),
+          "sub" => Instruction::Sub(SubInst::new(type_, lhs, rhs)),
+          "mul" => Instruction::Mul(MulInst::new(type_, lhs, rhs)),
+          _ => Instruction::Div(DivInst::new(type_, lhs, rhs)),
+        }
+      }
+      "cmp" => {
+        let (predicate, span) = cursor.name()?;
+        let predicate = match predicate {
+          "eq" => CmpPredicate::Eq,
+          "ne" => CmpPredicate::Ne,
+          "lt" => CmpPredicate::Lt,
+          "le" => CmpPredicate::Le,
+          "gt" => CmpPredicate::Gt,
+          "ge" => CmpPredicate::Ge,
+          _ => {
+            return Err(MirParseError::new(
+              format!("unknown comparison `{}`", predicate),
+              span,
+            ));
+          }
+        };
+        let type_ = cursor.type_()?;
+        let (lhs, rhs) = self.operands(cursor)?;
+        Instruction::Cmp(CmpInst::new(predicate, type_, lhs, rhs))
+      }
+      "and" => {
+        let (lhs, rhs) = self.operands(cursor)?;
+        Instruction::And(AndInst::new(lhs, rhs))
+      }
+      "or" => {
+        let (lhs, rhs) = self.operands(cursor)?;
+        Instruction::Or(OrInst::new(lhs, rhs))
+      }
+      "not" => Instruction::Not(NotInst::new(self.value(cursor)?)),
+      "ret" => {
+        let type_ = cursor.type_()?;
+        let value = if type_ == ValType::Void && cursor.rest().is_empty() {
+          MirValue::Constant(Constant::Null)
+        } else {
+          self.value(cursor)?
+        };
+        Instruction::Return(ReturnInst::new(value, type_))
+      }
+      "call" => {
+        let type_ = cursor.type_()?;
+        cursor.expect("@")?;
+        let (callee, span) = cursor.name()?;
+        let callee = match (self.func_ids.get(callee), callee.parse::<usize>()) {
+          (Some(id), _) => *id,
+          (None, Ok(idx)) if idx < self.func_ids.len() => MirFunctionId(idx),
+          _ => {
+            return Err(MirParseError::new(
+              format!("unknown function `@{}`", callee),
+              span,
+            ));
+          }
+        };
+        cursor.expect("(")?;
+        let mut args = Vec::new();
+        if !cursor.eat(")") {
+          loop {
+            args.push(self.value(cursor)?);
+            if cursor.eat(")") {
+              break;
+            }
+            cursor.expect(",")?;
+          }
+        }
+        Instruction::Call(CallInst::new(callee, type_, args))
+      }
+      "br" => {
+        let cond = self.value(cursor)?;
+        cursor.expect(",")?;
+        let then_blk = self.block(cursor)?;
+        cursor.expect(",")?;
+        let else_blk = self.block(cursor)?;
+        Instruction::Branch(BranchInst::new(cond, then_blk, else_blk))
+      }
+      "jmp" => Instruction::Jump(JumpInst::new(self.block(cursor)?)),
+      "phi" => {
+        let type_ = cursor.type_()?;
+        let mut incoming = Vec::new();
+        loop {
+          cursor.expect("[")?;
+          let value = self.value(cursor)?;
+          cursor.expect(",")?;
+          let blk = self.block(cursor)?;
+          cursor.expect("]")?;
+          incoming.push((blk, value));
+          if !cursor.eat(",") {
+            break;
+          }
+        }
+        Instruction::Phi(PhiInst::new(type_, incoming))
+      }
+      "unreachable" => Instruction::Unreachable,
+      _ => {
+        return Err(MirParseError::new(
+          format!("unknown instruction `{}`", opcode),
+          opcode_span,
+        ));
+      }
+    };
+    cursor.expect_end()?;
+
+    match (named, produces_value(&inst)) {
+      (false, true) => Err(MirParseError::new(
+        format!("the result of `{}` must be named", opcode),
+        opcode_span,
+      )),
+      (true, false) => Err(MirParseError::new(
+        format!("`{}` does not produce a value", opcode),
+        opcode_span,
+      )),
+      _ => Ok(inst),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser};
+  use sable_sema::sema::Sema;
+
+  use crate::lowering::Lowerer;
+
+  use super::*;
+
+  fn round_trip(source: &str) {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast.clone());
+    assert!(sema.analyze().is_ok());
+    let mut lowerer = Lowerer::new(MirModule::new("test"), ast, sema.into_types());
+    let module = lowerer.lower().unwrap();
+    let mut module = module.borrow_mut();
+    // Textual MIR carries no source positions.
+    for idx in 0..module.get_funcs().len() {
+      let func = module.get_func_mut(MirFunctionId(idx)).unwrap();
+      let ids = func
+        .get_blocks()
+        .iter()
+        .flat_map(|blk| blk.insts().to_vec())
+        .collect::<Vec<_>>();
+      for id in ids {
+        func.set_inst_pos(id, None);
+      }
+    }
+
+    let text = module.to_string();
+    let parsed = parse_module(&text).unwrap();
+    assert_eq!(parsed, *module);
+    assert_eq!(parsed.to_string(), text);
+  }
+
+  fn parse_err(source: &str) -> (String, &str) {
+    let err = parse_module(source).unwrap_err();
+    (err.message().to_string(), &source[err.span()])
+  }
+
+  #[test]
+  fn test_round_trip_lowered_modules() {
+    round_trip(
+      "
+      func i32 main() { return sum(3) + fact(4); }
+      func i32 sum(i32 n) {
+        let i32 acc = 0;
+        while n > 0 { acc = acc + n; if acc > 100 { break; } }
+        return acc;
+      }
+      noinline func i32 fact(i32 n) { if n <= 1 { return 1; } return n * fact(n - 1); }
+      func bool check(f32 x, bool b) { return !(x <= 0.5) && b || false; }
+      inline func void nothing() { let f32 y = 2.25 / 1.5; }
+    ",
+    );
+  }
+
+  #[test]
+  fn test_parse_hand_written() {
+    let source = "
+; a comment before the module
+module hand
+
+func twice(i32 %arg0) -> i32 {
+start.0:
+  %x = load i32 %arg0   ; the parameter
+  %y = add i32 %x, %x
+  jmp done.1
+done.1:
+  ret i32 %y
+}
+
+func main() -> i32 {
+entry.0:
+  %r = call i32 @twice(21)
+  ret i32 %r
+}
+";
+    let module = parse_module(source).unwrap();
+    let expected = "\
+module hand
+
+func twice(i32 %arg0) -> i32 {
+start.0:
+  %0 = load i32 %arg0
+  %1 = add i32 %0, %0
+  jmp done.1
+done.1:
+  ret i32 %1
+}
+
+func main() -> i32 {
+entry.0:
+  %0 = call i32 @twice(21)
+  ret i32 %0
+}
+";
+    assert_eq!(module.to_string(), expected);
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    let header = "module m\nfunc f(i32 %arg0) -> i32 {\nentry.0:\n";
+    let cases = [
+      ("  ret i32 %nope\n}", "undefined value `%nope`", "%nope"),
+      ("  jmp exit.1\n}", "unknown block `exit.1`", "exit.1"),
+      ("  ret i32 1 2\n}", "unexpected `2`", "2"),
+      ("  %a = frob i32\n}", "unknown instruction `frob`", "frob"),
+      (
+        "  load i32 %arg0\n}",
+        "the result of `load` must be named",
+        "load",
+      ),
+      (
+        "  %a = ret i32 1\n}",
+        "`ret` does not produce a value",
+        "ret",
+      ),
+      (
+        "  %a = add i32 %arg0, 1\n}",
+        "parameters can only be used by `load`",
+        "%arg0",
+      ),
+      ("  ret i32 1\n", "expected `}` closing the function", ""),
+    ];
+    for (body, message, span) in cases {
+      let source = format!("{}{}", header, body);
+      assert_eq!(parse_err(&source), (message.to_string(), span));
+    }
+  }
+}
@@ -0,0 +1,14 @@
+use super::instruction::MirInstId;
+
+/// A location a value is loaded from or stored to.
+///
+/// Only locals can be stored to: a parameter that is assigned is spilled
+/// to a local first, so `store` names its slot directly. Fields and indexes
+/// will project from a place once aggregates exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Place {
+  /// A stack slot, the result of an `alloca`.
+  Local(MirInstId),
+  /// A parameter of the function, by index.
+  Param(usize),
+}
@@ -0,0 +1,346 @@
+//! The textual form of the MIR:
+//!
+//! ```text
+//! module example
+//!
+//! func max(i32 %arg0, i32 %arg1) -> i32 {
+//! entry.0:
+//!   %0 = load i32 %arg0
+//!   %1 = load i32 %arg1
+//!   %2 = cmp gt i32 %0, %1
+//!   br %2, then.1, merge.2
+//! then.1:
+//!   %4 = load i32 %arg0
+//!   ret i32 %4
+//! merge.2:
+//!   %6 = load i32 %arg1
+//!   ret i32 %6
+//! }
+//! ```
+//!
+//! Values are named after the id of the instruction producing them, blocks
+//! after their name and id. Blocks are printed in layout order, not in the
+//! order of their ids. Callees are printed by name when a whole module is
+//! printed and by id (`@0`) when a function is printed on its own.
+//! [`super::parser`] reads this form back, numbering instructions by their
+//! position in the text, so a function reads back with the same ids once
+//! it is compacted.
+
+use std::fmt::{self, Display};
+
+use pretty::RcDoc;
+use sable_parser::info::{InlineHint, ValType};
+
+use crate::mir::{
+  function::{MirFunction, block::MirBlockId},
+  instruction::{CmpPredicate, Instruction, MirInstId},
+  module::MirModule,
+  place::Place,
+  value::{Constant, MirValue},
+};
+
+const WIDTH: usize = 100;
+const INDENT: isize = 2;
+
+impl Display for Constant {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Constant::Null => write!(f, "null"),
+      Constant::IntValue(ValType::I32, value) => write!(f, "{}", *value as i32),
+      Constant::IntValue(_, value) => write!(f, "{}", value),
+      Constant::FloatValue(_, value) => write!(f, "{:?}", value),
+      Constant::BoolValue(value) => write!(f, "{}", value),
+    }
+  }
+}
+
+impl Display for MirInstId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "%{}", self.0)
+  }
+}
+
+impl Display for MirValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MirValue::Constant(constant) => write!(f, "{}", constant),
+      MirValue::Inst(id) => write!(f, "{}", id),
+    }
+  }
+}
+
+impl Display for Place {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Place::Local(id) => write!(f, "{}", id),
+      Place::Param(idx) => write!(f, "%arg{}", idx),
+    }
+  }
+}
+
+impl Display for CmpPredicate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      CmpPredicate::Eq => "eq",
+      CmpPredicate::Ne => "ne",
+      CmpPredicate::Lt => "lt",
+      CmpPredicate::Le => "le",
+      CmpPredicate::Gt => "gt",
+      CmpPredicate::Ge => "ge",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+pub(crate) fn block_label(func: &MirFunction, id: MirBlockId) -> String {
+  let name = func.get_block(id).map_or("?", |blk| blk.name());
+  format!("{}.{}", name, id.0)
+}
+
+fn values<'a>(values: impl IntoIterator<Item = &'a MirValue>) -> RcDoc<'a> {
+  RcDoc::intersperse(values.into_iter().map(RcDoc::as_string), RcDoc::text(", "))
+}
+
+fn inst_doc<'a>(
+  module: Option<&MirModule>,
+  func: &MirFunction,
+  id: MirInstId,
+  inst: &'a Instruction,
+) -> RcDoc<'a> {
+  let binary = |op: &'static str, type_, lhs: &'a MirValue, rhs: &'a MirValue| {
+    RcDoc::text(format!("{} {} ", op, type_)).append(values([lhs, rhs]))
+  };
+
+  let body = match inst {
+    Instruction::Alloca(alloca) => RcDoc::text(format!("alloca {}", alloca.type_())),
+    Instruction::Store(store) => {
+      RcDoc::text(format!("store {}, {}", store.target(), store.value()))
+    }
+    Instruction::Load(load) => RcDoc::text(format!("load {} {}", load.by(), load.from())),
+    Instruction::Add(add) => binary("add", add.type_(), add.lhs(), add.rhs()),
+    Instruction::Sub(sub) => binary("sub", sub.type_(), sub.lhs(), sub.rhs()),
+    Instruction::Mul(mul) => binary("mul", mul.type_(), mul.lhs(), mul.rhs()),
+    Instruction::Div(div) => binary("div", div.type_(), div.lhs(), div.rhs()),
+    Instruction::Cmp(cmp) => RcDoc::text(format!("cmp {} {} ", cmp.predicate(), cmp.type_()))
+      .append(values([cmp.lhs(), cmp.rhs()])),
+    Instruction::And(and) => RcDoc::text("and ").append(values([and.lhs(), and.rhs()])),
+    Instruction::Or(or) => RcDoc::text("or ").append(values([or.lhs(), or.rhs()])),
+    Instruction::Not(not) => RcDoc::text(format!("not {}", not.value())),
+    Instruction::Return(ret) => match ret.ret_value() {
+      MirValue::Constant(Constant::Null) => RcDoc::text(format!("ret {}", ret.type_())),
+      value => RcDoc::text(format!("ret {} {}", ret.type_(), value)),
+    },
+    Instruction::Call(call) => {
+      let callee = match module.and_then(|module| module.get_func(call.callee())) {
+        Some(callee) => callee.name().to_string(),
+        None => call.callee().0.to_string(),
+      };
+      RcDoc::text(format!("call {} @{}(", call.type_(), callee))
+        .append(values(call.args()))
+        .append(")")
+    }
+    Instruction::Branch(br) => RcDoc::text(format!(
+      "br {}, {}, {}",
+      br.cond(),
+      block_label(func, br.then_blk()),
+      block_label(func, br.else_blk())
+    )),
+    Instruction::Jump(jmp) => RcDoc::text(format!("jmp {}", block_label(func, jmp.target()))),
+    Instruction::Phi(phi) => {
+      let incoming = phi
+        .incoming()
+        .iter()
+        .map(|(blk, value)| RcDoc::text(format!("[{}, {}]", value, block_label(func, *blk))));
+      RcDoc::text(format!("phi {} ", phi.type_()))
+        .append(RcDoc::intersperse(incoming, RcDoc::text(", ")))
+    }
+    Instruction::Unreachable => RcDoc::text("unreachable"),
+  };
+
+  if produces_value(inst) {
+    RcDoc::text(format!("{} = ", id)).append(body)
+  } else {
+    body
+  }
+}
+
+/// The line `inst`, which has id `id`, is printed as.
+pub(crate) fn inst_text(
+  module: Option<&MirModule>,
+  func: &MirFunction,
+  id: MirInstId,
+  inst: &Instruction,
+) -> String {
+  let mut text = String::new();
+  inst_doc(module, func, id, inst)
+    .render_fmt(WIDTH, &mut text)
+    .unwrap();
+  text
+}
+
+/// Whether `inst` defines a value other instructions can refer to.
+pub fn produces_value(inst: &Instruction) -> bool {
+  !matches!(
+    inst,
+    Instruction::Store(_)
+      | Instruction::Return(_)
+      | Instruction::Branch(_)
+      | Instruction::Jump(_)
+      | Instruction::Unreachable
+  )
+}
+
+fn function_doc<'a>(module: Option<&MirModule>, func: &'a MirFunction) -> RcDoc<'a> {
+  let params = func
+    .get_params()
+    .iter()
+    .enumerate()
+    .map(|(idx, type_)| RcDoc::text(format!("{} %arg{}", type_, idx)));
+  let annotation = match func.get_inline() {
+    InlineHint::Auto => "",
+    InlineHint::Always => "inline ",
+    InlineHint::Never => "noinline ",
+  };
+  let header = RcDoc::text(format!("{}func {}(", annotation, func.name()))
+    .append(RcDoc::intersperse(params, RcDoc::text(", ")))
+    .append(RcDoc::text(format!(") -> {} {{", func.ret_type())));
+
+  let blocks = func.get_layout().into_iter().map(|id| {
+    let insts = func
+      .get_insts(id)
+      .map(|(inst_id, inst)| RcDoc::hardline().append(inst_doc(module, func, inst_id, inst)));
+    RcDoc::hardline()
+      .append(RcDoc::text(format!("{}:", block_label(func, id))))
+      .append(RcDoc::concat(insts).nest(INDENT))
+  });
+
+  header
+    .append(RcDoc::concat(blocks))
+    .append(RcDoc::hardline())
+    .append("}")
+}
+
+fn module_doc<'a>(module: &'a MirModule) -> RcDoc<'a> {
+  let funcs = module.get_funcs().iter().map(|func| {
+    RcDoc::hardline()
+      .append(RcDoc::hardline())
+      .append(function_doc(Some(module), func))
+  });
+  RcDoc::text(format!("module {}", module.name()))
+    .append(RcDoc::concat(funcs))
+    .append(RcDoc::hardline())
+}
+
+impl Display for MirFunction<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    function_doc(None, self).render_fmt(WIDTH, f)
+  }
+}
+
+impl Display for MirModule<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    module_doc(self).render_fmt(WIDTH, f)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser};
+  use sable_sema::sema::Sema;
+
+  use crate::lowering::Lowerer;
+
+  use super::*;
+
+  fn print(source: &str) -> String {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast.clone());
+    assert!(sema.analyze().is_ok());
+    let mut lowerer = Lowerer::new(MirModule::new("test"), ast, sema.into_types());
+    let module = lowerer.lower().unwrap();
+    module.borrow().to_string()
+  }
+
+  #[test]
+  fn test_print_module() {
+    let source = "
+      func i32 main() { return sum(3); }
+      func i32 sum(i32 n) {
+        let i32 acc = 0;
+        while n > 0 { acc = acc + n; if acc > 100 { break; } }
+        return acc;
+      }
+      func bool check(f32 x) { return !(x <= 0.5) && true; }
+    ";
+    let expected = "\
+module test
+
+func main() -> i32 {
+entry.0:
+  %0 = call i32 @sum(3)
+  ret i32 %0
+}
+
+func sum(i32 %arg0) -> i32 {
+entry.0:
+  %0 = alloca i32
+  store %0, 0
+  jmp header.1
+header.1:
+  %3 = load i32 %arg0
+  %4 = cmp gt i32 %3, 0
+  br %4, body.2, exit.3
+body.2:
+  %6 = load i32 %0
+  %7 = load i32 %arg0
+  %8 = add i32 %6, %7
+  store %0, %8
+  %10 = load i32 %0
+  %11 = cmp gt i32 %10, 100
+  br %11, then.4, merge.5
+then.4:
+  jmp exit.3
+merge.5:
+  jmp header.1
+exit.3:
+  %15 = load i32 %0
+  ret i32 %15
+}
+
+func check(f32 %arg0) -> bool {
+entry.0:
+  %0 = load f32 %arg0
+  %1 = cmp le f32 %0, 0.5
+  %2 = not %1
+  br %2, rhs.1, merge.2
+rhs.1:
+  jmp merge.2
+merge.2:
+  %5 = phi bool [false, entry.0], [true, rhs.1]
+  ret bool %5
+}
+";
+    assert_eq!(print(source), expected);
+  }
+
+  #[test]
+  fn test_function_callees_print_by_id() {
+    let module = print("func i32 main() { return one(); } func i32 one() { return 1; }");
+    assert!(module.contains("call i32 @one()"));
+
+    let mut lexer = Lexer::new("func void f() { f(); }");
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast.clone());
+    assert!(sema.analyze().is_ok());
+    let mut lowerer = Lowerer::new(MirModule::new("test"), ast, sema.into_types());
+    let module = lowerer.lower().unwrap();
+    let func = module.borrow().get_funcs()[0].to_string();
+    assert_eq!(
+      func,
+      "func f() -> void {\nentry.0:\n  %0 = call void @0()\n  ret void\n}"
+    );
+  }
+}
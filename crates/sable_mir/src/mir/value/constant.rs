@@ -5,4 +5,5 @@ pub enum Constant {
   Null,
   IntValue(ValType, u64),
   FloatValue(ValType, f64),
+  BoolValue(bool),
 }
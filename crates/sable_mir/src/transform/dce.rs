@@ -0,0 +1,147 @@
+//! Dead-instruction elimination.
+//!
+//! Stores, calls, terminators and integer divisions that may trap are
+//! kept, along with everything they read, transitively. Every other
+//! instruction produces a value nothing needs and is removed, including
+//! cycles of phis that only feed each other and slots no kept instruction
+//! accesses.
+
+use sable_parser::info::ValType;
+
+use crate::{
+  analysis::manager::FunctionAnalyses,
+  mir::{
+    function::MirFunction,
+    instruction::Instruction,
+    value::{Constant, MirValue},
+  },
+  transform::pass::{FunctionPass, Statistics},
+};
+
+pub struct DeadCodeElimination;
+
+impl FunctionPass for DeadCodeElimination {
+  fn name(&self) -> &'static str {
+    "dce"
+  }
+
+  fn run_on_function(
+    &mut self,
+    func: &mut MirFunction<'_>,
+    analyses: &mut FunctionAnalyses,
+    stats: &mut Statistics,
+  ) {
+    let removed = eliminate_dead_code(func);
+    if removed > 0 {
+      analyses.invalidate();
+    }
+    stats.add("instructions removed", removed);
+  }
+}
+
+/// Removes the instructions of `func` whose results are unused, returning
+/// how many were removed.
+pub fn eliminate_dead_code(func: &mut MirFunction) -> usize {
+  let ids = (0..func.get_blocks().len())
+    .flat_map(|blk| func.get_blocks()[blk].insts().to_vec())
+    .collect::<Vec<_>>();
+  let mut live = vec![false; func.get_inst_count()];
+  let mut worklist = ids
+    .iter()
+    .copied()
+    .filter(|id| has_side_effects(func.get_inst(*id).unwrap()))
+    .collect::<Vec<_>>();
+  for id in &worklist {
+    live[id.0] = true;
+  }
+  while let Some(id) = worklist.pop() {
+    for used in func.get_inst(id).unwrap().references() {
+      if !live[used.0] {
+        live[used.0] = true;
+        worklist.push(used);
+      }
+    }
+  }
+
+  let dead = ids.into_iter().filter(|id| !live[id.0]).collect::<Vec<_>>();
+  for &id in &dead {
+    func.erase_inst(id);
+  }
+  dead.len()
+}
+
+fn has_side_effects(inst: &Instruction) -> bool {
+  match inst {
+    Instruction::Store(_) | Instruction::Call(_) => true,
+    Instruction::Div(div) => {
+      div.type_() == ValType::I32
+        && !matches!(div.rhs(), MirValue::Constant(Constant::IntValue(_, rhs)) if *rhs != 0)
+    }
+    _ => inst.is_terminator(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    analysis::manager::AnalysisManager, mir::parser::parse_module, transform::pass::Pass,
+    verify::verify,
+  };
+
+  #[test]
+  fn test_removes_unused_values() {
+    let mut module = parse_module(
+      "
+      module test
+      func f(i32 %arg0, f32 %arg1) -> i32 {
+      entry.0:
+        %slot = alloca i32
+        %dead = alloca i32
+        store %slot, 1
+        %x = load i32 %arg0
+        %y = load f32 %arg1
+        %unused = add i32 %x, 1
+        %kept = div i32 1, %x
+        %gone = div i32 %x, 2
+        %fdiv = div f32 %y, %y
+        %v = load i32 %slot
+        jmp loop.1
+      loop.1:
+        %a = phi i32 [0, entry.0], [%b, loop.1]
+        %b = add i32 %a, 1
+        %c = call i32 @f(%x, %y)
+        br true, loop.1, exit.2
+      exit.2:
+        ret i32 %v
+      }
+      ",
+    )
+    .unwrap();
+    let mut stats = Statistics::new();
+    DeadCodeElimination.run(&mut module, &mut AnalysisManager::new(), &mut stats);
+    assert_eq!(verify(&module), Ok(()));
+    assert_eq!(stats.get("instructions removed"), 6);
+    let expected = "\
+module test
+
+func f(i32 %arg0, f32 %arg1) -> i32 {
+entry.0:
+  %0 = alloca i32
+  store %0, 1
+  %3 = load i32 %arg0
+  %4 = load f32 %arg1
+  %6 = div i32 1, %3
+  %9 = load i32 %0
+  jmp loop.1
+loop.1:
+  %13 = call i32 @f(%3, %4)
+  br true, loop.1, exit.2
+exit.2:
+  ret i32 %9
+}
+";
+    // The instructions left keep their ids.
+    assert_eq!(module.to_string(), expected);
+  }
+}
@@ -0,0 +1,273 @@
+//! Constant folding.
+//!
+//! Arithmetic, comparisons and logic on constant operands are evaluated
+//! the way the interpreter would, `i32` wrapping and `f32` rounding
+//! included, and their uses read the result instead. A phi whose incoming
+//! values are all the same value is replaced by it. Folding repeats until
+//! nothing changes, so results feed into further folding, also through the
+//! phis of loops.
+//!
+//! An integer division by zero is left for the program to trap on, and a
+//! float result that is not finite is kept as an instruction because the
+//! textual MIR has no spelling for it.
+
+use sable_parser::info::ValType;
+
+use crate::{
+  analysis::manager::FunctionAnalyses,
+  mir::{
+    function::MirFunction,
+    instruction::{CmpPredicate, Instruction, MirInstId},
+    value::{Constant, MirValue},
+  },
+  transform::pass::{FunctionPass, Statistics},
+};
+
+pub struct ConstantFolding;
+
+impl FunctionPass for ConstantFolding {
+  fn name(&self) -> &'static str {
+    "const-fold"
+  }
+
+  fn run_on_function(
+    &mut self,
+    func: &mut MirFunction<'_>,
+    analyses: &mut FunctionAnalyses,
+    stats: &mut Statistics,
+  ) {
+    let folded = fold_constants(func);
+    if folded > 0 {
+      analyses.invalidate();
+    }
+    stats.add("instructions folded", folded);
+  }
+}
+
+/// Folds the instructions of `func` with a constant result, returning how
+/// many were removed.
+pub fn fold_constants(func: &mut MirFunction) -> usize {
+  let mut folded = 0;
+  loop {
+    let ids = func
+      .get_layout()
+      .into_iter()
+      .flat_map(|blk| func.get_blocks()[blk.0].insts().to_vec())
+      .collect::<Vec<_>>();
+    let mut changed = false;
+    for id in ids {
+      if let Some(value) = fold(id, func.get_inst(id).unwrap()) {
+        func.replace_all_uses_with(id, value);
+        func.erase_inst(id);
+        folded += 1;
+        changed = true;
+      }
+    }
+    if !changed {
+      return folded;
+    }
+  }
+}
+
+/// The value `inst`, which has id `id`, always produces, if it is known.
+fn fold(id: MirInstId, inst: &Instruction) -> Option<MirValue> {
+  let constant = |value: &MirValue| match value {
+    MirValue::Constant(constant) => Some(constant.clone()),
+    _ => None,
+  };
+  let folded = match inst {
+    Instruction::Add(add) => arith(
+      constant(add.lhs())?,
+      constant(add.rhs())?,
+      i32::wrapping_add,
+      |a, b| a + b,
+    )?,
+    Instruction::Sub(sub) => arith(
+      constant(sub.lhs())?,
+      constant(sub.rhs())?,
+      i32::wrapping_sub,
+      |a, b| a - b,
+    )?,
+    Instruction::Mul(mul) => arith(
+      constant(mul.lhs())?,
+      constant(mul.rhs())?,
+      i32::wrapping_mul,
+      |a, b| a * b,
+    )?,
+    Instruction::Div(div) => {
+      let rhs = constant(div.rhs())?;
+      if let Constant::IntValue(_, 0) = rhs {
+        return None;
+      }
+      arith(constant(div.lhs())?, rhs, i32::wrapping_div, |a, b| a / b)?
+    }
+    Instruction::Cmp(cmp) => Constant::BoolValue(compare(
+      cmp.predicate(),
+      constant(cmp.lhs())?,
+      constant(cmp.rhs())?,
+    )?),
+    Instruction::And(and) => match (constant(and.lhs())?, constant(and.rhs())?) {
+      (Constant::BoolValue(lhs), Constant::BoolValue(rhs)) => Constant::BoolValue(lhs && rhs),
+      _ => return None,
+    },
+    Instruction::Or(or) => match (constant(or.lhs())?, constant(or.rhs())?) {
+      (Constant::BoolValue(lhs), Constant::BoolValue(rhs)) => Constant::BoolValue(lhs || rhs),
+      _ => return None,
+    },
+    Instruction::Not(not) => match constant(not.value())? {
+      Constant::BoolValue(value) => Constant::BoolValue(!value),
+      _ => return None,
+    },
+    Instruction::Phi(phi) => {
+      // Incoming values that are the phi itself come around a loop and
+      // do not change what it holds.
+      let mut values = phi
+        .incoming()
+        .iter()
+        .map(|(_, value)| value)
+        .filter(|value| **value != MirValue::Inst(id));
+      let first = values.next()?;
+      return values.all(|value| value == first).then(|| first.clone());
+    }
+    _ => return None,
+  };
+  Some(MirValue::Constant(folded))
+}
+
+fn arith(
+  lhs: Constant,
+  rhs: Constant,
+  int: fn(i32, i32) -> i32,
+  float: fn(f32, f32) -> f32,
+) -> Option<Constant> {
+  match (lhs, rhs) {
+    (Constant::IntValue(type_, lhs), Constant::IntValue(_, rhs)) => {
+      Some(int_value(type_, int(lhs as i32, rhs as i32)))
+    }
+    (Constant::FloatValue(type_, lhs), Constant::FloatValue(_, rhs)) => {
+      let value = float(lhs as f32, rhs as f32);
+      value
+        .is_finite()
+        .then_some(Constant::FloatValue(type_, value as f64))
+    }
+    _ => None,
+  }
+}
+
+/// An integer constant holding `value`, stored as its bit pattern.
+fn int_value(type_: ValType, value: i32) -> Constant {
+  Constant::IntValue(type_, value as u32 as u64)
+}
+
+fn compare(predicate: CmpPredicate, lhs: Constant, rhs: Constant) -> Option<bool> {
+  let ordering = match (lhs, rhs) {
+    (Constant::IntValue(_, lhs), Constant::IntValue(_, rhs)) => {
+      (lhs as i32).partial_cmp(&(rhs as i32))
+    }
+    (Constant::FloatValue(_, lhs), Constant::FloatValue(_, rhs)) => {
+      (lhs as f32).partial_cmp(&(rhs as f32))
+    }
+    (Constant::BoolValue(lhs), Constant::BoolValue(rhs)) => lhs.partial_cmp(&rhs),
+    _ => return None,
+  };
+  // Only `ne` holds between NaNs.
+  let Some(ordering) = ordering else {
+    return Some(predicate == CmpPredicate::Ne);
+  };
+  Some(match predicate {
+    CmpPredicate::Eq => ordering.is_eq(),
+    CmpPredicate::Ne => ordering.is_ne(),
+    CmpPredicate::Lt => ordering.is_lt(),
+    CmpPredicate::Le => ordering.is_le(),
+    CmpPredicate::Gt => ordering.is_gt(),
+    CmpPredicate::Ge => ordering.is_ge(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    analysis::manager::AnalysisManager, mir::parser::parse_module, transform::pass::Pass,
+    verify::verify,
+  };
+
+  fn fold_text(source: &str) -> String {
+    let mut module = parse_module(source).unwrap();
+    let mut stats = Statistics::new();
+    ConstantFolding.run(&mut module, &mut AnalysisManager::new(), &mut stats);
+    assert_eq!(verify(&module), Ok(()));
+    module.compact();
+    module.to_string()
+  }
+
+  #[test]
+  fn test_folds_arithmetic() {
+    let mir = fold_text(
+      "
+      module test
+      func f(i32 %arg0) -> f32 {
+      entry.0:
+        %p = load i32 %arg0
+        %a = mul i32 2147483647, 2
+        %b = sub i32 %a, 1
+        %c = div i32 %b, 0
+        %d = div i32 %p, %b
+        %e = cmp lt i32 %b, 0
+        %f = not %e
+        %g = div f32 1.0, 3.0
+        %h = div f32 1.0, 0.0
+        br %f, then.1, else.2
+      then.1:
+        ret f32 %g
+      else.2:
+        %i = add i32 %c, %d
+        ret f32 %h
+      }
+      ",
+    );
+    let expected = "\
+module test
+
+func f(i32 %arg0) -> f32 {
+entry.0:
+  %0 = load i32 %arg0
+  %1 = div i32 -3, 0
+  %2 = div i32 %0, -3
+  %3 = div f32 1.0, 0.0
+  br false, then.1, else.2
+then.1:
+  ret f32 0.3333333432674408
+else.2:
+  %6 = add i32 %1, %2
+  ret f32 %3
+}
+";
+    assert_eq!(mir, expected);
+    assert_eq!(parse_module(&mir).unwrap().to_string(), mir);
+  }
+
+  #[test]
+  fn test_folds_phis_around_loops() {
+    let mir = fold_text(
+      "
+      module test
+      func f(bool %arg0) -> i32 {
+      entry.0:
+        %b = load bool %arg0
+        jmp header.1
+      header.1:
+        %x = phi i32 [4, entry.0], [%y, body.2]
+        br %b, body.2, exit.3
+      body.2:
+        %y = add i32 2, 2
+        jmp header.1
+      exit.3:
+        ret i32 %x
+      }
+      ",
+    );
+    assert!(mir.contains("exit.3:\n  ret i32 4\n"), "{}", mir);
+    assert!(!mir.contains("phi"), "{}", mir);
+  }
+}
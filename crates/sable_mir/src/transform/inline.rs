@@ -0,0 +1,417 @@
+//! Function inlining.
+//!
+//! A call is replaced by a copy of the callee's blocks. The caller's block
+//! is split at the call: the first half jumps to the copy of the callee's
+//! entry block, and every `ret` of the copy jumps to the second half, where
+//! a phi merges the returned values. Loads of the callee's parameters read
+//! the call's arguments instead, and the callee's slots move to the
+//! caller's entry block.
+//!
+//! Functions are visited callees first, so a callee has had its own calls
+//! inlined by the time it is copied. Calls within a cycle of the call graph
+//! are never inlined. Otherwise an `inline` function always is and a
+//! `noinline` one never is; any other callee is inlined if it has at most
+//! `threshold` instructions and the caller stays within `max_caller_size`.
+
+use std::collections::HashMap;
+
+use sable_parser::info::{InlineHint, ValType};
+
+use crate::{
+  analysis::{
+    call_graph::CallGraph,
+    cfg::{predecessors, successors},
+    manager::AnalysisManager,
+  },
+  mir::{
+    function::{
+      MirFunction, MirFunctionId,
+      block::{MirBlock, MirBlockId},
+    },
+    instruction::{BranchInst, Instruction, JumpInst, MirInstId, PhiInst},
+    module::MirModule,
+    place::Place,
+    value::MirValue,
+  },
+  transform::pass::{Pass, Statistics},
+};
+
+pub struct Inliner {
+  threshold: usize,
+  max_caller_size: usize,
+}
+
+impl Default for Inliner {
+  fn default() -> Self {
+    Self::new(24, 1000)
+  }
+}
+
+impl Inliner {
+  pub fn new(threshold: usize, max_caller_size: usize) -> Self {
+    Inliner {
+      threshold,
+      max_caller_size,
+    }
+  }
+
+  /// Inlines the calls of every function of `module`, returning how many
+  /// were inlined.
+  pub fn inline_calls(&self, module: &mut MirModule) -> usize {
+    self.inline_all(module, &mut AnalysisManager::new())
+  }
+
+  /// Like `inline_calls`, invalidating the analyses of the callers that
+  /// changed.
+  fn inline_all(&self, module: &mut MirModule, analyses: &mut AnalysisManager) -> usize {
+    let graph = CallGraph::new(module);
+    let mut inlined = 0;
+    for caller in graph.postorder() {
+      let count = self.inline_into(module, &graph, caller);
+      if count > 0 {
+        analyses.invalidate(caller);
+      }
+      inlined += count;
+    }
+    inlined
+  }
+
+  /// Whether to inline a call to `callee` into `caller`, which has
+  /// `size` instructions so far.
+  fn should_inline(
+    &self,
+    graph: &CallGraph,
+    caller: MirFunctionId,
+    size: usize,
+    callee: &CallTarget,
+  ) -> bool {
+    if !callee.entry_has_no_preds || graph.reaches(callee.id, caller) {
+      return false;
+    }
+    match callee.inline {
+      InlineHint::Always => true,
+      InlineHint::Never => false,
+      InlineHint::Auto => {
+        callee.size <= self.threshold && size + callee.size <= self.max_caller_size
+      }
+    }
+  }
+
+  fn inline_into(&self, module: &mut MirModule, graph: &CallGraph, caller: MirFunctionId) -> usize {
+    let targets = module
+      .get_funcs()
+      .iter()
+      .enumerate()
+      .map(|(idx, func)| CallTarget {
+        id: MirFunctionId(idx),
+        inline: func.get_inline(),
+        size: func.get_size(),
+        entry_has_no_preds: predecessors(func).first().is_some_and(Vec::is_empty),
+      })
+      .collect::<Vec<_>>();
+
+    let func = module.get_func(caller).unwrap();
+    let mut size = func.get_size();
+    let mut calls = Vec::new();
+    for blk in func.get_layout() {
+      for (id, inst) in func.get_insts(blk) {
+        if let Instruction::Call(call) = inst {
+          let target = &targets[call.callee().0];
+          if self.should_inline(graph, caller, size, target) {
+            calls.push((id, target.id));
+            size += target.size;
+          }
+        }
+      }
+    }
+
+    let mut last_slot = None;
+    for &(call, callee) in &calls {
+      let callee = module.get_func(callee).unwrap().clone();
+      let func = module.get_func_mut(caller).unwrap();
+      inline_call(func, call, &callee, &mut last_slot);
+    }
+    calls.len()
+  }
+}
+
+impl Pass for Inliner {
+  fn name(&self) -> &'static str {
+    "inline"
+  }
+
+  fn run(
+    &mut self,
+    module: &mut MirModule<'_>,
+    analyses: &mut AnalysisManager,
+    stats: &mut Statistics,
+  ) {
+    stats.add("calls inlined", self.inline_all(module, analyses));
+  }
+}
+
+/// What deciding whether to inline a call needs to know of its callee.
+struct CallTarget {
+  id: MirFunctionId,
+  inline: InlineHint,
+  size: usize,
+  /// A jump to the entry block cannot be added if it has phis to update.
+  entry_has_no_preds: bool,
+}
+
+/// Replaces the call `call_id` with a copy of `callee`. The slots of the
+/// callee go to the start of the caller's entry block, after `last_slot`
+/// if another call put some there already.
+fn inline_call<'ctx>(
+  func: &mut MirFunction<'ctx>,
+  call_id: MirInstId,
+  callee: &MirFunction<'ctx>,
+  last_slot: &mut Option<MirInstId>,
+) {
+  let Some(Instruction::Call(call)) = func.get_inst(call_id).cloned() else {
+    unreachable!("{} is not a call", call_id);
+  };
+  let blk = func.get_inst_block(call_id).unwrap();
+  let call_pos = func.get_inst_pos(call_id).cloned();
+
+  // The copies of the callee's blocks, then the rest of `blk`.
+  let copies = callee
+    .get_blocks()
+    .iter()
+    .map(|callee_blk| func.add_block(MirBlock::new(callee_blk.name())))
+    .collect::<Vec<_>>();
+  let rest = func.add_block(MirBlock::new(func.get_blocks()[blk.0].name()));
+  let mut prev = blk;
+  for copy in callee
+    .get_layout()
+    .into_iter()
+    .map(|id| copies[id.0])
+    .chain([rest])
+  {
+    func.place_block_after(copy, prev);
+    prev = copy;
+  }
+  let insts = func.get_blocks()[blk.0].insts();
+  let tail = insts[insts.iter().position(|id| *id == call_id).unwrap() + 1..].to_vec();
+  for id in tail {
+    func.move_to_end(id, rest);
+  }
+  // Phis after the split see the rest of `blk` as their predecessor.
+  let phis = successors(func, rest)
+    .into_iter()
+    .flat_map(|succ| func.get_insts(succ).map(|(id, _)| id).collect::<Vec<_>>())
+    .collect::<Vec<_>>();
+  for id in phis {
+    if let Some(Instruction::Phi(phi)) = func.get_inst(id) {
+      let mut phi = phi.clone();
+      for (pred, _) in phi.incoming_mut() {
+        if *pred == blk {
+          *pred = rest;
+        }
+      }
+      func.replace_inst(id, Instruction::Phi(phi));
+    }
+  }
+
+  // Give every copied instruction an id first, so operands can refer to
+  // the ones copied later. Loads of parameters read the arguments instead.
+  let mut ids = HashMap::new();
+  let mut args = HashMap::new();
+  for callee_blk in callee.get_layout() {
+    for (id, inst) in callee.get_insts(callee_blk) {
+      let copy = match inst {
+        Instruction::Load(load) => match load.from() {
+          Place::Param(param) => {
+            args.insert(id, call.args()[*param].clone());
+            continue;
+          }
+          Place::Local(_) => func.append_inst(copies[callee_blk.0], Instruction::Unreachable),
+        },
+        Instruction::Alloca(_) => {
+          let slot = match *last_slot {
+            Some(last) => func.insert_after(last, Instruction::Unreachable),
+            None => func.prepend_inst(MirBlockId(0), Instruction::Unreachable),
+          };
+          *last_slot = Some(slot);
+          slot
+        }
+        _ => func.append_inst(copies[callee_blk.0], Instruction::Unreachable),
+      };
+      ids.insert(id, copy);
+    }
+  }
+
+  let mut returned = Vec::new();
+  for callee_blk in callee.get_layout() {
+    let copy = copies[callee_blk.0];
+    for (id, inst) in callee.get_insts(callee_blk) {
+      let Some(&new_id) = ids.get(&id) else {
+        continue;
+      };
+      let mut inst = inst.clone();
+      for operand in inst.operands_mut() {
+        if let MirValue::Inst(used) = operand {
+          *operand = match args.get(used) {
+            Some(arg) => arg.clone(),
+            None => MirValue::Inst(ids[used]),
+          };
+        }
+      }
+      if let Some(slot) = inst.slot() {
+        inst.set_slot(ids[&slot]);
+      }
+      match &mut inst {
+        Instruction::Return(ret) => {
+          returned.push((copy, ret.ret_value().clone()));
+          inst = Instruction::Jump(JumpInst::new(rest));
+        }
+        Instruction::Jump(jump) => {
+          inst = Instruction::Jump(JumpInst::new(copies[jump.target().0]));
+        }
+        Instruction::Branch(branch) => {
+          inst = Instruction::Branch(BranchInst::new(
+            branch.cond().clone(),
+            copies[branch.then_blk().0],
+            copies[branch.else_blk().0],
+          ));
+        }
+        Instruction::Phi(phi) => {
+          for (pred, _) in phi.incoming_mut() {
+            *pred = copies[pred.0];
+          }
+        }
+        _ => {}
+      }
+      func.replace_inst(new_id, inst);
+      func.set_inst_pos(new_id, callee.get_inst_pos(id).cloned());
+    }
+  }
+
+  if call.type_() != ValType::Void {
+    let value = match returned.as_slice() {
+      [(_, value)] => value.clone(),
+      _ => {
+        let merge = PhiInst::new(call.type_(), returned);
+        let merge = func.prepend_inst(rest, Instruction::Phi(merge));
+        func.set_inst_pos(merge, call_pos.clone());
+        MirValue::Inst(merge)
+      }
+    };
+    func.replace_all_uses_with(call_id, value);
+  }
+  func.erase_inst(call_id);
+  let jump = func.append_inst(blk, Instruction::Jump(JumpInst::new(copies[0])));
+  func.set_inst_pos(jump, call_pos);
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser};
+  use sable_sema::sema::Sema;
+
+  use super::*;
+  use crate::{lowering::Lowerer, transform::mem2reg::mem2reg, verify::verify};
+
+  /// Lowers `source`, promotes its slots and runs `inliner` over it,
+  /// returning the resulting functions, renumbered.
+  fn inline(source: &str, mut inliner: Inliner) -> (Vec<String>, Statistics) {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast.clone());
+    assert!(sema.analyze().is_ok());
+    let mut lowerer = Lowerer::new(MirModule::new("test"), ast, sema.into_types());
+    let module = lowerer.lower().unwrap();
+    let mut module = module.borrow_mut();
+    mem2reg(&mut module);
+    let mut stats = Statistics::new();
+    inliner.run(&mut module, &mut AnalysisManager::new(), &mut stats);
+    assert_eq!(verify(&module), Ok(()));
+    module.compact();
+    let funcs = module.get_funcs().iter().map(ToString::to_string).collect();
+    (funcs, stats)
+  }
+
+  #[test]
+  fn test_inlines_helpers() {
+    let (funcs, stats) = inline(
+      "
+      func i32 add(i32 a, i32 b) { return a + b; }
+      func i32 max(i32 a, i32 b) { if a > b { return a; } return b; }
+      func i32 main() { let i32 x = add(1, 2); while x < 10 { x = max(x, 7) + 1; } return x; }
+      ",
+      Inliner::default(),
+    );
+    assert_eq!(stats.get("calls inlined"), 2);
+    let expected = "\
+func main() -> i32 {
+entry.0:
+  jmp entry.4
+entry.4:
+  %1 = add i32 1, 2
+  jmp entry.5
+entry.5:
+  jmp header.1
+header.1:
+  %4 = phi i32 [%1, entry.5], [%13, body.9]
+  %5 = cmp lt i32 %4, 10
+  br %5, body.2, exit.3
+body.2:
+  jmp entry.6
+entry.6:
+  %8 = cmp gt i32 %4, 7
+  br %8, then.7, merge.8
+then.7:
+  jmp body.9
+merge.8:
+  jmp body.9
+body.9:
+  %12 = phi i32 [%4, then.7], [7, merge.8]
+  %13 = add i32 %12, 1
+  jmp header.1
+exit.3:
+  ret i32 %4
+}";
+    assert_eq!(funcs[2], expected);
+  }
+
+  #[test]
+  fn test_respects_cycles_and_annotations() {
+    let (funcs, stats) = inline(
+      "
+      func i32 fact(i32 n) { if n <= 1 { return 1; } return n * fact(n - 1); }
+      func bool even(i32 n) { if n == 0 { return true; } return odd(n - 1); }
+      func bool odd(i32 n) { if n == 0 { return false; } return even(n - 1); }
+      noinline func i32 one() { return 1; }
+      inline func i32 two(i32 n) { let i32 x = n; x = x + n; return x; }
+      func i32 three() { return 3; }
+      func i32 main() {
+        if even(4) { return fact(3) + one() + two(1) + three(); }
+        return 0;
+      }
+      ",
+      Inliner::new(2, 1000),
+    );
+    let [fact, even, odd, _, _, _, main] = &funcs[..] else {
+      panic!("expected seven functions");
+    };
+    assert!(fact.contains("call i32 @0("), "{}", fact);
+    assert!(even.contains("call bool @2(") && odd.contains("call bool @1("));
+    // `one` stays a call, `two` is inlined despite its size and `three`
+    // because it is small. `fact` and `even` are too large.
+    assert!(main.contains("call i32 @3()"), "{}", main);
+    assert!(main.contains("call i32 @0(") && main.contains("call bool @1("));
+    assert!(!main.contains("@4") && !main.contains("@5"), "{}", main);
+    assert_eq!(stats.get("calls inlined"), 2);
+
+    let (funcs, _) = inline(
+      "
+      func i32 fact(i32 n) { if n <= 1 { return 1; } return n * fact(n - 1); }
+      func i32 main() { return fact(5); }
+      ",
+      Inliner::default(),
+    );
+    assert_eq!(funcs[1].matches("call i32 @0(").count(), 1, "{}", funcs[1]);
+    assert!(funcs[1].contains("mul i32"), "{}", funcs[1]);
+  }
+}
@@ -0,0 +1,407 @@
+//! Promotes stack slots to SSA values.
+//!
+//! Lowering gives every local an `alloca` and goes through `store` and
+//! `load` for each access. A slot that is only ever loaded from and stored
+//! to is replaced by the values stored into it, following Cytron et al.:
+//! phis go on the iterated dominance frontier of the blocks storing to the
+//! slot, then a walk of the dominator tree replaces each load with the
+//! value stored last. Phis that end up unused are removed again.
+//!
+//! Where no store reaches, the slot holds the zero value of its type. Sema
+//! rejects reading a variable before it is assigned on every path, so in
+//! lowered programs only phis no load needs take the zero, and they are
+//! removed with the other unused phis.
+
+use std::collections::{HashMap, HashSet};
+
+use sable_parser::info::ValType;
+
+use crate::{
+  analysis::{cfg::successors, dominators::DominatorTree, manager::FunctionAnalyses},
+  mir::{
+    function::{MirFunction, MirFunctionId, block::MirBlockId},
+    instruction::{Instruction, MirInstId, PhiInst},
+    module::MirModule,
+    place::Place,
+    value::{Constant, MirValue},
+  },
+  transform::{
+    pass::{FunctionPass, Statistics},
+    resolve,
+  },
+};
+
+pub struct Mem2Reg;
+
+impl FunctionPass for Mem2Reg {
+  fn name(&self) -> &'static str {
+    "mem2reg"
+  }
+
+  fn run_on_function(
+    &mut self,
+    func: &mut MirFunction<'_>,
+    analyses: &mut FunctionAnalyses,
+    stats: &mut Statistics,
+  ) {
+    stats.add("slots promoted", promote(func, analyses));
+  }
+}
+
+/// Promotes the slots of every function of `module`, returning how many
+/// were promoted.
+pub fn mem2reg(module: &mut MirModule) -> usize {
+  (0..module.get_funcs().len())
+    .map(|idx| promote_slots(module.get_func_mut(MirFunctionId(idx)).unwrap()))
+    .sum()
+}
+
+/// Promotes the slots of `func`, returning how many were promoted.
+pub fn promote_slots(func: &mut MirFunction) -> usize {
+  promote(func, &mut FunctionAnalyses::new())
+}
+
+/// Like `promote_slots`, with the dominator tree of `func` from
+/// `analyses`, which are invalidated if anything is promoted.
+fn promote(func: &mut MirFunction, analyses: &mut FunctionAnalyses) -> usize {
+  let slots = promotable_slots(func);
+  if slots.is_empty() {
+    return 0;
+  }
+  let blocks = func.get_blocks().len();
+  let slot_index = slots
+    .iter()
+    .enumerate()
+    .map(|(idx, (slot, _))| (*slot, idx))
+    .collect::<HashMap<_, _>>();
+  let promoted = |place: &Place| match place {
+    Place::Local(slot) => slot_index.get(slot).copied(),
+    Place::Param(_) => None,
+  };
+
+  let mut def_blocks = vec![Vec::new(); slots.len()];
+  for blk in 0..blocks {
+    for (_, inst) in func.get_insts(MirBlockId(blk)) {
+      if let Instruction::Store(store) = inst
+        && let Some(idx) = slot_index.get(&store.target())
+        && !def_blocks[*idx].contains(&MirBlockId(blk))
+      {
+        def_blocks[*idx].push(MirBlockId(blk));
+      }
+    }
+  }
+
+  // Place the phis at the start of the blocks, empty until renaming.
+  let tree = analyses.get::<DominatorTree>(func);
+  let mut block_phis: Vec<Vec<(usize, MirInstId)>> = vec![Vec::new(); blocks];
+  let mut phis = HashMap::new();
+  for (idx, (_, type_)) in slots.iter().enumerate() {
+    let mut worklist = def_blocks[idx].clone();
+    let mut has_phi = HashSet::new();
+    while let Some(blk) = worklist.pop() {
+      for &join in tree.frontier(blk) {
+        if !has_phi.insert(join) {
+          continue;
+        }
+        let phi = Instruction::Phi(PhiInst::new(type_.clone(), Vec::new()));
+        let id = match block_phis[join.0].last() {
+          Some((_, last)) => func.insert_after(*last, phi),
+          None => func.prepend_inst(join, phi),
+        };
+        block_phis[join.0].push((idx, id));
+        phis.insert(id, PhiInst::new(type_.clone(), Vec::new()));
+        if !def_blocks[idx].contains(&join) {
+          worklist.push(join);
+        }
+      }
+    }
+  }
+
+  // Rename along the dominator tree, then start over from each block the
+  // entry block does not reach so their successors' phis get an incoming
+  // value for them too.
+  let mut replacements = HashMap::new();
+  let mut stacks: Vec<Vec<MirValue>> = vec![Vec::new(); slots.len()];
+  let mut visited = vec![false; blocks];
+  for root in (0..blocks).map(MirBlockId) {
+    if visited[root.0] {
+      continue;
+    }
+    let mut walk = vec![Walk::Enter(root)];
+    while let Some(step) = walk.pop() {
+      let blk = match step {
+        Walk::Enter(blk) => blk,
+        Walk::Exit(marks) => {
+          for (stack, mark) in stacks.iter_mut().zip(marks) {
+            stack.truncate(mark);
+          }
+          continue;
+        }
+      };
+      visited[blk.0] = true;
+      walk.push(Walk::Exit(stacks.iter().map(Vec::len).collect()));
+
+      for &(idx, phi) in &block_phis[blk.0] {
+        stacks[idx].push(MirValue::Inst(phi));
+      }
+      for (id, inst) in func.get_insts(blk) {
+        match inst {
+          Instruction::Store(store) => {
+            if let Some(idx) = slot_index.get(&store.target()) {
+              stacks[*idx].push(store.value().clone());
+            }
+          }
+          Instruction::Load(load) => {
+            if let Some(idx) = promoted(load.from()) {
+              let value = current(&stacks[idx], &slots[idx].1);
+              replacements.insert(id, value);
+            }
+          }
+          _ => {}
+        }
+      }
+      for succ in successors(func, blk) {
+        for &(idx, phi) in &block_phis[succ.0] {
+          let value = current(&stacks[idx], &slots[idx].1);
+          phis
+            .get_mut(&phi)
+            .unwrap()
+            .incoming_mut()
+            .push((blk, value));
+        }
+      }
+
+      for &child in tree.children(blk).iter().rev() {
+        walk.push(Walk::Enter(child));
+      }
+    }
+  }
+
+  // Everything but the promoted slots and their accesses stays, apart from
+  // the new phis.
+  let mut removed = Vec::new();
+  let mut kept = Vec::new();
+  for blk in 0..blocks {
+    for (id, inst) in func.get_insts(MirBlockId(blk)) {
+      let remove = match inst {
+        Instruction::Alloca(_) => slot_index.contains_key(&id),
+        Instruction::Store(store) => slot_index.contains_key(&store.target()),
+        Instruction::Load(load) => promoted(load.from()).is_some(),
+        Instruction::Phi(_) if phis.contains_key(&id) => continue,
+        _ => false,
+      };
+      if remove {
+        removed.push(id);
+      } else {
+        kept.push(id);
+      }
+    }
+  }
+
+  // A phi is needed if a kept instruction reads it, directly or through
+  // other phis.
+  let mut live = HashSet::new();
+  let mut worklist = kept
+    .iter()
+    .flat_map(|id| func.get_inst(*id).unwrap().operands())
+    .map(|value| resolve(value, &replacements))
+    .collect::<Vec<_>>();
+  while let Some(value) = worklist.pop() {
+    if let MirValue::Inst(id) = value
+      && let Some(phi) = phis.get(&id)
+      && live.insert(id)
+    {
+      for (_, value) in phi.incoming() {
+        worklist.push(resolve(value, &replacements));
+      }
+    }
+  }
+
+  for (id, mut phi) in phis {
+    if live.contains(&id) {
+      for (_, value) in phi.incoming_mut() {
+        *value = resolve(value, &replacements);
+      }
+      func.replace_inst(id, Instruction::Phi(phi));
+    } else {
+      func.erase_inst(id);
+    }
+  }
+  for id in removed {
+    if let Some(value) = replacements.get(&id) {
+      func.replace_all_uses_with(id, resolve(value, &replacements));
+    }
+    func.erase_inst(id);
+  }
+  analyses.invalidate();
+  slots.len()
+}
+
+enum Walk {
+  Enter(MirBlockId),
+  /// Leaves a block, restoring the stacks to the given heights.
+  Exit(Vec<usize>),
+}
+
+/// The value a slot holds at this point of the walk.
+fn current(stack: &[MirValue], type_: &ValType) -> MirValue {
+  stack.last().cloned().unwrap_or_else(|| zero(type_))
+}
+
+fn zero(type_: &ValType) -> MirValue {
+  MirValue::Constant(match type_ {
+    ValType::I32 => Constant::IntValue(ValType::I32, 0),
+    ValType::F32 => Constant::FloatValue(ValType::F32, 0.0),
+    ValType::Bool => Constant::BoolValue(false),
+    ValType::Void | ValType::Untyped => Constant::Null,
+  })
+}
+
+/// The slots of `func` that are only loaded from and stored to, with the
+/// type they hold.
+fn promotable_slots(func: &MirFunction) -> Vec<(MirInstId, ValType)> {
+  let mut slots = (0..func.get_inst_count())
+    .filter_map(|id| match func.get_inst(MirInstId(id)) {
+      Some(Instruction::Alloca(alloca)) => Some((MirInstId(id), alloca.type_())),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+
+  // A slot used as a value escapes.
+  slots.retain(|(slot, _)| {
+    func.get_users(*slot).iter().all(|user| {
+      let inst = func.get_inst(*user).unwrap();
+      !inst.operands().contains(&&MirValue::Inst(*slot))
+    })
+  });
+  slots
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser};
+  use sable_sema::sema::Sema;
+
+  use super::*;
+  use crate::{lowering::Lowerer, mir::parser::parse_module, verify::verify};
+
+  fn promote(source: &str) -> String {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast.clone());
+    assert!(sema.analyze().is_ok());
+    let mut lowerer = Lowerer::new(MirModule::new("test"), ast, sema.into_types());
+    let module = lowerer.lower().unwrap();
+    let mut module = module.borrow_mut();
+    mem2reg(&mut module);
+    assert_eq!(verify(&module), Ok(()));
+    module.compact();
+    module.to_string()
+  }
+
+  #[test]
+  fn test_promotes_loop_variables() {
+    let mir = promote(
+      "
+      func i32 sum(i32 n) {
+        let i32 acc = 0;
+        let i32 i = 0;
+        while i < n { acc = acc + i; i = i + 1; }
+        return acc;
+      }
+      ",
+    );
+    let expected = "\
+module test
+
+func sum(i32 %arg0) -> i32 {
+entry.0:
+  jmp header.1
+header.1:
+  %1 = phi i32 [0, entry.0], [%6, body.2]
+  %2 = phi i32 [0, entry.0], [%7, body.2]
+  %3 = load i32 %arg0
+  %4 = cmp lt i32 %2, %3
+  br %4, body.2, exit.3
+body.2:
+  %6 = add i32 %1, %2
+  %7 = add i32 %2, 1
+  jmp header.1
+exit.3:
+  ret i32 %1
+}
+";
+    assert_eq!(mir, expected);
+    assert_eq!(parse_module(&mir).unwrap().to_string(), mir);
+  }
+
+  #[test]
+  fn test_merges_branches() {
+    let mir = promote(
+      "
+      func f32 pick(bool b) {
+        let f32 x = 1.5;
+        if b { x = 2.5; }
+        let f32 y = x;
+        return y * x;
+      }
+      ",
+    );
+    assert!(!mir.contains("alloca") && !mir.contains("store"), "{}", mir);
+    assert!(
+      mir.contains("phi f32 [1.5, entry.0], [2.5, then.1]"),
+      "{}",
+      mir
+    );
+  }
+
+  #[test]
+  fn test_unassigned_paths_leave_no_phis() {
+    // `x` and `b` are unassigned on the way around the loop, where sema
+    // makes sure they are not read.
+    let mir = promote(
+      "
+      func i32 f(i32 n) {
+        let i32 x;
+        let bool b;
+        let i32 i = 0;
+        while i < n {
+          if i > 1 { x = i; b = x > 2; if b { return x; } }
+          i = i + 1;
+        }
+        return 0;
+      }
+      ",
+    );
+    assert_eq!(mir.matches("phi").count(), 1, "{}", mir);
+    assert!(mir.contains("phi i32 [0, entry.0], [%11, merge.5]"), "{}", mir);
+  }
+
+  #[test]
+  fn test_unreached_loads_read_zero() {
+    let mut module = parse_module(
+      "
+module m
+func f() -> i32 {
+entry.0:
+  %x = alloca i32
+  %v = load i32 %x
+  ret i32 %v
+}
+func g() -> bool {
+entry.0:
+  %b = alloca bool
+  %v = load bool %b
+  ret bool %v
+}
+",
+    )
+    .unwrap();
+    mem2reg(&mut module);
+    module.compact();
+    let mir = module.to_string();
+    assert!(mir.contains("ret i32 0\n"), "{}", mir);
+    assert!(mir.contains("ret bool false\n"), "{}", mir);
+  }
+}
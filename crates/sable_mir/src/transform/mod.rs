@@ -0,0 +1,26 @@
+//! Transformations of MIR functions.
+//!
+//! Transformations edit functions in place through the instruction and
+//! use-list APIs of `MirFunction`. Passes wrap them for a
+//! [`pass::PassManager`] to run.
+
+pub mod dce;
+pub mod fold;
+pub mod inline;
+pub mod mem2reg;
+pub mod pass;
+
+use std::collections::HashMap;
+
+use crate::mir::{instruction::MirInstId, value::MirValue};
+
+/// Follows `replacements` from `value` to the value that replaces it.
+pub(crate) fn resolve(value: &MirValue, replacements: &HashMap<MirInstId, MirValue>) -> MirValue {
+  let mut value = value;
+  while let MirValue::Inst(id) = value
+    && let Some(replacement) = replacements.get(id)
+  {
+    value = replacement;
+  }
+  value.clone()
+}
@@ -0,0 +1,339 @@
+//! Running transformations as a pipeline.
+//!
+//! A [`Pass`] transforms a whole module. Most passes look at one function
+//! at a time; they implement [`FunctionPass`] instead and run over every
+//! function of the module. A [`PassManager`] runs a list of passes in
+//! order, collects the statistics each of them reports and prints the
+//! module around the passes asked for. The analyses the passes request
+//! are shared between them until a pass changes the function.
+
+use std::fmt::{self, Display};
+
+use crate::{
+  analysis::manager::{AnalysisManager, FunctionAnalyses},
+  mir::{
+    function::{MirFunction, MirFunctionId},
+    module::MirModule,
+  },
+  transform::{dce::DeadCodeElimination, fold::ConstantFolding, inline::Inliner, mem2reg::Mem2Reg},
+};
+
+pub trait Pass {
+  /// The name the pass is selected, dumped and reported by.
+  fn name(&self) -> &'static str;
+  /// Transforms `module`, invalidating the analyses of every function it
+  /// changes.
+  fn run(
+    &mut self,
+    module: &mut MirModule<'_>,
+    analyses: &mut AnalysisManager,
+    stats: &mut Statistics,
+  );
+}
+
+pub trait FunctionPass {
+  fn name(&self) -> &'static str;
+  /// Transforms `func`, invalidating `analyses` if it changes it.
+  fn run_on_function(
+    &mut self,
+    func: &mut MirFunction<'_>,
+    analyses: &mut FunctionAnalyses,
+    stats: &mut Statistics,
+  );
+}
+
+impl<P: FunctionPass> Pass for P {
+  fn name(&self) -> &'static str {
+    FunctionPass::name(self)
+  }
+
+  fn run(
+    &mut self,
+    module: &mut MirModule<'_>,
+    analyses: &mut AnalysisManager,
+    stats: &mut Statistics,
+  ) {
+    for idx in 0..module.get_funcs().len() {
+      let func = module.get_func_mut(MirFunctionId(idx)).unwrap();
+      self.run_on_function(func, analyses.function(MirFunctionId(idx)), stats);
+    }
+  }
+}
+
+/// Creates the pass called `name`.
+pub fn create_pass(name: &str) -> Option<Box<dyn Pass>> {
+  match name {
+    "mem2reg" => Some(Box::new(Mem2Reg)),
+    "inline" => Some(Box::new(Inliner::default())),
+    "const-fold" => Some(Box::new(ConstantFolding)),
+    "dce" => Some(Box::new(DeadCodeElimination)),
+    _ => None,
+  }
+}
+
+/// How much optimization `PassManager::with_level` sets up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OptLevel {
+  /// No passes, the MIR stays as lowered.
+  #[default]
+  O0,
+  /// Locals are promoted, then constants are folded and whatever becomes
+  /// unused is removed.
+  O1,
+  /// Like `O1`, inlining calls before folding.
+  O2,
+}
+
+impl OptLevel {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "0" => Some(OptLevel::O0),
+      "1" => Some(OptLevel::O1),
+      "2" => Some(OptLevel::O2),
+      _ => None,
+    }
+  }
+
+  /// The names of the passes run at this level, in order.
+  pub fn pipeline(&self) -> &'static [&'static str] {
+    match self {
+      OptLevel::O0 => &[],
+      OptLevel::O1 => &["mem2reg", "const-fold", "dce"],
+      OptLevel::O2 => &["mem2reg", "inline", "const-fold", "dce"],
+    }
+  }
+}
+
+/// Named counters a pass bumps while it runs, in the order they were
+/// first added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Statistics {
+  counters: Vec<(&'static str, usize)>,
+}
+
+impl Statistics {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add(&mut self, counter: &'static str, count: usize) {
+    match self.counters.iter_mut().find(|(name, _)| *name == counter) {
+      Some((_, total)) => *total += count,
+      None => self.counters.push((counter, count)),
+    }
+  }
+
+  pub fn get(&self, counter: &str) -> usize {
+    self
+      .counters
+      .iter()
+      .find(|(name, _)| *name == counter)
+      .map_or(0, |(_, count)| *count)
+  }
+
+  pub fn counters(&self) -> &[(&'static str, usize)] {
+    &self.counters
+  }
+}
+
+impl Display for Statistics {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (idx, (name, count)) in self.counters.iter().enumerate() {
+      if idx > 0 {
+        write!(f, ", ")?;
+      }
+      write!(f, "{} {}", count, name)?;
+    }
+    Ok(())
+  }
+}
+
+/// When to print the module around a pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpPoint {
+  Before,
+  After,
+}
+
+pub struct PassManager {
+  passes: Vec<Box<dyn Pass>>,
+  stats: Vec<(&'static str, Statistics)>,
+  dumps: Vec<(DumpPoint, String)>,
+  dump_sink: Box<dyn FnMut(&str)>,
+}
+
+impl Default for PassManager {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PassManager {
+  /// An empty pipeline that writes its dumps to stderr.
+  pub fn new() -> Self {
+    PassManager {
+      passes: Vec::new(),
+      stats: Vec::new(),
+      dumps: Vec::new(),
+      dump_sink: Box::new(|text| eprint!("{}", text)),
+    }
+  }
+
+  pub fn with_level(level: OptLevel) -> Self {
+    let mut manager = Self::new();
+    for name in level.pipeline() {
+      manager.add_pass(create_pass(name).unwrap());
+    }
+    manager
+  }
+
+  pub fn add_pass(&mut self, pass: Box<dyn Pass>) {
+    self.passes.push(pass);
+  }
+
+  pub fn get_passes(&self) -> impl Iterator<Item = &'static str> + '_ {
+    self.passes.iter().map(|pass| pass.name())
+  }
+
+  /// Prints the module before or after each run of the pass called
+  /// `pass`, or of every pass for `all`.
+  pub fn add_dump(&mut self, point: DumpPoint, pass: impl Into<String>) {
+    self.dumps.push((point, pass.into()));
+  }
+
+  /// Where dumps are written instead of stderr.
+  pub fn set_dump_sink(&mut self, sink: impl FnMut(&str) + 'static) {
+    self.dump_sink = Box::new(sink);
+  }
+
+  /// The statistics of every pass run so far, in the order they ran.
+  pub fn get_stats(&self) -> &[(&'static str, Statistics)] {
+    &self.stats
+  }
+
+  /// Runs the pipeline over `module`, then renumbers the instructions of
+  /// every function to close the gaps the passes left.
+  pub fn run(&mut self, module: &mut MirModule<'_>) {
+    let mut analyses = AnalysisManager::new();
+    for pass in &mut self.passes {
+      let name = pass.name();
+      let dumped = |point| {
+        self
+          .dumps
+          .iter()
+          .any(|(at, dumped)| *at == point && (dumped == name || dumped == "all"))
+      };
+      let (before, after) = (dumped(DumpPoint::Before), dumped(DumpPoint::After));
+
+      if before {
+        (self.dump_sink)(&format!("; MIR before {}\n{}", name, module));
+      }
+      let mut stats = Statistics::new();
+      pass.run(module, &mut analyses, &mut stats);
+      #[cfg(debug_assertions)]
+      if let Err(errs) = crate::verify::verify(module) {
+        let errs = errs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        panic!("`{}` produced invalid MIR:\n{}", name, errs.join("\n"));
+      }
+      if after {
+        (self.dump_sink)(&format!("; MIR after {}\n{}", name, module));
+      }
+      self.stats.push((name, stats));
+    }
+    module.compact();
+  }
+
+  /// The statistics as a table, one line per pass run.
+  pub fn report(&self) -> String {
+    let width = self.stats.iter().map(|(name, _)| name.len()).max();
+    let mut report = String::new();
+    for (name, stats) in &self.stats {
+      let line = format!("{:width$}  {}", name, stats, width = width.unwrap());
+      report.push_str(line.trim_end());
+      report.push('\n');
+    }
+    report
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{cell::RefCell, rc::Rc};
+
+  use super::*;
+  use crate::mir::parser::parse_module;
+
+  const SOURCE: &str = "\
+module test
+
+func main() -> i32 {
+entry.0:
+  %0 = alloca i32
+  store %0, 3
+  %2 = load i32 %0
+  %3 = add i32 69, %2
+  ret i32 %3
+}
+";
+
+  #[test]
+  fn test_runs_pipeline() {
+    let mut module = parse_module(SOURCE).unwrap();
+    let mut manager = PassManager::with_level(OptLevel::O1);
+    assert_eq!(
+      manager.get_passes().collect::<Vec<_>>(),
+      ["mem2reg", "const-fold", "dce"]
+    );
+    manager.run(&mut module);
+    assert!(
+      module.to_string().ends_with("entry.0:\n  ret i32 72\n}\n"),
+      "{}",
+      module
+    );
+    assert_eq!(manager.get_stats()[1].1.get("instructions folded"), 1);
+    assert_eq!(
+      manager.report(),
+      "\
+mem2reg     1 slots promoted
+const-fold  1 instructions folded
+dce         0 instructions removed
+"
+    );
+
+    let mut module = parse_module(SOURCE).unwrap();
+    let mut manager = PassManager::with_level(OptLevel::O0);
+    manager.run(&mut module);
+    assert_eq!(module.to_string(), SOURCE);
+    assert_eq!(manager.report(), "");
+  }
+
+  #[test]
+  fn test_dumps_around_passes() {
+    let dumps = Rc::new(RefCell::new(String::new()));
+    let mut manager = PassManager::new();
+    manager.add_pass(create_pass("mem2reg").unwrap());
+    manager.add_pass(create_pass("const-fold").unwrap());
+    manager.add_dump(DumpPoint::Before, "mem2reg");
+    manager.add_dump(DumpPoint::After, "all");
+    let sink = dumps.clone();
+    manager.set_dump_sink(move |text| sink.borrow_mut().push_str(text));
+
+    let mut module = parse_module(SOURCE).unwrap();
+    manager.run(&mut module);
+    let dumps = dumps.borrow();
+    let headers = dumps
+      .lines()
+      .filter(|line| line.starts_with(';'))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      headers,
+      [
+        "; MIR before mem2reg",
+        "; MIR after mem2reg",
+        "; MIR after const-fold"
+      ]
+    );
+    assert!(dumps.contains(&format!("; MIR before mem2reg\n{}", SOURCE)));
+    assert!(create_pass("gvn").is_none());
+  }
+}
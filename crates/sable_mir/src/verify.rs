@@ -0,0 +1,423 @@
+//! Checks the structural and type invariants every `MirModule` has to hold
+//! once lowering is done:
+//!
+//! - every block ends with its only terminator,
+//! - operands refer to existing instructions that produce a value, stores
+//!   and loads go through `alloca` slots or parameters,
+//! - operand, result, argument and return types agree,
+//! - phis start their block and have one incoming value per predecessor.
+
+use sable_parser::info::ValType;
+
+use crate::{
+  analysis::cfg::predecessors,
+  error::{VerifyError, VerifyErrorKind},
+  mir::{
+    function::{MirFunction, block::MirBlockId},
+    instruction::{Instruction, MirInstId},
+    module::MirModule,
+    place::Place,
+    printer::{block_label, produces_value},
+    value::{Constant, MirValue},
+  },
+};
+
+/// Verifies every function of `module`, reporting all violations found.
+pub fn verify(module: &MirModule) -> Result<(), Vec<VerifyError>> {
+  let mut errors = Vec::new();
+  for func in module.get_funcs() {
+    FunctionVerifier {
+      module,
+      func,
+      preds: Vec::new(),
+      block: None,
+      inst: None,
+      errors: &mut errors,
+    }
+    .verify();
+  }
+
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
+
+/// The type of the value of a constant.
+pub fn constant_type(constant: &Constant) -> ValType {
+  match constant {
+    Constant::Null => ValType::Void,
+    Constant::IntValue(type_, _) | Constant::FloatValue(type_, _) => type_.clone(),
+    Constant::BoolValue(_) => ValType::Bool,
+  }
+}
+
+struct FunctionVerifier<'a, 'ctx> {
+  module: &'a MirModule<'ctx>,
+  func: &'a MirFunction<'ctx>,
+  preds: Vec<Vec<MirBlockId>>,
+  block: Option<MirBlockId>,
+  inst: Option<MirInstId>,
+  errors: &'a mut Vec<VerifyError>,
+}
+
+impl FunctionVerifier<'_, '_> {
+  fn report(&mut self, kind: VerifyErrorKind) {
+    let block = self.block.map(|id| block_label(self.func, id));
+    self
+      .errors
+      .push(VerifyError::new(self.func.name(), block, self.inst, kind));
+  }
+
+  fn verify(mut self) {
+    if self.func.get_blocks().is_empty() {
+      self.report(VerifyErrorKind::NoBlocks);
+      return;
+    }
+    self.preds = predecessors(self.func);
+
+    for idx in 0..self.func.get_blocks().len() {
+      let id = MirBlockId(idx);
+      self.block = Some(id);
+      self.inst = None;
+      if self.func.get_terminator(id).is_none() {
+        self.report(VerifyErrorKind::MissingTerminator);
+      }
+      let mut in_phis = true;
+      let len = self.func.get_insts(id).len();
+      for (pos, (inst_id, inst)) in self.func.get_insts(id).enumerate() {
+        self.inst = Some(inst_id);
+        if inst.is_terminator() && pos + 1 != len {
+          self.report(VerifyErrorKind::TerminatorNotLast);
+        }
+        match inst {
+          Instruction::Phi(_) if !in_phis => self.report(VerifyErrorKind::MisplacedPhi),
+          Instruction::Phi(_) => {}
+          _ => in_phis = false,
+        }
+        self.verify_inst(inst);
+      }
+    }
+  }
+
+  fn inst_at(&self, id: MirInstId) -> Option<&Instruction> {
+    self.func.get_inst(id)
+  }
+
+  /// The type of `value`, or `None` after reporting why it has none.
+  fn value_type(&mut self, value: &MirValue) -> Option<ValType> {
+    let id = match value {
+      MirValue::Constant(constant) => return Some(constant_type(constant)),
+      MirValue::Inst(id) => *id,
+    };
+    let Some(inst) = self.inst_at(id) else {
+      self.report(VerifyErrorKind::UndefinedValue(id));
+      return None;
+    };
+    let type_ = match inst {
+      Instruction::Alloca(_) => {
+        self.report(VerifyErrorKind::SlotAsValue(id));
+        return None;
+      }
+      Instruction::Load(load) => load.by(),
+      Instruction::Add(inst) => inst.type_(),
+      Instruction::Sub(inst) => inst.type_(),
+      Instruction::Mul(inst) => inst.type_(),
+      Instruction::Div(inst) => inst.type_(),
+      Instruction::Call(call) => call.type_(),
+      Instruction::Phi(phi) => phi.type_(),
+      Instruction::Cmp(_) | Instruction::And(_) | Instruction::Or(_) | Instruction::Not(_) => {
+        ValType::Bool
+      }
+      inst => {
+        debug_assert!(!produces_value(inst));
+        self.report(VerifyErrorKind::NotAValue(id));
+        return None;
+      }
+    };
+    Some(type_)
+  }
+
+  /// The type stored in the `alloca` `id`.
+  fn slot_type(&mut self, id: MirInstId) -> Option<ValType> {
+    match self.inst_at(id) {
+      Some(Instruction::Alloca(alloca)) => Some(alloca.type_()),
+      Some(_) => {
+        self.report(VerifyErrorKind::NotASlot(id));
+        None
+      }
+      None => {
+        self.report(VerifyErrorKind::UndefinedValue(id));
+        None
+      }
+    }
+  }
+
+  fn expect_type(&mut self, value: &MirValue, expected: &ValType) {
+    if let Some(found) = self.value_type(value)
+      && found != *expected
+    {
+      self.report(VerifyErrorKind::TypeMismatch {
+        expected: expected.clone(),
+        found,
+      });
+    }
+  }
+
+  fn expect_block(&mut self, id: MirBlockId) {
+    if self.func.get_block(id).is_none() {
+      self.report(VerifyErrorKind::UnknownBlock(id));
+    }
+  }
+
+  /// Checks that `type_` is the type of values that can be stored and
+  /// compared.
+  fn expect_value_type(&mut self, type_: ValType) {
+    if matches!(type_, ValType::Void | ValType::Untyped) {
+      self.report(VerifyErrorKind::IllegalType(type_));
+    }
+  }
+
+  fn verify_binary(&mut self, type_: ValType, lhs: &MirValue, rhs: &MirValue) {
+    if !type_.is_numeric() {
+      self.report(VerifyErrorKind::IllegalType(type_.clone()));
+    }
+    self.expect_type(lhs, &type_);
+    self.expect_type(rhs, &type_);
+  }
+
+  fn verify_inst(&mut self, inst: &Instruction) {
+    match inst {
+      Instruction::Alloca(alloca) => {
+        self.expect_value_type(alloca.type_());
+      }
+      Instruction::Store(store) => {
+        if let Some(type_) = self.slot_type(store.target()) {
+          self.expect_type(store.value(), &type_);
+        }
+      }
+      Instruction::Load(load) => {
+        let type_ = match load.from() {
+          Place::Local(id) => self.slot_type(*id),
+          Place::Param(idx) => match self.func.get_params().get(*idx) {
+            Some(type_) => Some(type_.clone()),
+            None => {
+              self.report(VerifyErrorKind::UnknownParam(*idx));
+              None
+            }
+          },
+        };
+        if let Some(found) = type_
+          && found != load.by()
+        {
+          self.report(VerifyErrorKind::TypeMismatch {
+            expected: load.by(),
+            found,
+          });
+        }
+      }
+      Instruction::Add(inst) => self.verify_binary(inst.type_(), inst.lhs(), inst.rhs()),
+      Instruction::Sub(inst) => self.verify_binary(inst.type_(), inst.lhs(), inst.rhs()),
+      Instruction::Mul(inst) => self.verify_binary(inst.type_(), inst.lhs(), inst.rhs()),
+      Instruction::Div(inst) => self.verify_binary(inst.type_(), inst.lhs(), inst.rhs()),
+      Instruction::Cmp(cmp) => {
+        self.expect_value_type(cmp.type_());
+        self.expect_type(cmp.lhs(), &cmp.type_());
+        self.expect_type(cmp.rhs(), &cmp.type_());
+      }
+      Instruction::And(inst) => {
+        self.expect_type(inst.lhs(), &ValType::Bool);
+        self.expect_type(inst.rhs(), &ValType::Bool);
+      }
+      Instruction::Or(inst) => {
+        self.expect_type(inst.lhs(), &ValType::Bool);
+        self.expect_type(inst.rhs(), &ValType::Bool);
+      }
+      Instruction::Not(inst) => self.expect_type(inst.value(), &ValType::Bool),
+      Instruction::Return(ret) => {
+        let ret_type = self.func.ret_type();
+        if ret.type_() != ret_type {
+          self.report(VerifyErrorKind::TypeMismatch {
+            expected: ret_type.clone(),
+            found: ret.type_(),
+          });
+        }
+        self.expect_type(ret.ret_value(), &ret_type);
+      }
+      Instruction::Call(call) => {
+        let Some(callee) = self.module.get_func(call.callee()) else {
+          self.report(VerifyErrorKind::UnknownFunction(call.callee()));
+          return;
+        };
+        if call.type_() != callee.ret_type() {
+          self.report(VerifyErrorKind::TypeMismatch {
+            expected: callee.ret_type(),
+            found: call.type_(),
+          });
+        }
+        if call.args().len() != callee.get_params().len() {
+          self.report(VerifyErrorKind::ArgumentCount {
+            expected: callee.get_params().len(),
+            found: call.args().len(),
+          });
+        }
+        for (arg, param) in call.args().iter().zip(callee.get_params()) {
+          self.expect_type(arg, param);
+        }
+      }
+      Instruction::Branch(branch) => {
+        self.expect_type(branch.cond(), &ValType::Bool);
+        self.expect_block(branch.then_blk());
+        self.expect_block(branch.else_blk());
+      }
+      Instruction::Jump(jump) => self.expect_block(jump.target()),
+      Instruction::Phi(phi) => {
+        self.expect_value_type(phi.type_());
+        let preds = self.block.map(|blk| self.preds[blk.0].clone());
+        let mut seen = Vec::new();
+        for (blk, value) in phi.incoming() {
+          self.expect_type(value, &phi.type_());
+          if seen.contains(blk) || !preds.as_ref().is_some_and(|preds| preds.contains(blk)) {
+            self.report(VerifyErrorKind::UnexpectedIncoming(*blk));
+          }
+          seen.push(*blk);
+        }
+        for pred in preds.unwrap_or_default() {
+          if !seen.contains(&pred) {
+            self.report(VerifyErrorKind::MissingIncoming(pred));
+          }
+        }
+      }
+      Instruction::Unreachable => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::info::ValType;
+
+  use super::*;
+  use crate::mir::{
+    function::block::MirBlock,
+    instruction::{AddInst, ReturnInst},
+    parser::parse_module,
+  };
+
+  fn errors(source: &str) -> Vec<String> {
+    let module = parse_module(source).unwrap();
+    match verify(&module) {
+      Ok(()) => Vec::new(),
+      Err(errs) => errs.iter().map(ToString::to_string).collect(),
+    }
+  }
+
+  #[test]
+  fn test_accepts_valid_module() {
+    let source = "
+module ok
+func f(i32 %arg0, f32 %arg1) -> bool {
+entry.0:
+  %x = alloca f32
+  %a = load f32 %arg1
+  store %x, %a
+  %b = load f32 %x
+  %c = cmp lt f32 %b, 1.5
+  br %c, yes.1, no.2
+yes.1:
+  ret bool true
+no.2:
+  %n = load i32 %arg0
+  %d = call bool @f(%n, 2.0)
+  %e = not %d
+  ret bool %e
+}
+";
+    assert_eq!(errors(source), Vec::<String>::new());
+  }
+
+  #[test]
+  fn test_reports_violations() {
+    let source = "
+module bad
+func f(i32 %arg0) -> i32 {
+entry.0:
+  %a = load i32 %arg0
+  store %a, 1
+  %b = add i32 %a, 1.5
+  jmp exit.1
+exit.1:
+  %c = call f32 @f(%b, 2)
+}
+";
+    assert_eq!(
+      errors(source),
+      [
+        "in function `f`, block `entry.0`, instruction %1: %0 is not a stack slot",
+        "in function `f`, block `entry.0`, instruction %2: expected type `i32`, found `f32`",
+        "in function `f`, block `exit.1`: block does not end with a terminator",
+        "in function `f`, block `exit.1`, instruction %4: expected type `i32`, found `f32`",
+        "in function `f`, block `exit.1`, instruction %4: expected 1 arguments, found 2",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_reports_bad_phis() {
+    let source = "
+module bad
+func f(bool %arg0) -> i32 {
+entry.0:
+  %c = load bool %arg0
+  br %c, left.1, right.2
+left.1:
+  jmp join.3
+right.2:
+  jmp join.3
+join.3:
+  %a = phi i32 [1, left.1], [2, entry.0]
+  %s = add i32 %a, 1
+  %b = phi f32 [1.5, left.1], [2.5, right.2]
+  ret i32 %s
+}
+";
+    assert_eq!(
+      errors(source),
+      [
+        "in function `f`, block `join.3`, instruction %4: unexpected incoming value for block 0",
+        "in function `f`, block `join.3`, instruction %4: phi has no incoming value for \
+         predecessor 2",
+        "in function `f`, block `join.3`, instruction %6: phi after the start of the block",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_reports_undefined_values() {
+    let mut func = MirFunction::new("f", ValType::I32);
+    let entry = func.add_block(MirBlock::new("entry"));
+    let add = AddInst::new(
+      ValType::I32,
+      MirValue::Inst(MirInstId(7)),
+      MirValue::Constant(Constant::IntValue(ValType::I32, 1)),
+    );
+    for inst in [
+      Instruction::Add(add),
+      Instruction::Return(ReturnInst::new(MirValue::Inst(MirInstId(0)), ValType::I32)),
+    ] {
+      func.append_inst(entry, inst);
+    }
+    let mut module = MirModule::new("m");
+    module.add_func(func);
+
+    let errs = verify(&module).unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].block(), Some("entry.0"));
+    assert_eq!(errs[0].inst(), Some(MirInstId(0)));
+    assert_eq!(
+      errs[0].kind(),
+      &VerifyErrorKind::UndefinedValue(MirInstId(7))
+    );
+  }
+}
@@ -7,6 +7,7 @@ pub mod literal_expr;
 pub mod null_expr;
 pub mod variable_expr;
 pub mod call_expr;
+pub mod unary_expr;
 
 pub use assign_expr::AssignExpression;
 pub use binary_expr::BinaryExpression;
@@ -15,6 +16,7 @@ pub use literal_expr::LiteralExpression;
 pub use null_expr::NullExpression;
 pub use variable_expr::VariableExpression;
 pub use call_expr::CallExpression;
+pub use unary_expr::UnaryExpression;
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
@@ -26,6 +28,7 @@ pub enum Expression<'s> {
   BinaryExpression(binary_expr::BinaryExpression<'s>),
   NullExpression(null_expr::NullExpression),
   CallExpression(call_expr::CallExpression<'s>),
+  UnaryExpression(unary_expr::UnaryExpression<'s>),
 }
 
 impl<'s> Expression<'s> {
@@ -38,6 +41,7 @@ impl<'s> Expression<'s> {
       Expression::BinaryExpression(expr) => expr.get_pos(),
       Expression::NullExpression(expr) => expr.get_pos(),
       Expression::CallExpression(expr) => expr.get_pos(),
+      Expression::UnaryExpression(expr) => expr.get_pos(),
     }
   }
 }
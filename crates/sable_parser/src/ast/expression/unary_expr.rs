@@ -0,0 +1,37 @@
+use crate::{info::OperatorType, position::Position};
+
+use super::Expression;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UnaryExpression<'s> {
+  operator: OperatorType,
+  operand: Box<Expression<'s>>,
+  pos: Position,
+}
+
+impl<'s> UnaryExpression<'s> {
+  pub fn new(operator: OperatorType, operand: Expression<'s>, pos: Position) -> Self {
+    Self {
+      operator,
+      operand: Box::new(operand),
+      pos,
+    }
+  }
+
+  pub fn get_pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn get_operator(&self) -> &OperatorType {
+    &self.operator
+  }
+
+  pub fn get_operand(&self) -> &Expression<'s> {
+    &self.operand
+  }
+
+  pub fn get_operand_mut(&mut self) -> &mut Expression<'s> {
+    &mut self.operand
+  }
+}
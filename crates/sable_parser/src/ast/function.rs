@@ -1,4 +1,7 @@
-use crate::{info::ValType, position::Position};
+use crate::{
+  info::{InlineHint, ValType},
+  position::Position,
+};
 
 use super::expression::block_expr::BlockExpression;
 
@@ -40,6 +43,7 @@ pub struct Function<'s> {
   ret_type: ValType,
   params: Vec<FunctionParameter<'s>>,
   body: BlockExpression<'s>,
+  inline: InlineHint,
 }
 
 impl<'s> Function<'s> {
@@ -56,6 +60,7 @@ impl<'s> Function<'s> {
       ret_type,
       params,
       body,
+      inline: InlineHint::Auto,
     }
   }
 
@@ -82,4 +87,12 @@ impl<'s> Function<'s> {
   pub fn get_params(&self) -> &Vec<FunctionParameter<'s>> {
     &self.params
   }
+
+  pub fn get_inline(&self) -> InlineHint {
+    self.inline
+  }
+
+  pub fn set_inline(&mut self, inline: InlineHint) {
+    self.inline = inline;
+  }
 }
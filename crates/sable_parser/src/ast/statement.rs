@@ -2,11 +2,19 @@ use crate::position::Position;
 
 use super::expression;
 
+pub mod break_stmt;
+pub mod continue_stmt;
+pub mod if_stmt;
 pub mod let_stmt;
 pub mod return_stmt;
+pub mod while_stmt;
 
+pub use break_stmt::BreakStatement;
+pub use continue_stmt::ContinueStatement;
+pub use if_stmt::{ElseBranch, IfStatement};
 pub use let_stmt::LetStatement;
 pub use return_stmt::ReturnStatement;
+pub use while_stmt::WhileStatement;
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
@@ -14,6 +22,10 @@ pub enum Statement<'s> {
   Expression(expression::Expression<'s>),
   ReturnStatement(return_stmt::ReturnStatement<'s>),
   LetStatement(let_stmt::LetStatement<'s>),
+  IfStatement(if_stmt::IfStatement<'s>),
+  WhileStatement(while_stmt::WhileStatement<'s>),
+  BreakStatement(break_stmt::BreakStatement),
+  ContinueStatement(continue_stmt::ContinueStatement),
 }
 
 impl<'s> Statement<'s> {
@@ -22,6 +34,10 @@ impl<'s> Statement<'s> {
       Statement::Expression(expr) => expr.get_pos(),
       Statement::ReturnStatement(stmt) => stmt.get_pos(),
       Statement::LetStatement(stmt) => stmt.get_pos(),
+      Statement::IfStatement(stmt) => stmt.get_pos(),
+      Statement::WhileStatement(stmt) => stmt.get_pos(),
+      Statement::BreakStatement(stmt) => stmt.get_pos(),
+      Statement::ContinueStatement(stmt) => stmt.get_pos(),
     }
   }
 }
@@ -0,0 +1,17 @@
+use crate::position::Position;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BreakStatement {
+  pos: Position,
+}
+
+impl BreakStatement {
+  pub fn new(pos: Position) -> Self {
+    Self { pos }
+  }
+
+  pub fn get_pos(&self) -> Position {
+    self.pos.clone()
+  }
+}
@@ -0,0 +1,73 @@
+use crate::{
+  ast::expression::{BlockExpression, Expression},
+  position::Position,
+};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ElseBranch<'s> {
+  Block(BlockExpression<'s>),
+  If(Box<IfStatement<'s>>),
+}
+
+impl ElseBranch<'_> {
+  pub fn get_pos(&self) -> Position {
+    match self {
+      ElseBranch::Block(block) => block.get_pos(),
+      ElseBranch::If(if_stmt) => if_stmt.get_pos(),
+    }
+  }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IfStatement<'s> {
+  condition: Expression<'s>,
+  then_block: BlockExpression<'s>,
+  else_branch: Option<ElseBranch<'s>>,
+  pos: Position,
+}
+
+impl<'s> IfStatement<'s> {
+  pub fn new(
+    condition: Expression<'s>,
+    then_block: BlockExpression<'s>,
+    else_branch: Option<ElseBranch<'s>>,
+    pos: Position,
+  ) -> Self {
+    Self {
+      condition,
+      then_block,
+      else_branch,
+      pos,
+    }
+  }
+
+  pub fn get_pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn get_condition(&self) -> &Expression<'s> {
+    &self.condition
+  }
+
+  pub fn get_condition_mut(&mut self) -> &mut Expression<'s> {
+    &mut self.condition
+  }
+
+  pub fn get_then_block(&self) -> &BlockExpression<'s> {
+    &self.then_block
+  }
+
+  pub fn get_then_block_mut(&mut self) -> &mut BlockExpression<'s> {
+    &mut self.then_block
+  }
+
+  pub fn get_else_branch(&self) -> Option<&ElseBranch<'s>> {
+    self.else_branch.as_ref()
+  }
+
+  pub fn get_else_branch_mut(&mut self) -> Option<&mut ElseBranch<'s>> {
+    self.else_branch.as_mut()
+  }
+}
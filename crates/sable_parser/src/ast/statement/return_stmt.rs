@@ -1,31 +1,27 @@
-use crate::{ast::expression::Expression, info::ValType, position::Position};
+use crate::{ast::expression::Expression, position::Position};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ReturnStatement<'s> {
-  value: Expression<'s>,
-  type_: ValType,
+  value: Option<Expression<'s>>,
   pos: Position,
 }
 
 impl<'s> ReturnStatement<'s> {
-  pub fn new(value: Expression<'s>, type_: ValType, pos: Position) -> Self {
-    Self { value, type_, pos }
+  pub fn new(value: Option<Expression<'s>>, pos: Position) -> Self {
+    Self { value, pos }
   }
 
   pub fn get_pos(&self) -> Position {
     self.pos.clone()
   }
 
-  pub fn get_value(&self) -> &Expression<'s> {
-    &self.value
+  /// The returned value, `None` for a bare `return;`.
+  pub fn get_value(&self) -> Option<&Expression<'s>> {
+    self.value.as_ref()
   }
 
-  pub fn get_value_mut(&mut self) -> &mut Expression<'s> {
-    &mut self.value
-  }
-
-  pub fn get_type(&self) -> ValType {
-    self.type_.clone()
+  pub fn get_value_mut(&mut self) -> Option<&mut Expression<'s>> {
+    self.value.as_mut()
   }
 }
@@ -0,0 +1,42 @@
+use crate::{
+  ast::expression::{BlockExpression, Expression},
+  position::Position,
+};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WhileStatement<'s> {
+  condition: Expression<'s>,
+  body: BlockExpression<'s>,
+  pos: Position,
+}
+
+impl<'s> WhileStatement<'s> {
+  pub fn new(condition: Expression<'s>, body: BlockExpression<'s>, pos: Position) -> Self {
+    Self {
+      condition,
+      body,
+      pos,
+    }
+  }
+
+  pub fn get_pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn get_condition(&self) -> &Expression<'s> {
+    &self.condition
+  }
+
+  pub fn get_condition_mut(&mut self) -> &mut Expression<'s> {
+    &mut self.condition
+  }
+
+  pub fn get_body(&self) -> &BlockExpression<'s> {
+    &self.body
+  }
+
+  pub fn get_body_mut(&mut self) -> &mut BlockExpression<'s> {
+    &mut self.body
+  }
+}
@@ -6,20 +6,40 @@ pub enum ValType {
   Untyped,
   I32,
   F32,
+  Bool,
   Void,
 }
 
+impl ValType {
+  pub fn is_numeric(&self) -> bool {
+    matches!(self, ValType::I32 | ValType::F32)
+  }
+}
+
 impl Display for ValType {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       ValType::Untyped => write!(f, "untyped"),
       ValType::I32 => write!(f, "i32"),
       ValType::F32 => write!(f, "f32"),
+      ValType::Bool => write!(f, "bool"),
       ValType::Void => write!(f, "void"),
     }
   }
 }
 
+/// Whether calls to a function should be inlined, as annotated on its
+/// declaration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum InlineHint {
+  /// Up to the inliner's heuristic.
+  #[default]
+  Auto,
+  Always,
+  Never,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum OperatorType {
@@ -27,4 +47,62 @@ pub enum OperatorType {
   Sub,
   Mul,
   Div,
+  Eq,
+  NotEq,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  And,
+  Or,
+  Not,
+}
+
+impl OperatorType {
+  pub fn is_arithmetic(&self) -> bool {
+    matches!(
+      self,
+      OperatorType::Add | OperatorType::Sub | OperatorType::Mul | OperatorType::Div
+    )
+  }
+
+  pub fn is_comparison(&self) -> bool {
+    matches!(
+      self,
+      OperatorType::Eq
+        | OperatorType::NotEq
+        | OperatorType::Lt
+        | OperatorType::Le
+        | OperatorType::Gt
+        | OperatorType::Ge
+    )
+  }
+
+  pub fn is_logical(&self) -> bool {
+    matches!(
+      self,
+      OperatorType::And | OperatorType::Or | OperatorType::Not
+    )
+  }
+}
+
+impl Display for OperatorType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let symbol = match self {
+      OperatorType::Add => "+",
+      OperatorType::Sub => "-",
+      OperatorType::Mul => "*",
+      OperatorType::Div => "/",
+      OperatorType::Eq => "==",
+      OperatorType::NotEq => "!=",
+      OperatorType::Lt => "<",
+      OperatorType::Le => "<=",
+      OperatorType::Gt => ">",
+      OperatorType::Ge => ">=",
+      OperatorType::And => "&&",
+      OperatorType::Or => "||",
+      OperatorType::Not => "!",
+    };
+    write!(f, "{}", symbol)
+  }
 }
@@ -12,11 +12,21 @@ use super::token::{Token, TokenData, TokenType};
 const KEYWORDS: phf::Map<&'static str, (TokenType, Option<TokenData>)> = phf_map! {
     "i32" => (TokenType::Type, Some(TokenData::Type(ValType::I32))),
     "f32" => (TokenType::Type, Some(TokenData::Type(ValType::F32))),
+    "bool" => (TokenType::Type, Some(TokenData::Type(ValType::Bool))),
+    "true" => (TokenType::Boolean, Some(TokenData::Type(ValType::Bool))),
+    "false" => (TokenType::Boolean, Some(TokenData::Type(ValType::Bool))),
     "func" => (TokenType::Func, None),
+    "inline" => (TokenType::Inline, None),
+    "noinline" => (TokenType::NoInline, None),
     "return" => (TokenType::Return, None),
     "let" => (TokenType::Let, None),
     "void" => (TokenType::Type, Some(TokenData::Type(ValType::Void))),
     "null" => (TokenType::Null, None),
+    "if" => (TokenType::If, None),
+    "else" => (TokenType::Else, None),
+    "while" => (TokenType::While, None),
+    "break" => (TokenType::Break, None),
+    "continue" => (TokenType::Continue, None),
 };
 
 pub struct Lexer<'s> {
@@ -56,6 +66,10 @@ impl<'s> Lexer<'s> {
     Token::new(token_type, self.get_lexeme(), self.get_pos(), data)
   }
 
+  fn get_operator(&self, token_type: TokenType, operator: OperatorType) -> Token<'s> {
+    self.get_token_with_data(token_type, Some(TokenData::Operator(operator)))
+  }
+
   fn get_lexeme(&self) -> &'s str {
     if self.current > self.start {
       &self.source[self.start..self.current]
@@ -128,7 +142,7 @@ impl<'s> Lexer<'s> {
 
   fn lex_number(&mut self) -> Token<'s> {
     while let Some(c) = self.get_char() {
-      if c.is_digit(10) {
+      if c.is_ascii_digit() {
         self.advance();
       } else {
         break;
@@ -138,7 +152,7 @@ impl<'s> Lexer<'s> {
     if self.get_char() == Some('.') {
       self.advance();
       while let Some(c) = self.get_char() {
-        if c.is_digit(10) {
+        if c.is_ascii_digit() {
           self.advance();
         } else {
           break;
@@ -159,6 +173,16 @@ impl<'s> Lexer<'s> {
     }
   }
 
+  /// Consumes the next character if it is `expected`.
+  fn match_char(&mut self, expected: char) -> bool {
+    if self.get_char() == Some(expected) {
+      self.advance();
+      true
+    } else {
+      false
+    }
+  }
+
   fn next(&mut self) -> Token<'s> {
     self.lex_trivial();
     self.start = self.current;
@@ -180,7 +204,16 @@ impl<'s> Lexer<'s> {
       ':' => self.get_token(TokenType::Colon),
       ',' => self.get_token(TokenType::Comma),
       ';' => self.get_token(TokenType::Semicolon),
+      '=' if self.match_char('=') => self.get_operator(TokenType::Eq, OperatorType::Eq),
       '=' => self.get_token(TokenType::Assign),
+      '!' if self.match_char('=') => self.get_operator(TokenType::NotEq, OperatorType::NotEq),
+      '!' => self.get_operator(TokenType::Not, OperatorType::Not),
+      '<' if self.match_char('=') => self.get_operator(TokenType::Le, OperatorType::Le),
+      '<' => self.get_operator(TokenType::Lt, OperatorType::Lt),
+      '>' if self.match_char('=') => self.get_operator(TokenType::Ge, OperatorType::Ge),
+      '>' => self.get_operator(TokenType::Gt, OperatorType::Gt),
+      '&' if self.match_char('&') => self.get_operator(TokenType::And, OperatorType::And),
+      '|' if self.match_char('|') => self.get_operator(TokenType::Or, OperatorType::Or),
       '+' => self.get_token_with_data(
         TokenType::Plus,
         Some(TokenData::Operator(OperatorType::Add)),
@@ -191,15 +224,15 @@ impl<'s> Lexer<'s> {
       ),
       '*' => self.get_token_with_data(TokenType::Mul, Some(TokenData::Operator(OperatorType::Mul))),
       '/' => {
-        return if self.get_char() == Some('/') {
+        if self.get_char() == Some('/') {
           self.advance();
           self.lex_comment();
           self.next()
         } else {
           self.get_token_with_data(TokenType::Div, Some(TokenData::Operator(OperatorType::Div)))
-        };
+        }
       }
-      _ => return self.get_token(TokenType::Err),
+      _ => self.get_token(TokenType::Err),
     }
   }
 
@@ -263,6 +296,50 @@ mod tests {
     assert_eq!(token.lexeme, "f32");
   }
 
+  #[test]
+  fn test_lexing_booleans() {
+    let source = "bool true false";
+    let mut lexer = Lexer::new(source);
+
+    let token = lexer.lex();
+    assert_eq!(token.token_type, TokenType::Type);
+    assert_eq!(token.data, Some(TokenData::Type(ValType::Bool)));
+
+    let token = lexer.lex();
+    assert_eq!(token.token_type, TokenType::Boolean);
+    assert_eq!(token.lexeme, "true");
+
+    let token = lexer.lex();
+    assert_eq!(token.token_type, TokenType::Boolean);
+    assert_eq!(token.lexeme, "false");
+  }
+
+  #[test]
+  fn lex_operators() {
+    let source = "== != < <= > >= && || ! = & |";
+    let mut lexer = Lexer::new(source);
+
+    let expected = [
+      (TokenType::Eq, "=="),
+      (TokenType::NotEq, "!="),
+      (TokenType::Lt, "<"),
+      (TokenType::Le, "<="),
+      (TokenType::Gt, ">"),
+      (TokenType::Ge, ">="),
+      (TokenType::And, "&&"),
+      (TokenType::Or, "||"),
+      (TokenType::Not, "!"),
+      (TokenType::Assign, "="),
+      (TokenType::Err, "&"),
+      (TokenType::Err, "|"),
+    ];
+    for (token_type, lexeme) in expected {
+      let token = lexer.lex();
+      assert_eq!(token.token_type, token_type);
+      assert_eq!(token.lexeme, lexeme);
+    }
+  }
+
   #[test]
   fn lex_symbols() {
     let source = "({:},)";
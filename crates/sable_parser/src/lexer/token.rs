@@ -14,13 +14,21 @@ pub enum TokenType {
   Identifier,
   Integer,
   Float,
+  Boolean,
 
   // Keywords
   Type,
   Func,
+  Inline,
+  NoInline,
   Return,
   Let,
   Null,
+  If,
+  Else,
+  While,
+  Break,
+  Continue,
 
   // Operators
   Assign,
@@ -28,6 +36,15 @@ pub enum TokenType {
   Minus,
   Mul,
   Div,
+  Eq,
+  NotEq,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  And,
+  Or,
+  Not,
 
   // Symbols
   Paren(bool),
@@ -1,2 +1,4 @@
 pub mod error;
+#[allow(clippy::module_inception)]
 pub mod parser;
+pub mod precedence;
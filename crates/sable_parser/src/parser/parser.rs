@@ -7,12 +7,15 @@ use crate::{
     ast::AST,
     expression::{
       AssignExpression, BinaryExpression, BlockExpression, CallExpression, Expression,
-      LiteralExpression, NullExpression, VariableExpression,
+      LiteralExpression, NullExpression, UnaryExpression, VariableExpression,
     },
     function::{Function, FunctionParameter},
-    statement::{LetStatement, ReturnStatement, Statement},
+    statement::{
+      BreakStatement, ContinueStatement, ElseBranch, IfStatement, LetStatement, ReturnStatement,
+      Statement, WhileStatement,
+    },
   },
-  info::ValType,
+  info::InlineHint,
   lexer::{
     lexer::Lexer,
     token::{Token, TokenData, TokenType},
@@ -21,9 +24,12 @@ use crate::{
   position::Position,
 };
 
-use super::error::{
-  ParserError,
-  unexpected_token::{MAX_EXPECTED, UnexpectedTokenError},
+use super::{
+  error::{
+    ParserError,
+    unexpected_token::{MAX_EXPECTED, UnexpectedTokenError},
+  },
+  precedence::{infix_operator, is_prefix_operator},
 };
 
 macro_rules! next {
@@ -42,14 +48,14 @@ macro_rules! next {
   }};
 }
 
-pub struct Parser<'s> {
-  lexer: &'s mut Lexer<'s>,
+pub struct Parser<'l, 's> {
+  lexer: &'l mut Lexer<'s>,
   ast: Rc<RefCell<AST<'s>>>,
   errs: Vec<ParserError<'s>>,
 }
 
-impl<'s> Parser<'s> {
-  pub fn new(lexer: &'s mut Lexer<'s>) -> Parser<'s> {
+impl<'l, 's> Parser<'l, 's> {
+  pub fn new(lexer: &'l mut Lexer<'s>) -> Parser<'l, 's> {
     Parser {
       lexer,
       ast: Rc::new(RefCell::new(AST::new())),
@@ -98,7 +104,7 @@ impl<'s> Parser<'s> {
       if token.token_type == TokenType::Eof {
         break;
       }
-      if expected.iter().any(|t| *t == token.token_type) {
+      if expected.contains(&token.token_type) {
         break;
       }
       self.lexer.lex();
@@ -136,9 +142,9 @@ impl<'s> Parser<'s> {
   }
 
   fn parse_factor(&mut self) -> Result<Expression<'s>, ParserError<'s>> {
-    let tok = next!(@plain self, [TokenType::Integer, TokenType::Float, TokenType::Identifier, TokenType::Null, TokenType::Paren(true)]);
-    return match tok.token_type {
-      TokenType::Integer | TokenType::Float => {
+    let tok = next!(@plain self, [TokenType::Integer, TokenType::Float, TokenType::Boolean, TokenType::Identifier, TokenType::Null, TokenType::Paren(true)]);
+    match tok.token_type {
+      TokenType::Integer | TokenType::Float | TokenType::Boolean => {
         let val = match tok.data {
           Some(TokenData::Type(ty)) => ty,
           _ => unreachable!(),
@@ -168,43 +174,50 @@ impl<'s> Parser<'s> {
         Ok(expr)
       }
       _ => unreachable!(),
-    };
+    }
   }
 
-  fn parse_term(&mut self) -> Result<Expression<'s>, ParserError<'s>> {
-    let lhs = self.parse_factor()?;
+  fn parse_expression(&mut self) -> Result<Expression<'s>, ParserError<'s>> {
+    self.parse_binary(0)
+  }
 
-    if self.peek(smallvec![TokenType::Mul, TokenType::Div]) {
-      let tok = next!(@plain self, [TokenType::Mul, TokenType::Div]);
-      let operator = match tok.data {
-        Some(TokenData::Operator(op)) => op,
-        _ => unreachable!(),
-      };
-      let rhs = self.parse_term()?;
-      let pos = tok.pos.merge(rhs.get_pos());
-      let expr = Expression::BinaryExpression(BinaryExpression::new(lhs, operator, rhs, pos));
-      return Ok(expr);
+  fn parse_unary(&mut self) -> Result<Expression<'s>, ParserError<'s>> {
+    if !is_prefix_operator(&self.lexer.peek().token_type) {
+      return self.parse_factor();
     }
 
-    return Ok(lhs);
+    let tok = self.lexer.lex();
+    let operator = match tok.data {
+      Some(TokenData::Operator(op)) => op,
+      _ => unreachable!(),
+    };
+    let operand = self.parse_unary()?;
+    let pos = tok.pos.merge(operand.get_pos());
+    Ok(Expression::UnaryExpression(UnaryExpression::new(
+      operator, operand, pos,
+    )))
   }
 
-  fn parse_expression(&mut self) -> Result<Expression<'s>, ParserError<'s>> {
-    let lhs = self.parse_term()?;
+  fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression<'s>, ParserError<'s>> {
+    let mut lhs = self.parse_unary()?;
+
+    loop {
+      let op = match infix_operator(&self.lexer.peek().token_type) {
+        Some(op) if op.precedence >= min_precedence => op,
+        _ => break,
+      };
 
-    if self.peek(smallvec![TokenType::Plus, TokenType::Minus]) {
-      let tok = next!(@plain self, [TokenType::Plus, TokenType::Minus]);
+      let tok = self.lexer.lex();
       let operator = match tok.data {
         Some(TokenData::Operator(op)) => op,
         _ => unreachable!(),
       };
-      let rhs = self.parse_expression()?;
-      let pos = tok.pos.merge(rhs.get_pos());
-      let expr = Expression::BinaryExpression(BinaryExpression::new(lhs, operator, rhs, pos));
-      return Ok(expr);
+      let rhs = self.parse_binary(op.rhs_precedence())?;
+      let pos = lhs.get_pos().merge(rhs.get_pos());
+      lhs = Expression::BinaryExpression(BinaryExpression::new(lhs, operator, rhs, pos));
     }
 
-    return Ok(lhs);
+    Ok(lhs)
   }
 
   fn parse_variable_declaration(&mut self) -> Result<LetStatement<'s>, ParserError<'s>> {
@@ -230,18 +243,19 @@ impl<'s> Parser<'s> {
       return Ok(var_decl);
     }
 
-    let tok_res = self.next(smallvec![TokenType::Semicolon, TokenType::Comma]);
-    if tok_res.is_ok() {
-      unreachable!()
+    match self.next(smallvec![TokenType::Semicolon, TokenType::Comma]) {
+      Ok(_) => unreachable!(),
+      Err(err) => Err(err),
     }
-    return Err(tok_res.unwrap_err());
   }
 
   fn parse_statement(&mut self) -> Result<Statement<'s>, ParserError<'s>> {
     if self.peek(smallvec![
       TokenType::Integer,
       TokenType::Float,
-      TokenType::Identifier
+      TokenType::Boolean,
+      TokenType::Identifier,
+      TokenType::Not
     ]) {
       let expr = self.parse_expression()?;
       next!(@plain self, [TokenType::Semicolon]);
@@ -249,28 +263,91 @@ impl<'s> Parser<'s> {
       return Ok(stmt);
     }
 
-    let tok = next!(@plain self, [TokenType::Return, TokenType::Let]);
-    return match tok.token_type {
+    let tok = next!(@plain self, [
+      TokenType::Return,
+      TokenType::Let,
+      TokenType::If,
+      TokenType::While,
+      TokenType::Break,
+      TokenType::Continue
+    ]);
+    match tok.token_type {
       TokenType::Return => {
+        if self.peek(smallvec![TokenType::Semicolon]) {
+          next!(@plain self, [TokenType::Semicolon]);
+          return Ok(Statement::ReturnStatement(ReturnStatement::new(
+            None, tok.pos,
+          )));
+        }
         let expr = self.parse_expression()?;
         let pos = tok.pos.merge(expr.get_pos());
         next!(@plain self, [TokenType::Semicolon]);
-        let stmt = Statement::ReturnStatement(ReturnStatement::new(expr, ValType::Untyped, pos));
+        let stmt = Statement::ReturnStatement(ReturnStatement::new(Some(expr), pos));
         Ok(stmt)
       }
       TokenType::Let => {
-        let res = self.parse_variable_declaration();
-        if res.is_err() {
-          return Err(res.unwrap_err());
-        }
-
-        let var_decl = res.unwrap();
+        let var_decl = self.parse_variable_declaration()?;
         Ok(Statement::LetStatement(var_decl))
       }
+      TokenType::If => {
+        let if_stmt = self.parse_if(tok)?;
+        Ok(Statement::IfStatement(if_stmt))
+      }
+      TokenType::While => {
+        let condition = self.parse_expression()?;
+        let body = self.parse_block()?;
+        let pos = tok.pos.merge(body.get_pos());
+        Ok(Statement::WhileStatement(WhileStatement::new(
+          condition, body, pos,
+        )))
+      }
+      TokenType::Break => {
+        next!(@plain self, [TokenType::Semicolon]);
+        Ok(Statement::BreakStatement(BreakStatement::new(tok.pos)))
+      }
+      TokenType::Continue => {
+        next!(@plain self, [TokenType::Semicolon]);
+        Ok(Statement::ContinueStatement(ContinueStatement::new(
+          tok.pos,
+        )))
+      }
       _ => unreachable!(),
+    }
+  }
+
+  fn parse_block(&mut self) -> Result<BlockExpression<'s>, ParserError<'s>> {
+    let open = next!(@plain self, [TokenType::Brace(true)]);
+    let mut statements = Vec::new();
+    while !self.peek(smallvec![TokenType::Brace(false), TokenType::Eof]) {
+      statements.push(self.parse_statement()?);
+    }
+    let close = next!(@plain self, [TokenType::Brace(false)]);
+    Ok(BlockExpression::new(statements, open.pos.merge(close.pos)))
+  }
+
+  fn parse_if(&mut self, if_tok: Token<'s>) -> Result<IfStatement<'s>, ParserError<'s>> {
+    let condition = self.parse_expression()?;
+    let then_block = self.parse_block()?;
+    let mut pos = if_tok.pos.merge(then_block.get_pos());
+
+    let else_branch = if self.peek(smallvec![TokenType::Else]) {
+      next!(@plain self, [TokenType::Else]);
+      let branch = if self.peek(smallvec![TokenType::If]) {
+        let tok = next!(@plain self, [TokenType::If]);
+        ElseBranch::If(Box::new(self.parse_if(tok)?))
+      } else {
+        ElseBranch::Block(self.parse_block()?)
+      };
+      pos = pos.merge(branch.get_pos());
+      Some(branch)
+    } else {
+      None
     };
+
+    Ok(IfStatement::new(condition, then_block, else_branch, pos))
   }
 
+  #[allow(clippy::result_large_err)]
   fn parse_body(
     &mut self,
   ) -> Result<BlockExpression<'s>, SmallVec<[ParserError<'s>; MAX_EXPECTED]>> {
@@ -279,7 +356,7 @@ impl<'s> Parser<'s> {
 
     let mut pos: Option<Position> = None;
 
-    while !self.peek(smallvec![TokenType::Brace(false)]) {
+    while !self.peek(smallvec![TokenType::Brace(false), TokenType::Eof]) {
       let statement = self.parse_statement();
       match statement {
         Ok(stmt) => {
@@ -318,6 +395,7 @@ impl<'s> Parser<'s> {
     Ok(FunctionParameter::new(name.lexeme, pos, ty))
   }
 
+  #[allow(clippy::result_large_err)]
   fn parse_function(&mut self) -> Result<Function<'s>, SmallVec<[ParserError<'s>; MAX_EXPECTED]>> {
     let type_ = next!(@vec self, [TokenType::Type]);
     let ret_ty = match type_.data {
@@ -329,11 +407,10 @@ impl<'s> Parser<'s> {
     next!(@vec self, [TokenType::Paren(true)]);
     let mut params = Vec::new();
     while !self.peek(smallvec![TokenType::Paren(false)]) {
-      let param = self.parse_param();
-      if param.is_err() {
-        return Err(smallvec![param.unwrap_err()]);
-      }
-      let param = param.unwrap();
+      let param = match self.parse_param() {
+        Ok(param) => param,
+        Err(err) => return Err(smallvec![err]),
+      };
       params.push(param);
       if self.peek(smallvec![TokenType::Comma]) {
         next!(@vec self, [TokenType::Comma]);
@@ -341,11 +418,7 @@ impl<'s> Parser<'s> {
     }
     next!(@vec self, [TokenType::Paren(false)]);
     next!(@vec self, [TokenType::Brace(true)]);
-    let body = self.parse_body();
-    if body.is_err() {
-      return Err(body.unwrap_err());
-    }
-    let body = body.unwrap();
+    let body = self.parse_body()?;
 
     next!(@vec self, [TokenType::Brace(false)]);
 
@@ -353,27 +426,63 @@ impl<'s> Parser<'s> {
     Ok(Function::new(name.lexeme, params, ty_pos, ret_ty, body))
   }
 
+  /// Parses a function after its first token, `func` or the annotation
+  /// before it.
+  #[allow(clippy::result_large_err)]
+  fn parse_item(
+    &mut self,
+    first: TokenType,
+  ) -> Result<Function<'s>, SmallVec<[ParserError<'s>; MAX_EXPECTED]>> {
+    let inline = match first {
+      TokenType::Inline => InlineHint::Always,
+      TokenType::NoInline => InlineHint::Never,
+      _ => InlineHint::Auto,
+    };
+    if inline != InlineHint::Auto {
+      next!(@vec self, [TokenType::Func]);
+    }
+    let mut func = self.parse_function()?;
+    func.set_inline(inline);
+    Ok(func)
+  }
+
   pub fn get_ast(&self) -> Rc<RefCell<AST<'s>>> {
     self.ast.clone()
   }
 
   pub fn parse(&mut self) -> Result<Rc<RefCell<AST<'s>>>, &[ParserError<'s>]> {
     loop {
-      let tok = self.next(smallvec![TokenType::Func, TokenType::Eof]);
-      if tok.is_err() {
-        self.errs.push(tok.unwrap_err());
-        self.sync(smallvec![TokenType::Func, TokenType::Eof]);
-        continue;
-      }
-      let tok = tok.unwrap();
+      let tok = match self.next(smallvec![
+        TokenType::Func,
+        TokenType::Inline,
+        TokenType::NoInline,
+        TokenType::Eof
+      ]) {
+        Ok(tok) => tok,
+        Err(err) => {
+          self.errs.push(err);
+          self.sync(smallvec![
+            TokenType::Func,
+            TokenType::Inline,
+            TokenType::NoInline,
+            TokenType::Eof
+          ]);
+          continue;
+        }
+      };
 
       match tok.token_type {
-        TokenType::Func => {
-          let res = self.parse_function();
+        TokenType::Func | TokenType::Inline | TokenType::NoInline => {
+          let res = self.parse_item(tok.token_type);
           match res {
             Ok(f) => self.ast.borrow_mut().add_func(f),
             Err(errs) => {
-              self.sync(smallvec![TokenType::Func, TokenType::Eof]);
+              self.sync(smallvec![
+                TokenType::Func,
+                TokenType::Inline,
+                TokenType::NoInline,
+                TokenType::Eof
+              ]);
               for e in errs.iter() {
                 self.errs.push(e.clone())
               }
@@ -409,13 +518,199 @@ mod tests {
     assert_eq!(token.lexeme, "abc");
   }
 
+  fn parse_return_expr(expr: &str, check: impl FnOnce(&Expression)) {
+    let source = format!("func i32 f() {{ return {expr}; }}");
+    let mut lexer = Lexer::new(&source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let ast = ast.borrow();
+    let func = ast.get_funcs()[0].borrow();
+    match &func.get_body().get_stmts()[0] {
+      Statement::ReturnStatement(ret) => check(ret.get_value().unwrap()),
+      stmt => panic!("expected a return statement, found {:?}", stmt),
+    }
+  }
+
+  fn sexpr(expr: &Expression) -> String {
+    match expr {
+      Expression::LiteralExpression(lit) => lit.get_value().to_string(),
+      Expression::VariableExpression(var) => var.get_name().to_string(),
+      Expression::BinaryExpression(bin) => format!(
+        "({} {} {})",
+        bin.get_operator(),
+        sexpr(bin.get_left()),
+        sexpr(bin.get_right())
+      ),
+      Expression::UnaryExpression(unary) => {
+        format!("({} {})", unary.get_operator(), sexpr(unary.get_operand()))
+      }
+      other => panic!("unexpected expression {:?}", other),
+    }
+  }
+
+  fn assert_tree(expr: &str, expected: &str) {
+    parse_return_expr(expr, |e| {
+      assert_eq!(sexpr(e), expected, "while parsing `{expr}`")
+    });
+  }
+
+  #[test]
+  fn test_binary_left_associative() {
+    assert_tree("10 - 3 - 2", "(- (- 10 3) 2)");
+    assert_tree("8 / 4 / 2", "(/ (/ 8 4) 2)");
+    assert_tree("1 + 2 + 3 + 4", "(+ (+ (+ 1 2) 3) 4)");
+  }
+
+  #[test]
+  fn test_binary_precedence() {
+    assert_tree("1 + 2 * 3 - 4", "(- (+ 1 (* 2 3)) 4)");
+    assert_tree("a * b / c - d", "(- (/ (* a b) c) d)");
+    assert_tree("(1 + 2) * 3", "(* (+ 1 2) 3)");
+    assert_tree("1 - (2 - 3)", "(- 1 (- 2 3))");
+  }
+
+  #[test]
+  fn test_comparison_and_logical_precedence() {
+    assert_tree("a + 1 < b * 2", "(< (+ a 1) (* b 2))");
+    assert_tree("a == b != c", "(!= (== a b) c)");
+    assert_tree("a < b == c >= d", "(== (< a b) (>= c d))");
+    assert_tree("a || b && c || d", "(|| (|| a (&& b c)) d)");
+    assert_tree("x <= 1 && y > 2", "(&& (<= x 1) (> y 2))");
+  }
+
+  #[test]
+  fn test_prefix_not() {
+    assert_tree("!a && b", "(&& (! a) b)");
+    assert_tree("!!true", "(! (! true))");
+    assert_tree("!(a || b)", "(! (|| a b))");
+  }
+
+  #[test]
+  fn test_if_else_chain() {
+    let source = r#"
+    func i32 sign(i32 x) {
+      if x < 0 {
+        return 0 - 1;
+      } else if x == 0 {
+        return 0;
+      } else {
+        return 1;
+      }
+    }
+    "#;
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let ast = ast.borrow();
+    let func = ast.get_funcs()[0].borrow();
+    let Statement::IfStatement(if_stmt) = &func.get_body().get_stmts()[0] else {
+      panic!("expected an if statement");
+    };
+    assert_eq!(sexpr(if_stmt.get_condition()), "(< x 0)");
+    assert_eq!(if_stmt.get_then_block().get_stmts().len(), 1);
+
+    let Some(ElseBranch::If(else_if)) = if_stmt.get_else_branch() else {
+      panic!("expected an else-if branch");
+    };
+    assert_eq!(sexpr(else_if.get_condition()), "(== x 0)");
+    assert!(matches!(
+      else_if.get_else_branch(),
+      Some(ElseBranch::Block(block)) if block.get_stmts().len() == 1
+    ));
+  }
+
+  #[test]
+  fn test_while_with_break_and_continue() {
+    let source = r#"
+    func i32 f(i32 n) {
+      while n > 0 {
+        if n == 5 { break; }
+        continue;
+      }
+      return n;
+    }
+    "#;
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let ast = ast.borrow();
+    let func = ast.get_funcs()[0].borrow();
+    let Statement::WhileStatement(while_stmt) = &func.get_body().get_stmts()[0] else {
+      panic!("expected a while statement");
+    };
+    assert_eq!(sexpr(while_stmt.get_condition()), "(> n 0)");
+    let body = while_stmt.get_body().get_stmts();
+    assert!(matches!(&body[0], Statement::IfStatement(if_stmt)
+      if matches!(if_stmt.get_then_block().get_stmts()[0], Statement::BreakStatement(_))));
+    assert!(matches!(body[1], Statement::ContinueStatement(_)));
+  }
+
+  #[test]
+  fn test_return_without_value() {
+    let source = "func void f() { return; }";
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let ast = ast.borrow();
+    let func = ast.get_funcs()[0].borrow();
+    assert!(matches!(
+      &func.get_body().get_stmts()[0],
+      Statement::ReturnStatement(ret) if ret.get_value().is_none()
+    ));
+  }
+
+  #[test]
+  fn test_unterminated_body_is_an_error() {
+    let source = "func i32 f() { if true { return 1; }";
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    assert!(parser.parse().is_err());
+  }
+
+  #[test]
+  fn test_binary_position_covers_operands() {
+    parse_return_expr("10 - 3 - 2", |e| {
+      let Expression::BinaryExpression(outer) = e else {
+        panic!("expected a binary expression");
+      };
+      let offset = "func i32 f() { return ".len();
+      assert_eq!(outer.get_pos().range, offset..offset + "10 - 3 - 2".len());
+      assert_eq!(
+        outer.get_left().get_pos().range,
+        offset..offset + "10 - 3".len()
+      );
+    });
+  }
+
   #[test]
   fn test_err_or_ast() {
-    let source = "abc";
+    let source = "func i32 main() { return 0; }";
     let mut lexer = Lexer::new(source);
     let mut parser = Parser::new(&mut lexer);
 
     let result = parser.parse();
     assert!(result.is_ok());
   }
+
+  #[test]
+  fn test_inline_annotations() {
+    let source = "inline func i32 a() { return 0; } noinline func void b() {} func void c() {}";
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let hints = ast
+      .borrow()
+      .get_funcs()
+      .iter()
+      .map(|func| func.borrow().get_inline())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      hints,
+      [InlineHint::Always, InlineHint::Never, InlineHint::Auto]
+    );
+
+    let mut lexer = Lexer::new("inline i32 a() { return 0; }");
+    let mut parser = Parser::new(&mut lexer);
+    assert!(parser.parse().is_err());
+  }
 }
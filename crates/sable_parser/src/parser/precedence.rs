@@ -0,0 +1,64 @@
+use crate::lexer::token::TokenType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+  Left,
+  Right,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InfixOperator {
+  pub token_type: TokenType,
+  pub precedence: u8,
+  pub assoc: Associativity,
+}
+
+impl InfixOperator {
+  const fn new(token_type: TokenType, precedence: u8, assoc: Associativity) -> Self {
+    Self {
+      token_type,
+      precedence,
+      assoc,
+    }
+  }
+
+  /// The lowest precedence an operator in the right operand may have
+  /// without ending the operand.
+  pub fn rhs_precedence(&self) -> u8 {
+    match self.assoc {
+      Associativity::Left => self.precedence + 1,
+      Associativity::Right => self.precedence,
+    }
+  }
+}
+
+/// Binary operators known to the expression parser. A higher precedence
+/// binds tighter; new operators only need an entry here.
+pub const INFIX_OPERATORS: &[InfixOperator] = &[
+  InfixOperator::new(TokenType::Or, 1, Associativity::Left),
+  InfixOperator::new(TokenType::And, 2, Associativity::Left),
+  InfixOperator::new(TokenType::Eq, 3, Associativity::Left),
+  InfixOperator::new(TokenType::NotEq, 3, Associativity::Left),
+  InfixOperator::new(TokenType::Lt, 4, Associativity::Left),
+  InfixOperator::new(TokenType::Le, 4, Associativity::Left),
+  InfixOperator::new(TokenType::Gt, 4, Associativity::Left),
+  InfixOperator::new(TokenType::Ge, 4, Associativity::Left),
+  InfixOperator::new(TokenType::Plus, 5, Associativity::Left),
+  InfixOperator::new(TokenType::Minus, 5, Associativity::Left),
+  InfixOperator::new(TokenType::Mul, 6, Associativity::Left),
+  InfixOperator::new(TokenType::Div, 6, Associativity::Left),
+];
+
+/// Unary operators written before their operand. They bind tighter than
+/// every binary operator.
+pub const PREFIX_OPERATORS: &[TokenType] = &[TokenType::Not];
+
+pub fn infix_operator(token_type: &TokenType) -> Option<&'static InfixOperator> {
+  INFIX_OPERATORS
+    .iter()
+    .find(|op| op.token_type == *token_type)
+}
+
+pub fn is_prefix_operator(token_type: &TokenType) -> bool {
+  PREFIX_OPERATORS.contains(token_type)
+}
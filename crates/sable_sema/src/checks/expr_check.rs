@@ -1,18 +1,23 @@
 use std::{cell::RefCell, rc::Rc};
 
-use sable_parser::ast::{
-  expression::{
-    AssignExpression, BinaryExpression, BlockExpression, CallExpression, Expression,
-    VariableExpression,
+use sable_parser::{
+  ast::{
+    expression::{
+      AssignExpression, BinaryExpression, BlockExpression, CallExpression, Expression,
+      UnaryExpression, VariableExpression,
+    },
+    function::Function,
   },
-  function::Function,
+  info::{OperatorType, ValType},
 };
 
 use crate::{
-  checks::inference::infer_expr,
+  checks::{inference::infer_expr, lints::lint_unreachable},
   error::{
     AnalyzerError,
-    expr_errs::{ExprCheckError, TypeMismatch, VariableNotFound},
+    expr_errs::{
+      ExprCheckError, IllegalNullUntyped, InvalidOperand, TypeMismatch, VariableNotFound,
+    },
     func_checks::{FunctionArgumentMismatch, FunctionCheckError, FunctionNotFound},
   },
   sema::Sema,
@@ -20,10 +25,22 @@ use crate::{
 
 use super::stmt_check::check_stmt;
 
+/// Checks `expr` and records its type in the analyzer's type table.
 pub fn check_expr<'s>(
   analyzer: &mut Sema<'s>,
   expr: &mut Expression<'s>,
   f: Rc<RefCell<Function<'s>>>,
+) -> Result<(), AnalyzerError<'s>> {
+  check_expr_kind(analyzer, expr, f)?;
+  let type_ = infer_expr(analyzer, expr);
+  analyzer.types.insert(&expr.get_pos(), type_);
+  Ok(())
+}
+
+fn check_expr_kind<'s>(
+  analyzer: &mut Sema<'s>,
+  expr: &mut Expression<'s>,
+  f: Rc<RefCell<Function<'s>>>,
 ) -> Result<(), AnalyzerError<'s>> {
   match expr {
     Expression::LiteralExpression(_) => Ok(()),
@@ -43,6 +60,18 @@ pub fn check_expr<'s>(
     Expression::CallExpression(call_expression) => {
       check_call_expression(analyzer, call_expression, f)
     }
+    Expression::UnaryExpression(unary_expression) => {
+      check_unary_expression(analyzer, unary_expression, f)
+    }
+  }
+}
+
+/// Whether `operator` can be applied to operands of type `operand`.
+pub fn accepts_operand(operator: &OperatorType, operand: &ValType) -> bool {
+  match operator {
+    OperatorType::Eq | OperatorType::NotEq => operand.is_numeric() || *operand == ValType::Bool,
+    OperatorType::And | OperatorType::Or | OperatorType::Not => *operand == ValType::Bool,
+    _ => operand.is_numeric(),
   }
 }
 
@@ -52,6 +81,9 @@ pub fn check_call_expression<'s>(
   f: Rc<RefCell<Function<'s>>>,
 ) -> Result<(), AnalyzerError<'s>> {
   let name = call_expression.get_callee();
+  if name != f.borrow().get_name() {
+    analyzer.called.insert(name);
+  }
   let func_idx = analyzer.funcs.get(name);
   if func_idx.is_none() {
     return Err(AnalyzerError::FuncError(
@@ -79,10 +111,7 @@ pub fn check_call_expression<'s>(
   }
 
   for (i, arg) in args.iter_mut().enumerate() {
-    let res = check_expr(analyzer, arg, f.clone());
-    if res.is_err() {
-      return res;
-    }
+    check_expr(analyzer, arg, f.clone())?;
     let arg_type = infer_expr(analyzer, arg);
     let param_type = params[i].get_val_type();
     if arg_type != param_type {
@@ -101,13 +130,16 @@ pub fn check_block_expression<'s>(
   f: Rc<RefCell<Function<'s>>>,
 ) -> Result<(), AnalyzerError<'s>> {
   analyzer.resolver.enter_scope();
-  for (_, stmt) in block_expression.get_stmts_mut().iter_mut().enumerate() {
-    match check_stmt(analyzer, stmt, f.clone()) {
-      Ok(_) => {}
-      Err(err) => return Err(err),
+  lint_unreachable(analyzer, block_expression);
+  let mut result = Ok(());
+  for stmt in block_expression.get_stmts_mut().iter_mut() {
+    result = check_stmt(analyzer, stmt, f.clone());
+    if result.is_err() {
+      break;
     }
   }
-  Ok(())
+  analyzer.exit_scope();
+  result
 }
 
 pub fn check_binary_expression<'s>(
@@ -115,29 +147,47 @@ pub fn check_binary_expression<'s>(
   binary_expression: &mut BinaryExpression<'s>,
   f: Rc<RefCell<Function<'s>>>,
 ) -> Result<(), AnalyzerError<'s>> {
-  let lhs_checked = check_expr(analyzer, binary_expression.get_left_mut(), f.clone());
-  let rhs_checked = check_expr(analyzer, binary_expression.get_right_mut(), f);
-
-  if lhs_checked.is_err() {
-    return lhs_checked;
-  }
-
-  if rhs_checked.is_err() {
-    return rhs_checked;
-  }
+  check_expr(analyzer, binary_expression.get_left_mut(), f.clone())?;
+  check_expr(analyzer, binary_expression.get_right_mut(), f)?;
 
   let lhs = binary_expression.get_left();
   let rhs = binary_expression.get_right();
   let lhs_type = infer_expr(analyzer, lhs);
   let rhs_type = infer_expr(analyzer, rhs);
 
-  if lhs_type == rhs_type {
-    return Ok(());
+  if lhs_type != rhs_type {
+    return Err(AnalyzerError::ExprError(ExprCheckError::TypeMismatch(
+      TypeMismatch::new(lhs_type, rhs_type, binary_expression.get_pos().clone()),
+    )));
   }
 
-  Err(AnalyzerError::ExprError(ExprCheckError::TypeMismatch(
-    TypeMismatch::new(lhs_type, rhs_type, binary_expression.get_pos().clone()),
-  )))
+  let operator = binary_expression.get_operator();
+  if !accepts_operand(operator, &lhs_type) {
+    return Err(AnalyzerError::ExprError(ExprCheckError::InvalidOperand(
+      InvalidOperand::new(operator.clone(), lhs_type, binary_expression.get_pos()),
+    )));
+  }
+
+  Ok(())
+}
+
+pub fn check_unary_expression<'s>(
+  analyzer: &mut Sema<'s>,
+  unary_expression: &mut UnaryExpression<'s>,
+  f: Rc<RefCell<Function<'s>>>,
+) -> Result<(), AnalyzerError<'s>> {
+  check_expr(analyzer, unary_expression.get_operand_mut(), f)?;
+
+  let operand = unary_expression.get_operand();
+  let operand_type = infer_expr(analyzer, operand);
+  let operator = unary_expression.get_operator();
+  if !accepts_operand(operator, &operand_type) {
+    return Err(AnalyzerError::ExprError(ExprCheckError::InvalidOperand(
+      InvalidOperand::new(operator.clone(), operand_type, operand.get_pos()),
+    )));
+  }
+
+  Ok(())
 }
 
 pub fn check_variable_expression<'s>(
@@ -146,6 +196,7 @@ pub fn check_variable_expression<'s>(
 ) -> Result<(), AnalyzerError<'s>> {
   let name = variable_expression.get_name();
   if analyzer.resolver.is_declared(name) {
+    analyzer.resolver.mark_used(name);
     Ok(())
   } else {
     Err(AnalyzerError::ExprError(ExprCheckError::VariableNotFound(
@@ -159,5 +210,290 @@ pub fn check_assign_expression<'s>(
   assign_expression: &mut AssignExpression<'s>,
   f: Rc<RefCell<Function<'s>>>,
 ) -> Result<(), AnalyzerError<'s>> {
-  return check_expr(analyzer, assign_expression.get_value_mut(), f);
+  check_expr(analyzer, assign_expression.get_value_mut(), f)?;
+  let Some(name) = assign_expression.get_asignee() else {
+    return Ok(());
+  };
+  let Some(target) = analyzer.resolver.resolve_var(name) else {
+    return Err(AnalyzerError::ExprError(ExprCheckError::VariableNotFound(
+      VariableNotFound::new(name, assign_expression.get_pos()),
+    )));
+  };
+
+  let target_type = target.get_type().clone();
+  let val_type = infer_expr(analyzer, assign_expression.get_value());
+  if val_type == ValType::Void || val_type == ValType::Untyped {
+    return Err(AnalyzerError::ExprError(ExprCheckError::IllegalNullVoid(
+      IllegalNullUntyped::new(assign_expression.get_pos()),
+    )));
+  }
+  if val_type != target_type {
+    return Err(AnalyzerError::ExprError(ExprCheckError::TypeMismatch(
+      TypeMismatch::new(
+        target_type,
+        val_type,
+        assign_expression.get_value().get_pos(),
+      ),
+    )));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser, position::Position};
+
+  use super::*;
+
+  fn analyze(source: &str, check: impl FnOnce(Result<(), &Vec<AnalyzerError>>)) {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast);
+    check(sema.analyze());
+  }
+
+  #[test]
+  fn test_checked_expressions_are_typed() {
+    let source = "func bool f(i32 a) { let f32 b = 1.5; return a + 1 > 2 && b < 2.0; }";
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast);
+    assert!(sema.analyze().is_ok());
+
+    let type_of = |snippet: &str| {
+      let start = source.find(snippet).unwrap();
+      let pos = Position::new(0, 0, start..start + snippet.len());
+      sema.types.get(&pos).cloned()
+    };
+    assert_eq!(type_of("1.5"), Some(ValType::F32));
+    assert_eq!(type_of("a + 1"), Some(ValType::I32));
+    assert_eq!(type_of("a + 1 > 2"), Some(ValType::Bool));
+    assert_eq!(type_of("a + 1 > 2 && b < 2.0"), Some(ValType::Bool));
+  }
+
+  #[test]
+  fn test_assignments_resolve_their_target() {
+    let source = "func f32 f(f32 a) { let f32 b = 1.0; b = a * 2.0; a = b; return a; }";
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast);
+    assert!(sema.analyze().is_ok());
+    // An assignment spans from its `=` to the end of the value.
+    let start = source.find("= a * 2.0").unwrap();
+    let pos = Position::new(0, 0, start..start + "= a * 2.0".len());
+    assert_eq!(sema.types.get(&pos), Some(&ValType::F32));
+
+    analyze("func i32 f() { y = 3; return 0; }", |res| {
+      let errs = res.unwrap_err();
+      assert!(matches!(
+        &errs[0],
+        AnalyzerError::ExprError(ExprCheckError::VariableNotFound(err)) if err.name() == "y"
+      ));
+    });
+    for source in [
+      "func i32 f() { let i32 x = 0; x = 1.5; return x; }",
+      "func bool f() { let bool b = true; b = 1; return b; }",
+      "func i32 f(i32 n) { n = n > 0; return n; }",
+    ] {
+      analyze(source, |res| {
+        let errs = res.expect_err(source);
+        assert!(matches!(
+          errs[0],
+          AnalyzerError::ExprError(ExprCheckError::TypeMismatch(_))
+        ));
+      });
+    }
+  }
+
+  #[test]
+  fn test_calls_to_later_functions() {
+    analyze(
+      "func bool even(i32 n) { if n == 0 { return true; } return odd(n - 1); }
+       func bool odd(i32 n) { if n == 0 { return false; } return even(n - 1); }",
+      |res| assert!(res.is_ok()),
+    );
+    analyze(
+      "func i32 f() { return g(1); } func i32 g(i32 a, i32 b) { return a; }",
+      |res| {
+        let errs = res.unwrap_err();
+        assert!(matches!(
+          errs[0],
+          AnalyzerError::FuncError(FunctionCheckError::FunctionArgumentMismatch(_))
+        ));
+      },
+    );
+    analyze(
+      "func i32 f() { return 1; } func i32 f() { return 2; }",
+      |res| {
+        let errs = res.unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(matches!(errs[0], AnalyzerError::FunctionAlreadyDefined(_)));
+      },
+    );
+  }
+
+  #[test]
+  fn test_every_path_must_return() {
+    let accepted = [
+      "func i32 f(bool c) { if c { return 1; } else { return 2; } }",
+      "func i32 f(i32 n) { if n < 0 { return 0; } else if n == 0 { return 1; } else { return 2; } }",
+      "func i32 f() { while true { } }",
+      "func i32 f(bool c) { while true { while c { break; } } }",
+      "func void f() { let i32 x = 1; }",
+      "func void f(bool c) { if c { return; } }",
+    ];
+    for source in accepted {
+      analyze(source, |res| assert!(res.is_ok(), "{source}"));
+    }
+
+    let rejected = [
+      "func i32 f() { let i32 x = 1; }",
+      "func i32 f(bool c) { if c { return 1; } }",
+      "func i32 f(i32 n) { if n < 0 { return 0; } else if n == 0 { return 1; } }",
+      "func i32 f(bool c) { while c { return 1; } }",
+      "func i32 f(bool c) { while true { if c { break; } } }",
+    ];
+    for source in rejected {
+      analyze(source, |res| {
+        let errs = res.expect_err(source);
+        assert!(matches!(errs[0], AnalyzerError::MissingReturn(_)));
+      });
+    }
+
+    analyze("func i32 f() { return; }", |res| {
+      let errs = res.unwrap_err();
+      assert!(matches!(
+        errs[0],
+        AnalyzerError::ExprError(ExprCheckError::TypeMismatch(_))
+      ));
+    });
+  }
+
+  #[test]
+  fn test_reads_need_definite_assignment() {
+    let accepted = [
+      "func i32 f(bool c) { let i32 x; if c { x = 1; } else { x = 2; } return x; }",
+      "func i32 f(bool c) { let i32 x; if c { x = 1; } else { return 0; } return x; }",
+      "func i32 f(bool c) { let i32 x; while true { if c { x = 1; break; } } return x; }",
+      "func i32 f(bool c) { let i32 x; let i32 y = x = 3; return x + y; }",
+      "func i32 f(bool c) { if c { let i32 x; } let i32 x = 1; return x; }",
+    ];
+    for source in accepted {
+      analyze(source, |res| assert!(res.is_ok(), "{source}"));
+    }
+
+    let rejected = [
+      "func i32 f() { let i32 x; return x; }",
+      "func i32 f(bool c) { let i32 x; if c { x = 1; } return x; }",
+      "func i32 f(bool c) { let i32 x; while c { x = 1; } return x; }",
+      "func i32 f(bool c) { let i32 x; while c { if c { break; } x = 1; } return x; }",
+      "func i32 f(bool c) { let i32 x; if c || (x = 1) == 1 { return x; } return 0; }",
+      "func i32 f(bool c) { let i32 x; if c && (x = 1) == 1 { return x; } return 0; }",
+      "func i32 f(i32 n) { let i32 x; while n > 0 { n = x; x = 1; } return 0; }",
+    ];
+    for source in rejected {
+      analyze(source, |res| {
+        let errs = res.expect_err(source);
+        assert_eq!(errs.len(), 1, "{source}");
+        assert!(
+          matches!(&errs[0], AnalyzerError::UninitializedVariable(err) if err.name() == "x"),
+          "{source}"
+        );
+      });
+    }
+  }
+
+  #[test]
+  fn test_comparisons_and_logic_are_bool() {
+    analyze(
+      "func bool f(i32 a, i32 b) { return a < b && !(a == b) || true; }",
+      |res| assert!(res.is_ok()),
+    );
+    analyze("func bool f(f32 a) { return a >= 1.5; }", |res| {
+      assert!(res.is_ok())
+    });
+  }
+
+  #[test]
+  fn test_invalid_operands() {
+    let invalid = [
+      "func bool f(bool a) { return a < a; }",
+      "func bool f(i32 a) { return !a; }",
+      "func bool f(i32 a) { return a && a; }",
+      "func bool f(bool a) { return a + a; }",
+    ];
+    for source in invalid {
+      analyze(source, |res| {
+        let errs = res.expect_err(source);
+        assert!(matches!(
+          errs[0],
+          AnalyzerError::ExprError(ExprCheckError::InvalidOperand(_))
+        ));
+      });
+    }
+  }
+
+  #[test]
+  fn test_if_condition_must_be_bool() {
+    analyze(
+      "func i32 f(i32 a) { if a > 0 { return 1; } else if a < 0 { return 2; } return 3; }",
+      |res| assert!(res.is_ok()),
+    );
+    analyze(
+      "func i32 f(i32 a) { if a { return 1; } return 0; }",
+      |res| {
+        let errs = res.unwrap_err();
+        assert!(matches!(
+          errs[0],
+          AnalyzerError::ExprError(ExprCheckError::TypeMismatch(_))
+        ));
+      },
+    );
+  }
+
+  #[test]
+  fn test_if_blocks_are_scoped() {
+    analyze(
+      "func i32 f(bool c) { if c { let i32 x = 1; } else { let i32 x = 2; } return x; }",
+      |res| {
+        let errs = res.unwrap_err();
+        assert!(matches!(
+          errs[0],
+          AnalyzerError::ExprError(ExprCheckError::VariableNotFound(_))
+        ));
+      },
+    );
+  }
+
+  #[test]
+  fn test_loop_control_outside_loop() {
+    analyze(
+      "func i32 f(i32 n) { while n > 0 { if n == 2 { break; } continue; } return n; }",
+      |res| assert!(res.is_ok()),
+    );
+    for source in [
+      "func i32 f() { break; return 0; }",
+      "func i32 f(bool c) { if c { continue; } return 0; }",
+      "func i32 f(bool c) { while c { } break; return 0; }",
+    ] {
+      analyze(source, |res| {
+        let errs = res.expect_err(source);
+        assert!(matches!(errs[0], AnalyzerError::OutsideLoop(_)));
+      });
+    }
+  }
+
+  #[test]
+  fn test_comparison_operands_must_match() {
+    analyze("func bool f(i32 a, f32 b) { return a == b; }", |res| {
+      let errs = res.err().unwrap();
+      assert!(matches!(
+        errs[0],
+        AnalyzerError::ExprError(ExprCheckError::TypeMismatch(_))
+      ));
+    });
+  }
 }
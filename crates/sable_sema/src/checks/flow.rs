@@ -0,0 +1,58 @@
+use sable_parser::ast::{
+  expression::{BlockExpression, Expression},
+  statement::{ElseBranch, IfStatement, Statement},
+};
+
+/// Whether control can reach the end of `block`.
+pub fn block_falls_through(block: &BlockExpression) -> bool {
+  block.get_stmts().iter().all(stmt_falls_through)
+}
+
+/// Whether control can continue with the statement following `stmt`.
+pub fn stmt_falls_through(stmt: &Statement) -> bool {
+  match stmt {
+    Statement::ReturnStatement(_)
+    | Statement::BreakStatement(_)
+    | Statement::ContinueStatement(_) => false,
+    Statement::IfStatement(if_statement) => if_falls_through(if_statement),
+    // Only a `while true` without a `break` never exits.
+    Statement::WhileStatement(while_statement) => {
+      !is_true_literal(while_statement.get_condition()) || breaks_out(while_statement.get_body())
+    }
+    Statement::Expression(Expression::BlockExpression(block)) => block_falls_through(block),
+    Statement::Expression(_) | Statement::LetStatement(_) => true,
+  }
+}
+
+fn if_falls_through(if_statement: &IfStatement) -> bool {
+  block_falls_through(if_statement.get_then_block())
+    || match if_statement.get_else_branch() {
+      Some(ElseBranch::Block(block)) => block_falls_through(block),
+      Some(ElseBranch::If(else_if)) => if_falls_through(else_if),
+      None => true,
+    }
+}
+
+fn is_true_literal(expr: &Expression) -> bool {
+  matches!(expr, Expression::LiteralExpression(literal) if literal.get_value() == "true")
+}
+
+/// Whether `block` contains a `break` leaving the loop it belongs to. Breaks
+/// inside nested loops only leave those.
+fn breaks_out(block: &BlockExpression) -> bool {
+  block.get_stmts().iter().any(|stmt| match stmt {
+    Statement::BreakStatement(_) => true,
+    Statement::IfStatement(if_statement) => if_breaks_out(if_statement),
+    Statement::Expression(Expression::BlockExpression(block)) => breaks_out(block),
+    _ => false,
+  })
+}
+
+fn if_breaks_out(if_statement: &IfStatement) -> bool {
+  breaks_out(if_statement.get_then_block())
+    || match if_statement.get_else_branch() {
+      Some(ElseBranch::Block(block)) => breaks_out(block),
+      Some(ElseBranch::If(else_if)) => if_breaks_out(else_if),
+      None => false,
+    }
+}
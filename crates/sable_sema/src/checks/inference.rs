@@ -1,13 +1,20 @@
 use sable_parser::{
   ast::expression::{
-    AssignExpression, BinaryExpression, CallExpression, Expression, VariableExpression,
+    AssignExpression, BinaryExpression, CallExpression, Expression, UnaryExpression,
+    VariableExpression,
   },
   info::ValType,
 };
 
 use crate::sema::Sema;
 
+/// The type of `expr`, taken from the type table once `expr` has been
+/// checked.
 pub fn infer_expr<'s>(analyzer: &mut Sema<'s>, expr: &Expression) -> ValType {
+  if let Some(type_) = analyzer.types.get_expr(expr) {
+    return type_.clone();
+  }
+
   match expr {
     Expression::LiteralExpression(literal_expression) => literal_expression.get_type(),
     Expression::BlockExpression(_) => ValType::Untyped,
@@ -22,6 +29,9 @@ pub fn infer_expr<'s>(analyzer: &mut Sema<'s>, expr: &Expression) -> ValType {
     }
     Expression::CallExpression(call_expression) => infer_call_expression(analyzer, call_expression),
     Expression::NullExpression(_) => ValType::Void,
+    Expression::UnaryExpression(unary_expression) => {
+      infer_unary_expression(analyzer, unary_expression)
+    }
   }
 }
 
@@ -42,9 +52,16 @@ pub fn infer_assign_expression<'s>(
   analyzer: &mut Sema<'s>,
   assign_expression: &AssignExpression,
 ) -> ValType {
+  // An assignment to a variable has the variable's type, which checking
+  // made sure the value has.
+  if let Some(target) = assign_expression
+    .get_asignee()
+    .and_then(|name| analyzer.resolver.resolve_var(name))
+  {
+    return target.get_type().clone();
+  }
   let val = assign_expression.get_value();
-  let val_type = infer_expr(analyzer, val);
-  val_type
+  infer_expr(analyzer, val)
 }
 
 pub fn infer_variable_expression<'s>(
@@ -66,9 +83,24 @@ pub fn infer_binary_expression<'s>(
   let lhs = infer_expr(analyzer, binary_expression.get_left());
   let rhs = infer_expr(analyzer, binary_expression.get_right());
 
-  if lhs == rhs {
-    return lhs;
+  if lhs != rhs {
+    return ValType::Untyped;
+  }
+
+  let operator = binary_expression.get_operator();
+  if operator.is_comparison() || operator.is_logical() {
+    return ValType::Bool;
   }
+  lhs
+}
 
+pub fn infer_unary_expression<'s>(
+  analyzer: &mut Sema<'s>,
+  unary_expression: &UnaryExpression,
+) -> ValType {
+  let operand = infer_expr(analyzer, unary_expression.get_operand());
+  if operand == ValType::Bool {
+    return ValType::Bool;
+  }
   ValType::Untyped
 }
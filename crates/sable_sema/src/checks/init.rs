@@ -0,0 +1,186 @@
+//! Definite assignment.
+//!
+//! A variable declared without an initializer may only be read where every
+//! path from its `let` assigns it first. A loop body may run zero times, so
+//! its assignments only count after the loop when the loop is left through
+//! a `break` that follows them, and the right operand of `&&` and `||` may
+//! not run, so its assignments never count after the operator.
+
+use std::collections::HashSet;
+
+use sable_parser::{
+  ast::{
+    expression::{BlockExpression, Expression},
+    statement::{ElseBranch, IfStatement, Statement, WhileStatement},
+  },
+  info::OperatorType,
+};
+
+use crate::error::UninitializedVariable;
+
+/// The variables not assigned on every path to a point of the body, `None`
+/// where that point is unreachable.
+type Unassigned<'s> = Option<HashSet<&'s str>>;
+
+/// The state where the paths reaching `a` and `b` meet.
+fn join<'s>(a: Unassigned<'s>, b: Unassigned<'s>) -> Unassigned<'s> {
+  match (a, b) {
+    (Some(mut a), Some(b)) => {
+      a.extend(b);
+      Some(a)
+    }
+    (a, None) => a,
+    (None, b) => b,
+  }
+}
+
+struct DefiniteAssignment<'s> {
+  unassigned: Unassigned<'s>,
+  /// For each enclosing loop, the join of the states at its `break`s.
+  breaks: Vec<Unassigned<'s>>,
+  errors: Vec<UninitializedVariable<'s>>,
+}
+
+/// Reports the reads in `body` of variables that may not be assigned yet.
+pub fn check_definite_assignment<'s>(body: &BlockExpression<'s>) -> Vec<UninitializedVariable<'s>> {
+  let mut analysis = DefiniteAssignment {
+    unassigned: Some(HashSet::new()),
+    breaks: Vec::new(),
+    errors: Vec::new(),
+  };
+  analysis.block(body);
+  analysis.errors
+}
+
+impl<'s> DefiniteAssignment<'s> {
+  fn block(&mut self, block: &BlockExpression<'s>) {
+    let mut declared = Vec::new();
+    for stmt in block.get_stmts() {
+      if let Statement::LetStatement(let_statement) = stmt {
+        declared.push(let_statement.get_name());
+      }
+      self.stmt(stmt);
+    }
+    // The variables of the block may be declared again after it.
+    if let Some(unassigned) = &mut self.unassigned {
+      for name in declared {
+        unassigned.remove(name);
+      }
+    }
+  }
+
+  fn stmt(&mut self, stmt: &Statement<'s>) {
+    match stmt {
+      Statement::Expression(expr) => self.expr(expr),
+      Statement::LetStatement(let_statement) => {
+        if let Some(assignee) = let_statement.get_assignee() {
+          self.expr(assignee.get_value());
+        }
+        // A variable of an earlier block may have had the same name.
+        if let Some(unassigned) = &mut self.unassigned {
+          unassigned.remove(let_statement.get_name());
+          if let_statement.get_assignee().is_none() {
+            unassigned.insert(let_statement.get_name());
+          }
+        }
+      }
+      Statement::ReturnStatement(return_statement) => {
+        if let Some(value) = return_statement.get_value() {
+          self.expr(value);
+        }
+        self.unassigned = None;
+      }
+      Statement::IfStatement(if_statement) => self.if_stmt(if_statement),
+      Statement::WhileStatement(while_statement) => self.while_stmt(while_statement),
+      Statement::BreakStatement(_) => {
+        let state = self.unassigned.take();
+        if let Some(breaks) = self.breaks.last_mut() {
+          *breaks = join(breaks.take(), state);
+        }
+      }
+      // The loop starts over with no more variables assigned than before it.
+      Statement::ContinueStatement(_) => self.unassigned = None,
+    }
+  }
+
+  fn if_stmt(&mut self, if_statement: &IfStatement<'s>) {
+    self.expr(if_statement.get_condition());
+    let before = self.unassigned.clone();
+    self.block(if_statement.get_then_block());
+    let then_state = std::mem::replace(&mut self.unassigned, before);
+    match if_statement.get_else_branch() {
+      Some(ElseBranch::Block(block)) => self.block(block),
+      Some(ElseBranch::If(else_if)) => self.if_stmt(else_if),
+      None => {}
+    }
+    self.unassigned = join(then_state, self.unassigned.take());
+  }
+
+  fn while_stmt(&mut self, while_statement: &WhileStatement<'s>) {
+    let condition = while_statement.get_condition();
+    self.expr(condition);
+    let after_condition = self.unassigned.clone();
+
+    self.breaks.push(None);
+    self.block(while_statement.get_body());
+    let breaks = self.breaks.pop().unwrap();
+
+    // Only a `while true` is never left through its condition.
+    let is_true = matches!(
+      condition,
+      Expression::LiteralExpression(literal) if literal.get_value() == "true"
+    );
+    self.unassigned = if is_true {
+      breaks
+    } else {
+      join(after_condition, breaks)
+    };
+  }
+
+  fn expr(&mut self, expr: &Expression<'s>) {
+    match expr {
+      Expression::VariableExpression(variable_expression) => {
+        let name = variable_expression.get_name();
+        // Each variable is reported at its first such read only.
+        if self
+          .unassigned
+          .as_ref()
+          .is_some_and(|unassigned| unassigned.contains(name))
+          && self.errors.iter().all(|err| err.name() != name)
+        {
+          self.errors.push(UninitializedVariable::new(
+            name,
+            variable_expression.get_pos(),
+          ));
+        }
+      }
+      Expression::AssignExpression(assign_expression) => {
+        self.expr(assign_expression.get_value());
+        if let (Some(name), Some(unassigned)) =
+          (assign_expression.get_asignee(), &mut self.unassigned)
+        {
+          unassigned.remove(name);
+        }
+      }
+      Expression::BinaryExpression(binary_expression) => {
+        self.expr(binary_expression.get_left());
+        match binary_expression.get_operator() {
+          OperatorType::And | OperatorType::Or => {
+            let before = self.unassigned.clone();
+            self.expr(binary_expression.get_right());
+            self.unassigned = before;
+          }
+          _ => self.expr(binary_expression.get_right()),
+        }
+      }
+      Expression::UnaryExpression(unary_expression) => self.expr(unary_expression.get_operand()),
+      Expression::CallExpression(call_expression) => {
+        for arg in call_expression.get_args() {
+          self.expr(arg);
+        }
+      }
+      Expression::BlockExpression(block) => self.block(block),
+      Expression::LiteralExpression(_) | Expression::NullExpression(_) => {}
+    }
+  }
+}
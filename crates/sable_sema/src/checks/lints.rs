@@ -0,0 +1,91 @@
+use sable_parser::ast::{ast::AST, expression::BlockExpression};
+
+use crate::{
+  checks::flow::stmt_falls_through,
+  diagnostic::{Diagnostic, Severity},
+  lint::Lint,
+  scope::Scope,
+  sema::Sema,
+};
+
+/// Names starting with an underscore are intentionally unused.
+fn is_silenced(name: &str) -> bool {
+  name.starts_with('_')
+}
+
+/// Reports the variables and parameters of a scope that were never read.
+pub fn lint_unused_variables<'s>(analyzer: &mut Sema<'s>, scope: &Scope<'s>) {
+  let mut unused = scope
+    .variables()
+    .filter(|(name, value)| !value.is_used() && !is_silenced(name))
+    .collect::<Vec<_>>();
+  unused.sort_by_key(|(_, value)| value.get_pos().range.start);
+
+  for (name, value) in unused {
+    let (lint, what) = if value.is_param() {
+      (Lint::UnusedParameter, "parameter")
+    } else {
+      (Lint::UnusedVariable, "variable")
+    };
+    let diagnostic = Diagnostic::new(
+      Severity::Warning,
+      format!("unused {} `{}`", what, name),
+      value.get_pos().clone(),
+    )
+    .with_label(value.get_pos().clone(), "never read")
+    .with_note(format!(
+      "prefix it with an underscore to silence this: `_{}`",
+      name
+    ));
+    analyzer.lint(lint, diagnostic);
+  }
+}
+
+/// Reports the first statement of `block` that follows one control never
+/// continues from.
+pub fn lint_unreachable(analyzer: &mut Sema<'_>, block: &BlockExpression) {
+  let stmts = block.get_stmts();
+  let Some(idx) = stmts.iter().position(|stmt| !stmt_falls_through(stmt)) else {
+    return;
+  };
+  let Some(unreachable) = stmts.get(idx + 1) else {
+    return;
+  };
+
+  let diagnostic = Diagnostic::new(
+    Severity::Warning,
+    "unreachable statement",
+    unreachable.get_pos(),
+  )
+  .with_label(
+    stmts[idx].get_pos(),
+    "any code following this is unreachable",
+  )
+  .with_label(unreachable.get_pos(), "never executed");
+  analyzer.lint(Lint::UnreachableCode, diagnostic);
+}
+
+/// Reports functions no other function calls. `main` is the entry point
+/// and always used.
+pub fn lint_unused_functions(analyzer: &mut Sema<'_>, ast: &AST<'_>) {
+  for (idx, func) in ast.get_funcs().iter().enumerate() {
+    let func = func.borrow();
+    let name = func.get_name();
+    // Redefinitions are errors already.
+    if analyzer.funcs.get(name) != Some(&idx)
+      || name == "main"
+      || is_silenced(name)
+      || analyzer.called.contains(name)
+    {
+      continue;
+    }
+
+    let diagnostic = Diagnostic::new(
+      Severity::Warning,
+      format!("function `{}` is never called", name),
+      func.get_pos(),
+    )
+    .with_label(func.get_pos(), "defined here");
+    analyzer.lint(Lint::UnusedFunction, diagnostic);
+  }
+}
@@ -1,3 +1,6 @@
 pub mod expr_check;
+pub mod flow;
 pub mod inference;
+pub mod init;
+pub mod lints;
 pub mod stmt_check;
@@ -2,22 +2,29 @@ use std::{cell::RefCell, rc::Rc};
 
 use sable_parser::{
   ast::{
+    expression::Expression,
     function::Function,
-    statement::{LetStatement, ReturnStatement, Statement},
+    statement::{
+      ElseBranch, IfStatement, LetStatement, ReturnStatement, Statement, WhileStatement,
+    },
   },
   info::ValType,
+  position::Position,
 };
 
 use crate::{
   error::{
-    AnalyzerError, ExprCheckError, VariableRedeclared,
+    AnalyzerError, ExprCheckError, OutsideLoop, VariableRedeclared,
     expr_errs::{IllegalNullUntyped, TypeMismatch},
   },
   scope::NamendValue,
   sema::Sema,
 };
 
-use super::{expr_check::check_expr, inference::infer_expr};
+use super::{
+  expr_check::{check_block_expression, check_expr},
+  inference::infer_expr,
+};
 
 pub fn check_stmt<'s>(
   analyzer: &mut Sema<'s>,
@@ -28,6 +35,68 @@ pub fn check_stmt<'s>(
     Statement::Expression(expression) => check_expr(analyzer, expression, f),
     Statement::ReturnStatement(ret_statement) => check_ret_stmt(analyzer, ret_statement, f),
     Statement::LetStatement(let_statement) => check_let_stmt(analyzer, let_statement, f),
+    Statement::IfStatement(if_statement) => check_if_stmt(analyzer, if_statement, f),
+    Statement::WhileStatement(while_statement) => check_while_stmt(analyzer, while_statement, f),
+    Statement::BreakStatement(break_statement) => {
+      check_loop_control(analyzer, "break", break_statement.get_pos())
+    }
+    Statement::ContinueStatement(continue_statement) => {
+      check_loop_control(analyzer, "continue", continue_statement.get_pos())
+    }
+  }
+}
+
+fn check_condition<'s>(
+  analyzer: &mut Sema<'s>,
+  condition: &mut Expression<'s>,
+  f: Rc<RefCell<Function<'s>>>,
+) -> Result<(), AnalyzerError<'s>> {
+  check_expr(analyzer, condition, f)?;
+  let cond_type = infer_expr(analyzer, condition);
+  if cond_type != ValType::Bool {
+    return Err(AnalyzerError::ExprError(ExprCheckError::TypeMismatch(
+      TypeMismatch::new(ValType::Bool, cond_type, condition.get_pos()),
+    )));
+  }
+  Ok(())
+}
+
+pub fn check_while_stmt<'s>(
+  analyzer: &mut Sema<'s>,
+  while_statement: &mut WhileStatement<'s>,
+  f: Rc<RefCell<Function<'s>>>,
+) -> Result<(), AnalyzerError<'s>> {
+  check_condition(analyzer, while_statement.get_condition_mut(), f.clone())?;
+
+  analyzer.loop_depth += 1;
+  let result = check_block_expression(analyzer, while_statement.get_body_mut(), f);
+  analyzer.loop_depth -= 1;
+  result
+}
+
+pub fn check_loop_control<'s>(
+  analyzer: &mut Sema<'s>,
+  keyword: &'static str,
+  pos: Position,
+) -> Result<(), AnalyzerError<'s>> {
+  if analyzer.loop_depth == 0 {
+    return Err(AnalyzerError::OutsideLoop(OutsideLoop::new(keyword, pos)));
+  }
+  Ok(())
+}
+
+pub fn check_if_stmt<'s>(
+  analyzer: &mut Sema<'s>,
+  if_statement: &mut IfStatement<'s>,
+  f: Rc<RefCell<Function<'s>>>,
+) -> Result<(), AnalyzerError<'s>> {
+  check_condition(analyzer, if_statement.get_condition_mut(), f.clone())?;
+
+  check_block_expression(analyzer, if_statement.get_then_block_mut(), f.clone())?;
+  match if_statement.get_else_branch_mut() {
+    Some(ElseBranch::Block(block)) => check_block_expression(analyzer, block, f),
+    Some(ElseBranch::If(else_if)) => check_if_stmt(analyzer, else_if, f),
+    None => Ok(()),
   }
 }
 
@@ -36,8 +105,17 @@ pub fn check_ret_stmt<'s>(
   ret_statement: &mut ReturnStatement<'s>,
   f: Rc<RefCell<Function<'s>>>,
 ) -> Result<(), AnalyzerError<'s>> {
-  let mut value = ret_statement.get_value_mut();
-  check_expr(analyzer, &mut value, f.clone())?;
+  let ret_type = f.borrow().get_ret_type();
+  let value = match ret_statement.get_value_mut() {
+    Some(value) => value,
+    None if ret_type == ValType::Void => return Ok(()),
+    None => {
+      return Err(AnalyzerError::ExprError(ExprCheckError::TypeMismatch(
+        TypeMismatch::new(ret_type, ValType::Void, ret_statement.get_pos()),
+      )));
+    }
+  };
+  check_expr(analyzer, value, f.clone())?;
   let val_type = infer_expr(analyzer, value);
   if val_type == ValType::Void || val_type == ValType::Untyped {
     return Err(AnalyzerError::ExprError(ExprCheckError::IllegalNullVoid(
@@ -46,7 +124,11 @@ pub fn check_ret_stmt<'s>(
   }
   if val_type != f.borrow().get_ret_type() {
     return Err(AnalyzerError::ExprError(ExprCheckError::TypeMismatch(
-      TypeMismatch::new(f.borrow().get_ret_type().clone(), val_type, value.get_pos().clone()),
+      TypeMismatch::new(
+        f.borrow().get_ret_type().clone(),
+        val_type,
+        value.get_pos().clone(),
+      ),
     )));
   }
 
@@ -86,11 +168,7 @@ pub fn check_let_stmt<'s>(
 
     if val_type != statement_type {
       return Err(AnalyzerError::ExprError(ExprCheckError::TypeMismatch(
-        TypeMismatch::new(
-          statement_type.clone(),
-          val_type,
-          assignee.get_pos().clone(),
-        ),
+        TypeMismatch::new(statement_type.clone(), val_type, assignee.get_pos().clone()),
       )));
     }
   }
@@ -0,0 +1,111 @@
+use std::fmt::Display;
+
+use ariadne::{Color, Label, Report, ReportKind};
+use sable_parser::{parser::error::ParseErrReport, position::Position};
+
+use crate::lint::Lint;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+  Error,
+  Warning,
+  Note,
+  Help,
+}
+
+impl Display for Severity {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Severity::Error => write!(f, "error"),
+      Severity::Warning => write!(f, "warning"),
+      Severity::Note => write!(f, "note"),
+      Severity::Help => write!(f, "help"),
+    }
+  }
+}
+
+/// A diagnostic that does not necessarily stop compilation, such as the
+/// findings of a lint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  severity: Severity,
+  message: String,
+  pos: Position,
+  labels: Vec<(Position, String)>,
+  notes: Vec<String>,
+  lint: Option<Lint>,
+}
+
+impl Diagnostic {
+  pub fn new(severity: Severity, message: impl Into<String>, pos: Position) -> Self {
+    Self {
+      severity,
+      message: message.into(),
+      pos,
+      labels: Vec::new(),
+      notes: Vec::new(),
+      lint: None,
+    }
+  }
+
+  pub fn with_label(mut self, pos: Position, message: impl Into<String>) -> Self {
+    self.labels.push((pos, message.into()));
+    self
+  }
+
+  pub fn with_note(mut self, note: impl Into<String>) -> Self {
+    self.notes.push(note.into());
+    self
+  }
+
+  pub fn severity(&self) -> Severity {
+    self.severity
+  }
+
+  pub fn set_severity(&mut self, severity: Severity) {
+    self.severity = severity;
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  pub fn pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn lint(&self) -> Option<Lint> {
+    self.lint
+  }
+
+  pub fn set_lint(&mut self, lint: Lint) {
+    self.lint = Some(lint);
+  }
+
+  pub fn report<'f>(&self, filename: &'f str) -> ParseErrReport<'f> {
+    let (kind, color) = match self.severity {
+      Severity::Error => (ReportKind::Error, Color::Red),
+      Severity::Warning => (ReportKind::Warning, Color::Yellow),
+      Severity::Note => (ReportKind::Custom("Note", Color::Cyan), Color::Cyan),
+      Severity::Help => (ReportKind::Advice, Color::Fixed(147)),
+    };
+
+    let mut report =
+      Report::build(kind, (filename, self.pos.range.clone())).with_message(&self.message);
+    for (order, (pos, message)) in self.labels.iter().enumerate() {
+      report = report.with_label(
+        Label::new((filename, pos.range.clone()))
+          .with_message(message)
+          .with_color(color)
+          .with_order(order as i32),
+      );
+    }
+    for note in &self.notes {
+      report = report.with_note(note);
+    }
+    if let Some(lint) = self.lint {
+      report = report.with_note(format!("reported by the `{}` lint", lint));
+    }
+    report.finish()
+  }
+}
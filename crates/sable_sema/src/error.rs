@@ -1,12 +1,20 @@
 use sable_parser::parser::error::ParseErrReport;
 
+use crate::diagnostic::Diagnostic;
+
 pub mod func_checks;
 pub mod expr_errs;
 pub mod func_already_defined;
+pub mod missing_return;
+pub mod outside_loop;
+pub mod uninitialized;
 pub mod var_redeclared;
 
 pub use expr_errs::ExprCheckError;
 pub use func_already_defined::FunctionAlreadyDefined;
+pub use missing_return::MissingReturn;
+pub use outside_loop::OutsideLoop;
+pub use uninitialized::UninitializedVariable;
 pub use var_redeclared::VariableRedeclared;
 
 pub enum AnalyzerError<'s> {
@@ -14,6 +22,11 @@ pub enum AnalyzerError<'s> {
   VariableRedeclared(var_redeclared::VariableRedeclared<'s>),
   ExprError(expr_errs::ExprCheckError<'s>),
   FuncError(func_checks::FunctionCheckError<'s>),
+  OutsideLoop(outside_loop::OutsideLoop),
+  MissingReturn(missing_return::MissingReturn<'s>),
+  UninitializedVariable(uninitialized::UninitializedVariable<'s>),
+  /// A lint whose level is set to deny.
+  Lint(Diagnostic),
 }
 
 impl<'s> AnalyzerError<'s> {
@@ -23,6 +36,10 @@ impl<'s> AnalyzerError<'s> {
       AnalyzerError::VariableRedeclared(err) => err.report(filename),
       AnalyzerError::ExprError(err) => err.report(filename),
       AnalyzerError::FuncError(err) => err.report(filename),
+      AnalyzerError::OutsideLoop(err) => err.report(filename),
+      AnalyzerError::MissingReturn(err) => err.report(filename),
+      AnalyzerError::UninitializedVariable(err) => err.report(filename),
+      AnalyzerError::Lint(diag) => diag.report(filename),
     }
   }
 }
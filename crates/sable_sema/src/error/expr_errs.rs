@@ -1,5 +1,9 @@
 use ariadne::{Color, Label, Report, ReportKind};
-use sable_parser::{info::ValType, parser::error::ParseErrReport, position::Position};
+use sable_parser::{
+  info::{OperatorType, ValType},
+  parser::error::ParseErrReport,
+  position::Position,
+};
 
 pub struct VariableNotFound<'s> {
   name: &'s str,
@@ -78,10 +82,41 @@ impl IllegalNullUntyped {
   }
 }
 
+pub struct InvalidOperand {
+  operator: OperatorType,
+  found: ValType,
+  pos: Position,
+}
+
+impl InvalidOperand {
+  pub fn new(operator: OperatorType, found: ValType, pos: Position) -> Self {
+    Self {
+      operator,
+      found,
+      pos,
+    }
+  }
+
+  pub fn report<'f>(&self, filename: &'f str) -> ParseErrReport<'f> {
+    Report::build(ReportKind::Error, (filename, self.pos.range.clone()))
+      .with_message(format!(
+        "operator `{}` cannot be applied to `{}`",
+        self.operator, self.found
+      ))
+      .with_label(
+        Label::new((filename, self.pos.range.clone()))
+          .with_message(format!("this is of type `{}`", self.found))
+          .with_color(Color::Yellow),
+      )
+      .finish()
+  }
+}
+
 pub enum ExprCheckError<'s> {
   VariableNotFound(VariableNotFound<'s>),
   TypeMismatch(TypeMismatch),
   IllegalNullVoid(IllegalNullUntyped),
+  InvalidOperand(InvalidOperand),
 }
 
 impl<'s> ExprCheckError<'s> {
@@ -90,6 +125,7 @@ impl<'s> ExprCheckError<'s> {
       ExprCheckError::VariableNotFound(err) => err.report(filename),
       ExprCheckError::TypeMismatch(err) => err.report(filename),
       ExprCheckError::IllegalNullVoid(err) => err.report(filename),
+      ExprCheckError::InvalidOperand(err) => err.report(filename),
     }
   }
 }
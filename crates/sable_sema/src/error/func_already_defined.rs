@@ -58,7 +58,7 @@ mod tests {
     let mut parse = Parser::new(&mut lex);
     let ast = parse.parse().unwrap();
     let ast_borrow = ast.borrow();
-    let f = ast_borrow.get_funcs().get(0).unwrap();
+    let f = ast_borrow.get_funcs().first().unwrap();
 
     let err = FunctionAlreadyDefined::new(f.borrow().get_name(), f.borrow().get_pos().clone(), f.borrow().get_pos().clone());
     err
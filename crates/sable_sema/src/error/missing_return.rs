@@ -0,0 +1,43 @@
+use ariadne::{Color, Label, Report, ReportKind};
+use sable_parser::{info::ValType, parser::error::ParseErrReport, position::Position};
+
+pub struct MissingReturn<'s> {
+  name: &'s str,
+  ret_type: ValType,
+  pos: Position,
+}
+
+impl<'s> MissingReturn<'s> {
+  pub fn new(name: &'s str, ret_type: ValType, pos: Position) -> Self {
+    Self {
+      name,
+      ret_type,
+      pos,
+    }
+  }
+
+  pub fn name(&self) -> &'s str {
+    self.name
+  }
+
+  pub fn pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn report(&self, filename: &'s str) -> ParseErrReport<'s> {
+    Report::build(ReportKind::Error, (filename, self.pos.range.clone()))
+      .with_message(format!(
+        "function `{}` can reach its end without returning",
+        self.name
+      ))
+      .with_label(
+        Label::new((filename, self.pos.range.clone()))
+          .with_message(format!(
+            "expected to return `{}` on every path",
+            self.ret_type
+          ))
+          .with_color(Color::Yellow),
+      )
+      .finish()
+  }
+}
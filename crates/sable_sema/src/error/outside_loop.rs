@@ -0,0 +1,32 @@
+use ariadne::{Color, Label, Report, ReportKind};
+use sable_parser::{parser::error::ParseErrReport, position::Position};
+
+pub struct OutsideLoop {
+  keyword: &'static str,
+  pos: Position,
+}
+
+impl OutsideLoop {
+  pub fn new(keyword: &'static str, pos: Position) -> Self {
+    Self { keyword, pos }
+  }
+
+  pub fn keyword(&self) -> &'static str {
+    self.keyword
+  }
+
+  pub fn pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn report<'f>(&self, filename: &'f str) -> ParseErrReport<'f> {
+    Report::build(ReportKind::Error, (filename, self.pos.range.clone()))
+      .with_message(format!("`{}` outside of a loop", self.keyword))
+      .with_label(
+        Label::new((filename, self.pos.range.clone()))
+          .with_message(format!("cannot `{}` here", self.keyword))
+          .with_color(Color::Yellow),
+      )
+      .finish()
+  }
+}
@@ -0,0 +1,35 @@
+use ariadne::{Color, Label, Report, ReportKind};
+use sable_parser::{parser::error::ParseErrReport, position::Position};
+
+pub struct UninitializedVariable<'s> {
+  name: &'s str,
+  pos: Position,
+}
+
+impl<'s> UninitializedVariable<'s> {
+  pub fn new(name: &'s str, pos: Position) -> Self {
+    Self { name, pos }
+  }
+
+  pub fn name(&self) -> &'s str {
+    self.name
+  }
+
+  pub fn pos(&self) -> Position {
+    self.pos.clone()
+  }
+
+  pub fn report(&self, filename: &'s str) -> ParseErrReport<'s> {
+    Report::build(ReportKind::Error, (filename, self.pos.range.clone()))
+      .with_message(format!(
+        "variable `{}` may be read before it is assigned",
+        self.name
+      ))
+      .with_label(
+        Label::new((filename, self.pos.range.clone()))
+          .with_message("not assigned on every path to here")
+          .with_color(Color::Yellow),
+      )
+      .finish()
+  }
+}
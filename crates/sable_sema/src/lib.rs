@@ -1,5 +1,8 @@
 pub mod checks;
+pub mod diagnostic;
 pub mod error;
+pub mod lint;
 pub mod resolver;
 pub mod scope;
 pub mod sema;
+pub mod types;
@@ -0,0 +1,193 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::diagnostic::Severity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+  UnusedVariable,
+  UnusedParameter,
+  UnusedFunction,
+  UnreachableCode,
+}
+
+impl Lint {
+  pub const ALL: [Lint; 4] = [
+    Lint::UnusedVariable,
+    Lint::UnusedParameter,
+    Lint::UnusedFunction,
+    Lint::UnreachableCode,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Lint::UnusedVariable => "unused_variable",
+      Lint::UnusedParameter => "unused_parameter",
+      Lint::UnusedFunction => "unused_function",
+      Lint::UnreachableCode => "unreachable_code",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    Lint::ALL.into_iter().find(|lint| lint.name() == name)
+  }
+
+  pub fn default_level(&self) -> Level {
+    match self {
+      Lint::UnusedVariable
+      | Lint::UnusedParameter
+      | Lint::UnusedFunction
+      | Lint::UnreachableCode => Level::Warn,
+    }
+  }
+}
+
+impl Display for Lint {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+  Allow,
+  Warn,
+  Deny,
+}
+
+impl Level {
+  /// The severity a lint at this level is reported with, `None` if it is
+  /// not reported at all.
+  pub fn severity(&self) -> Option<Severity> {
+    match self {
+      Level::Allow => None,
+      Level::Warn => Some(Severity::Warning),
+      Level::Deny => Some(Severity::Error),
+    }
+  }
+}
+
+/// The level of every lint, starting from their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintLevels {
+  overrides: HashMap<Lint, Level>,
+  deny_warnings: bool,
+}
+
+impl LintLevels {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn set(&mut self, lint: Lint, level: Level) {
+    self.overrides.insert(lint, level);
+  }
+
+  /// Turns every lint that would warn into an error.
+  pub fn set_deny_warnings(&mut self, deny: bool) {
+    self.deny_warnings = deny;
+  }
+
+  pub fn level(&self, lint: Lint) -> Level {
+    let level = self
+      .overrides
+      .get(&lint)
+      .copied()
+      .unwrap_or_else(|| lint.default_level());
+    match level {
+      Level::Warn if self.deny_warnings => Level::Deny,
+      level => level,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser};
+
+  use super::*;
+  use crate::{diagnostic::Diagnostic, error::AnalyzerError, sema::Sema};
+
+  fn lints(source: &str, levels: LintLevels) -> (Vec<Diagnostic>, usize) {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let ast = parser.parse().unwrap();
+    let mut sema = Sema::new(ast);
+    sema.lints = levels;
+    let errors = match sema.analyze() {
+      Ok(()) => 0,
+      Err(errs) => {
+        assert!(errs.iter().all(|err| matches!(err, AnalyzerError::Lint(_))));
+        errs.len()
+      }
+    };
+    (sema.warnings().to_vec(), errors)
+  }
+
+  fn lint_names(diagnostics: &[Diagnostic]) -> Vec<&'static str> {
+    diagnostics
+      .iter()
+      .map(|diag| diag.lint().unwrap().name())
+      .collect()
+  }
+
+  #[test]
+  fn test_default_lints() {
+    let source = "
+      func i32 helper(i32 unused, i32 _ignored) { let i32 x = 1; return 2; }
+      func i32 main() { let i32 _y = 0; return 0; return 1; }
+      func i32 rec(i32 n) { return rec(n); }
+    ";
+    let (warnings, errors) = lints(source, LintLevels::new());
+    assert_eq!(errors, 0);
+    assert_eq!(
+      lint_names(&warnings),
+      [
+        "unused_function",
+        "unused_parameter",
+        "unused_variable",
+        "unreachable_code",
+        "unused_function",
+      ]
+    );
+    assert!(
+      warnings
+        .iter()
+        .all(|diag| diag.severity() == Severity::Warning)
+    );
+  }
+
+  #[test]
+  fn test_used_names_do_not_warn() {
+    let source = "
+      func i32 main() { let i32 a = 1; let i32 b = 2; if a < b { b = a; } return add(a, b); }
+      func i32 add(i32 x, i32 y) { return x + y; }
+    ";
+    let (warnings, errors) = lints(source, LintLevels::new());
+    assert!(warnings.is_empty(), "{:?}", lint_names(&warnings));
+    assert_eq!(errors, 0);
+  }
+
+  #[test]
+  fn test_levels() {
+    let source = "func i32 main() { let i32 x = 1; while true { } return 0; }";
+
+    let mut levels = LintLevels::new();
+    levels.set(Lint::UnusedVariable, Level::Allow);
+    let (warnings, errors) = lints(source, levels);
+    assert_eq!(lint_names(&warnings), ["unreachable_code"]);
+    assert_eq!(errors, 0);
+
+    let mut levels = LintLevels::new();
+    levels.set(Lint::UnreachableCode, Level::Deny);
+    let (warnings, errors) = lints(source, levels);
+    assert_eq!(lint_names(&warnings), ["unused_variable"]);
+    assert_eq!(errors, 1);
+
+    let mut levels = LintLevels::new();
+    levels.set_deny_warnings(true);
+    levels.set(Lint::UnusedVariable, Level::Allow);
+    let (warnings, errors) = lints(source, levels);
+    assert!(warnings.is_empty());
+    assert_eq!(errors, 1);
+  }
+}
@@ -26,12 +26,22 @@ impl<'s> Resolver<'s> {
     None
   }
 
+  /// Marks the innermost variable called `name` as read.
+  pub fn mark_used(&mut self, name: &'s str) {
+    for scope in self.scopes.iter_mut().rev() {
+      if let Some(value) = scope.get_variable_mut(name) {
+        value.mark_used();
+        return;
+      }
+    }
+  }
+
   pub fn enter_scope(&mut self) {
     self.scopes.push(Scope::new());
   }
 
-  pub fn exit_scope(&mut self) {
-    self.scopes.pop();
+  pub fn exit_scope(&mut self) -> Option<Scope<'s>> {
+    self.scopes.pop()
   }
 
   pub fn is_declared(&self, name: &'s str) -> bool {
@@ -43,3 +53,9 @@ impl<'s> Resolver<'s> {
     false
   }
 }
+
+impl Default for Resolver<'_> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
@@ -5,11 +5,37 @@ use sable_parser::{info::ValType, position::Position};
 pub struct NamendValue {
   val_type: ValType,
   position: Position,
+  is_param: bool,
+  used: bool,
 }
 
 impl NamendValue {
   pub fn new(val_type: ValType, position: Position) -> Self {
-    Self { val_type, position }
+    Self {
+      val_type,
+      position,
+      is_param: false,
+      used: false,
+    }
+  }
+
+  pub fn param(val_type: ValType, position: Position) -> Self {
+    Self {
+      is_param: true,
+      ..Self::new(val_type, position)
+    }
+  }
+
+  pub fn is_param(&self) -> bool {
+    self.is_param
+  }
+
+  pub fn is_used(&self) -> bool {
+    self.used
+  }
+
+  pub fn mark_used(&mut self) {
+    self.used = true;
   }
 
   pub fn get_pos(&self) -> &Position {
@@ -39,7 +65,21 @@ impl<'s> Scope<'s> {
     self.variables.get(name)
   }
 
+  pub fn get_variable_mut(&mut self, name: &'s str) -> Option<&mut NamendValue> {
+    self.variables.get_mut(name)
+  }
+
   pub fn is_declared(&self, name: &'s str) -> bool {
     self.variables.contains_key(name)
   }
+
+  pub fn variables(&self) -> impl Iterator<Item = (&'s str, &NamendValue)> {
+    self.variables.iter().map(|(name, value)| (*name, value))
+  }
+}
+
+impl Default for Scope<'_> {
+  fn default() -> Self {
+    Self::new()
+  }
 }
@@ -1,17 +1,39 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+  cell::RefCell,
+  collections::{HashMap, HashSet},
+  rc::Rc,
+};
 
 use crate::{
-  checks::stmt_check::check_stmt,
-  error::{AnalyzerError, func_already_defined::FunctionAlreadyDefined},
+  checks::{
+    flow::block_falls_through,
+    init::check_definite_assignment,
+    lints::{lint_unreachable, lint_unused_functions, lint_unused_variables},
+    stmt_check::check_stmt,
+  },
+  diagnostic::{Diagnostic, Severity},
+  error::{AnalyzerError, MissingReturn, func_already_defined::FunctionAlreadyDefined},
+  lint::{Lint, LintLevels},
   resolver::Resolver,
   scope::NamendValue,
+  types::TypeTable,
+};
+use sable_parser::{
+  ast::{ast::AST, expression::BlockExpression, function::Function},
+  info::ValType,
 };
-use sable_parser::ast::{ast::AST, expression::BlockExpression, function::Function};
 
 pub struct Sema<'s> {
   errors: Vec<AnalyzerError<'s>>,
   pub resolver: Resolver<'s>,
   pub funcs: HashMap<&'s str, usize>,
+  /// Number of loops enclosing the statement being checked.
+  pub loop_depth: usize,
+  pub types: TypeTable,
+  pub lints: LintLevels,
+  /// Functions called from a function other than themselves.
+  pub called: HashSet<&'s str>,
+  warnings: Vec<Diagnostic>,
   ast: Rc<RefCell<AST<'s>>>,
 }
 
@@ -21,35 +43,79 @@ impl<'s> Sema<'s> {
       errors: Vec::new(),
       resolver: Resolver::new(),
       funcs: HashMap::new(),
+      loop_depth: 0,
+      types: TypeTable::new(),
+      lints: LintLevels::new(),
+      called: HashSet::new(),
+      warnings: Vec::new(),
       ast,
     }
   }
 
+  /// Diagnostics that do not fail the analysis.
+  pub fn warnings(&self) -> &[Diagnostic] {
+    &self.warnings
+  }
+
+  /// Reports `diagnostic` for `lint` at the level configured for it.
+  pub fn lint(&mut self, lint: Lint, mut diagnostic: Diagnostic) {
+    let Some(severity) = self.lints.level(lint).severity() else {
+      return;
+    };
+    diagnostic.set_severity(severity);
+    diagnostic.set_lint(lint);
+    if severity == Severity::Error {
+      self.errors.push(AnalyzerError::Lint(diagnostic));
+    } else {
+      self.warnings.push(diagnostic);
+    }
+  }
+
+  /// Leaves the innermost scope, reporting the variables never read in it.
+  pub fn exit_scope(&mut self) {
+    if let Some(scope) = self.resolver.exit_scope() {
+      lint_unused_variables(self, &scope);
+    }
+  }
+
+  /// Consumes the analyzer, keeping the types it proved for lowering.
+  pub fn into_types(self) -> TypeTable {
+    self.types
+  }
+
   pub fn get_func(&self, idx: usize) -> Rc<RefCell<Function<'s>>> {
     let ast = self.ast.borrow();
     ast.get_funcs()[idx].clone()
   }
 
-  pub fn check_function(
+  /// Registers the signature of `f` so that calls to it can be checked
+  /// regardless of where it is defined.
+  pub fn declare_function(
     &mut self,
     f: Rc<RefCell<Function<'s>>>,
     func_idx: usize,
-  ) -> Result<(), Vec<AnalyzerError<'s>>> {
-    if self.funcs.contains_key(f.borrow().get_name()) {
-      let earlier = self.funcs[f.borrow().get_name()];
+  ) -> Result<(), AnalyzerError<'s>> {
+    if let Some(&earlier) = self.funcs.get(f.borrow().get_name()) {
       let earlier_func = self.get_func(earlier);
-      return Err(vec![AnalyzerError::FunctionAlreadyDefined(
+      return Err(AnalyzerError::FunctionAlreadyDefined(
         FunctionAlreadyDefined::new(
           f.borrow().get_name(),
           earlier_func.borrow().get_pos().clone(),
           f.borrow().get_pos().clone(),
         ),
-      )]);
+      ));
     }
     self.funcs.insert(f.borrow().get_name(), func_idx);
+    Ok(())
+  }
+
+  pub fn check_function(
+    &mut self,
+    f: Rc<RefCell<Function<'s>>>,
+  ) -> Result<(), Vec<AnalyzerError<'s>>> {
     self.resolver.enter_scope();
     for param in f.borrow().get_params() {
-      let nv = NamendValue::new(param.get_val_type().clone(), param.get_pos().clone());
+      let nv = NamendValue::param(param.get_val_type().clone(), param.get_pos().clone());
       self.resolver.define_var(param.get_name(), nv);
     }
 
@@ -62,9 +128,31 @@ impl<'s> Sema<'s> {
       self.check_block(&mut *body_ptr, f.clone())
     };
 
-    self.resolver.exit_scope();
+    self.exit_scope();
 
-    result
+    let func = f.borrow();
+    let mut errors = result.err().unwrap_or_default();
+    // Following reads needs every variable to resolve.
+    if errors.is_empty() {
+      errors.extend(
+        check_definite_assignment(func.get_body())
+          .into_iter()
+          .map(AnalyzerError::UninitializedVariable),
+      );
+    }
+    if func.get_ret_type() != ValType::Void && block_falls_through(func.get_body()) {
+      errors.push(AnalyzerError::MissingReturn(MissingReturn::new(
+        func.get_name(),
+        func.get_ret_type(),
+        func.get_pos(),
+      )));
+    }
+
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors)
+    }
   }
 
   pub fn check_block(
@@ -72,6 +160,7 @@ impl<'s> Sema<'s> {
     block: &mut BlockExpression<'s>,
     f: Rc<RefCell<Function<'s>>>,
   ) -> Result<(), Vec<AnalyzerError<'s>>> {
+    lint_unreachable(self, block);
     let mut errors = Vec::new();
     for stmt in block.get_stmts_mut().iter_mut() {
       if let Err(e) = check_stmt(self, stmt, f.clone()) {
@@ -93,16 +182,31 @@ impl<'s> Sema<'s> {
         .iter()
         .cloned()
         .enumerate()
-        .map(|(i, f_rc)| (i, f_rc))
         .collect()
     };
 
+    for (i, f_rc) in &func_entries {
+      if let Err(err) = self.declare_function(f_rc.clone(), *i) {
+        self.errors.push(err);
+      }
+    }
+
     for (i, f_rc) in func_entries {
-      if let Err(errs) = self.check_function(f_rc, i) {
+      // Redefinitions were reported above, only the first body is checked.
+      if self.funcs[f_rc.borrow().get_name()] != i {
+        continue;
+      }
+      if let Err(errs) = self.check_function(f_rc) {
         self.errors.extend(errs);
       }
     }
 
+    let ast = self.ast.clone();
+    lint_unused_functions(self, &ast.borrow());
+    self
+      .warnings
+      .sort_by_key(|diagnostic| diagnostic.pos().range.start);
+
     if self.errors.is_empty() {
       Ok(())
     } else {
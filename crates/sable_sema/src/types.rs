@@ -0,0 +1,36 @@
+use std::{collections::HashMap, ops::Range};
+
+use sable_parser::{ast::expression::Expression, info::ValType, position::Position};
+
+/// The types sema proved for the expressions of a module, keyed by the
+/// source span of each expression.
+#[derive(Debug, Default, Clone)]
+pub struct TypeTable {
+  types: HashMap<Range<usize>, ValType>,
+}
+
+impl TypeTable {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, pos: &Position, type_: ValType) {
+    self.types.insert(pos.range.clone(), type_);
+  }
+
+  pub fn get(&self, pos: &Position) -> Option<&ValType> {
+    self.types.get(&pos.range)
+  }
+
+  pub fn get_expr(&self, expr: &Expression) -> Option<&ValType> {
+    self.get(&expr.get_pos())
+  }
+
+  pub fn len(&self) -> usize {
+    self.types.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.types.is_empty()
+  }
+}
@@ -0,0 +1,444 @@
+use std::{fmt::Display, path::PathBuf};
+
+use sable_mir::transform::pass::{OptLevel, create_pass};
+use sable_sema::lint::{Level, Lint, LintLevels};
+
+pub const USAGE: &str = "\
+usage: sable <command> [options] <file.sbl>...
+
+commands:
+  check    parse and analyze the input files
+  ast      print the AST of each input file as JSON
+  mir      print the MIR of each input file, or write its graphs with
+           `--emit dot`
+  build    compile each input file and write the output next to it
+  run      interpret a single input file, exiting with the status `main` returns
+
+options:
+  -o <path>    output path for `build` (only with a single input file)
+  --emit <kind>
+               what `build` writes: `mir` (default), `c` source, a `wat`
+               WebAssembly module, x86-64 `asm`, an `exe` built with the
+               system C compiler (`$CC` or `cc`), or `dot` graphs: the call
+               graph at the output path and the control flow of each
+               function `f` next to it, in `<output>.f.dot`
+  --backend <name>
+               how `--emit exe` generates code: through `c` (default) or
+               natively for `x86_64`
+  -g           map the C and assembly output back to the source lines, with
+               `#line` directives and `.loc` line tables
+  -O0, -O1, -O2
+               how much to optimize the MIR before printing, building or
+               running it, `-O0` (default) keeps it as lowered
+  --passes <list>
+               run the comma-separated passes instead of the `-O` pipeline
+  --print-before <pass>, --print-after <pass>
+               print the MIR to stderr around each run of a pass, or of
+               every pass for `all`
+  --pass-stats print what each pass changed to stderr
+  -A <lint>    allow a lint
+  -W <lint>    warn on a lint
+  -D <lint>    deny a lint, `-D warnings` denies every lint that warns
+  -h, --help   print this message
+
+Options taking a value also accept it as `--option=value`.
+
+passes: mem2reg, inline, const-fold, dce
+
+lints: unused_variable, unused_parameter, unused_function, unreachable_code";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+  Check,
+  Ast,
+  Mir,
+  Build,
+  Run,
+}
+
+impl Command {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "check" => Some(Command::Check),
+      "ast" => Some(Command::Ast),
+      "mir" => Some(Command::Mir),
+      "build" => Some(Command::Build),
+      "run" => Some(Command::Run),
+      _ => None,
+    }
+  }
+}
+
+/// The output of `build`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+  Mir,
+  C,
+  Wat,
+  Asm,
+  Exe,
+  Dot,
+}
+
+impl Emit {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "mir" => Some(Emit::Mir),
+      "c" => Some(Emit::C),
+      "wat" => Some(Emit::Wat),
+      "asm" => Some(Emit::Asm),
+      "exe" => Some(Emit::Exe),
+      "dot" => Some(Emit::Dot),
+      _ => None,
+    }
+  }
+
+  /// The extension of the output written next to the input file.
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Emit::Mir => "mir",
+      Emit::C => "c",
+      Emit::Wat => "wat",
+      Emit::Asm => "s",
+      Emit::Exe => "",
+      Emit::Dot => "dot",
+    }
+  }
+}
+
+/// The code generator behind `--emit exe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+  C,
+  X86_64,
+}
+
+impl Backend {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "c" => Some(Backend::C),
+      "x86_64" => Some(Backend::X86_64),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+  MissingCommand,
+  UnknownCommand(String),
+  UnknownOption(String),
+  MissingValue(&'static str),
+  UnknownLint(String),
+  UnknownEmit(String),
+  UnknownBackend(String),
+  UnknownOptLevel(String),
+  UnknownPass(String),
+  NoInputFiles,
+  OutputWithMultipleFiles,
+  RunWithMultipleFiles,
+}
+
+impl Display for CliError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CliError::MissingCommand => write!(f, "no command given"),
+      CliError::UnknownCommand(name) => write!(f, "unknown command `{}`", name),
+      CliError::UnknownOption(name) => write!(f, "unknown option `{}`", name),
+      CliError::MissingValue(option) => write!(f, "option `{}` expects a value", option),
+      CliError::UnknownLint(name) => write!(f, "unknown lint `{}`", name),
+      CliError::UnknownEmit(name) => write!(f, "unknown output kind `{}`", name),
+      CliError::UnknownBackend(name) => write!(f, "unknown backend `{}`", name),
+      CliError::UnknownOptLevel(level) => write!(f, "unknown optimization level `{}`", level),
+      CliError::UnknownPass(name) => write!(f, "unknown pass `{}`", name),
+      CliError::NoInputFiles => write!(f, "no input files"),
+      CliError::OutputWithMultipleFiles => {
+        write!(f, "`-o` cannot be used with more than one input file")
+      }
+      CliError::RunWithMultipleFiles => write!(f, "`run` expects a single input file"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invocation {
+  Help,
+  Run(Options),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+  pub command: Command,
+  pub files: Vec<PathBuf>,
+  pub output: Option<PathBuf>,
+  pub emit: Emit,
+  pub backend: Backend,
+  /// Whether `-g` asks for line tables.
+  pub debug_info: bool,
+  pub opt_level: OptLevel,
+  /// The passes given with `--passes`, which replace the `-O` pipeline.
+  pub passes: Option<Vec<String>>,
+  pub print_before: Vec<String>,
+  pub print_after: Vec<String>,
+  pub pass_stats: bool,
+  pub lints: LintLevels,
+}
+
+/// Checks that `name` is a pass, or `all` where that is allowed.
+fn pass_name(name: String, allow_all: bool) -> Result<String, CliError> {
+  if (allow_all && name == "all") || create_pass(&name).is_some() {
+    Ok(name)
+  } else {
+    Err(CliError::UnknownPass(name))
+  }
+}
+
+pub fn parse_args<I>(args: I) -> Result<Invocation, CliError>
+where
+  I: IntoIterator<Item = String>,
+{
+  let mut args = args.into_iter().flat_map(|arg| match arg.split_once('=') {
+    Some((option, value)) if option.starts_with("--") => {
+      vec![option.to_string(), value.to_string()]
+    }
+    _ => vec![arg],
+  });
+  let command = match args.next() {
+    Some(arg) if arg == "-h" || arg == "--help" => return Ok(Invocation::Help),
+    Some(arg) => Command::from_name(&arg).ok_or(CliError::UnknownCommand(arg))?,
+    None => return Err(CliError::MissingCommand),
+  };
+
+  let mut files = Vec::new();
+  let mut output = None;
+  let mut emit = Emit::Mir;
+  let mut backend = Backend::C;
+  let mut debug_info = false;
+  let mut opt_level = OptLevel::O0;
+  let mut passes = None;
+  let mut print_before = Vec::new();
+  let mut print_after = Vec::new();
+  let mut pass_stats = false;
+  let mut lints = LintLevels::new();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-h" | "--help" => return Ok(Invocation::Help),
+      "-o" => {
+        output = Some(PathBuf::from(
+          args.next().ok_or(CliError::MissingValue("-o"))?,
+        ))
+      }
+      "--emit" => {
+        let name = args.next().ok_or(CliError::MissingValue("--emit"))?;
+        emit = Emit::from_name(&name).ok_or(CliError::UnknownEmit(name))?;
+      }
+      "--passes" => {
+        let list = args.next().ok_or(CliError::MissingValue("--passes"))?;
+        passes = Some(
+          list
+            .split(',')
+            .map(|name| pass_name(name.to_string(), false))
+            .collect::<Result<Vec<_>, _>>()?,
+        );
+      }
+      "--print-before" => {
+        let name = args
+          .next()
+          .ok_or(CliError::MissingValue("--print-before"))?;
+        print_before.push(pass_name(name, true)?);
+      }
+      "--print-after" => {
+        let name = args.next().ok_or(CliError::MissingValue("--print-after"))?;
+        print_after.push(pass_name(name, true)?);
+      }
+      "--pass-stats" => pass_stats = true,
+      "-g" => debug_info = true,
+      _ if arg.starts_with("-O") => {
+        opt_level = OptLevel::from_name(&arg[2..]).ok_or(CliError::UnknownOptLevel(arg))?;
+      }
+      "--backend" => {
+        let name = args.next().ok_or(CliError::MissingValue("--backend"))?;
+        backend = Backend::from_name(&name).ok_or(CliError::UnknownBackend(name))?;
+      }
+      "-A" | "-W" | "-D" => {
+        let (option, level) = match arg.as_str() {
+          "-A" => ("-A", Level::Allow),
+          "-W" => ("-W", Level::Warn),
+          _ => ("-D", Level::Deny),
+        };
+        let name = args.next().ok_or(CliError::MissingValue(option))?;
+        if level == Level::Deny && name == "warnings" {
+          lints.set_deny_warnings(true);
+          continue;
+        }
+        let lint = Lint::from_name(&name).ok_or(CliError::UnknownLint(name))?;
+        lints.set(lint, level);
+      }
+      _ if arg.starts_with('-') => return Err(CliError::UnknownOption(arg)),
+      _ => files.push(PathBuf::from(arg)),
+    }
+  }
+
+  if files.is_empty() {
+    return Err(CliError::NoInputFiles);
+  }
+  if output.is_some() && files.len() > 1 {
+    return Err(CliError::OutputWithMultipleFiles);
+  }
+  if command == Command::Run && files.len() > 1 {
+    return Err(CliError::RunWithMultipleFiles);
+  }
+
+  Ok(Invocation::Run(Options {
+    command,
+    files,
+    output,
+    emit,
+    backend,
+    debug_info,
+    opt_level,
+    passes,
+    print_before,
+    print_after,
+    pass_stats,
+    lints,
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+  }
+
+  #[test]
+  fn test_parse_command_and_files() {
+    let inv = parse_args(args(&["check", "a.sbl", "b.sbl"])).unwrap();
+    assert_eq!(
+      inv,
+      Invocation::Run(Options {
+        command: Command::Check,
+        files: vec![PathBuf::from("a.sbl"), PathBuf::from("b.sbl")],
+        output: None,
+        emit: Emit::Mir,
+        backend: Backend::C,
+        debug_info: false,
+        opt_level: OptLevel::O0,
+        passes: None,
+        print_before: Vec::new(),
+        print_after: Vec::new(),
+        pass_stats: false,
+        lints: LintLevels::new(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_lint_levels() {
+    let Ok(Invocation::Run(options)) = parse_args(args(&[
+      "check",
+      "-A",
+      "unused_function",
+      "-D",
+      "warnings",
+      "a.sbl",
+    ])) else {
+      panic!("expected options");
+    };
+    assert_eq!(options.lints.level(Lint::UnusedFunction), Level::Allow);
+    assert_eq!(options.lints.level(Lint::UnusedVariable), Level::Deny);
+    assert_eq!(
+      parse_args(args(&["check", "-W", "unused", "a.sbl"])),
+      Err(CliError::UnknownLint("unused".into()))
+    );
+  }
+
+  #[test]
+  fn test_parse_optimization_options() {
+    let Ok(Invocation::Run(options)) = parse_args(args(&[
+      "mir",
+      "-O2",
+      "--print-after",
+      "all",
+      "--pass-stats",
+      "a.sbl",
+    ])) else {
+      panic!("expected options");
+    };
+    assert_eq!(options.opt_level, OptLevel::O2);
+    assert_eq!(options.passes, None);
+    assert_eq!(options.print_after, ["all"]);
+    assert!(options.pass_stats);
+
+    let Ok(Invocation::Run(options)) =
+      parse_args(args(&["run", "--passes", "mem2reg,dce", "a.sbl"]))
+    else {
+      panic!("expected options");
+    };
+    assert_eq!(options.passes, Some(vec!["mem2reg".into(), "dce".into()]));
+    assert_eq!(
+      parse_args(args(&["mir", "-O3", "a.sbl"])),
+      Err(CliError::UnknownOptLevel("-O3".into()))
+    );
+    assert_eq!(
+      parse_args(args(&["mir", "--passes", "mem2reg,gvn", "a.sbl"])),
+      Err(CliError::UnknownPass("gvn".into()))
+    );
+    assert_eq!(
+      parse_args(args(&["mir", "--print-before", "all,dce", "a.sbl"])),
+      Err(CliError::UnknownPass("all,dce".into()))
+    );
+  }
+
+  #[test]
+  fn test_parse_inline_values() {
+    let Ok(Invocation::Run(options)) = parse_args(args(&[
+      "mir",
+      "--emit=dot",
+      "--passes=mem2reg",
+      "-g",
+      "a.sbl",
+    ])) else {
+      panic!("expected options");
+    };
+    assert_eq!(options.emit, Emit::Dot);
+    assert!(options.debug_info);
+    assert_eq!(options.passes, Some(vec!["mem2reg".into()]));
+    assert_eq!(
+      parse_args(args(&["build", "--backend=", "a.sbl"])),
+      Err(CliError::UnknownBackend("".into()))
+    );
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert_eq!(parse_args(args(&[])), Err(CliError::MissingCommand));
+    assert_eq!(
+      parse_args(args(&["exec", "a.sbl"])),
+      Err(CliError::UnknownCommand("exec".into()))
+    );
+    assert_eq!(parse_args(args(&["mir"])), Err(CliError::NoInputFiles));
+    assert_eq!(
+      parse_args(args(&["build", "-o"])),
+      Err(CliError::MissingValue("-o"))
+    );
+    assert_eq!(
+      parse_args(args(&["build", "-o", "out", "a.sbl", "b.sbl"])),
+      Err(CliError::OutputWithMultipleFiles)
+    );
+    assert_eq!(
+      parse_args(args(&["run", "a.sbl", "b.sbl"])),
+      Err(CliError::RunWithMultipleFiles)
+    );
+    assert_eq!(
+      parse_args(args(&["build", "--emit", "obj", "a.sbl"])),
+      Err(CliError::UnknownEmit("obj".into()))
+    );
+    assert_eq!(
+      parse_args(args(&["build", "--backend", "arm", "a.sbl"])),
+      Err(CliError::UnknownBackend("arm".into()))
+    );
+  }
+}
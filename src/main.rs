@@ -1,59 +1,186 @@
-use ariadne::Source;
-use sable_mir::{lowering::Lowerer, mir::module::MirModule};
-use sable_parser::{lexer::lexer::Lexer, parser::parser::Parser};
-use sable_sema::sema::Sema;
-
-const SOURCE: &str = r#"
-func i32 add(i32 x, i32 y) {
-  let i32 z = x + y;
-  let i32 xy = 69 + z;
-  return add(1, 2);
-}
-"#;
-const FILENAME: &str = "test.sbl";
-
-fn main() {
-  let mut lexer = Lexer::new(SOURCE);
-  let mut parser = Parser::new(&mut lexer);
-  let parse_res = parser.parse();
-  let ast = match parse_res {
-    Ok(ast) => {
-      let serialized = serde_json::to_string_pretty(&*ast).unwrap();
-      println!("AST: {serialized}");
-      ast
-    }
-    Err(errs) => {
-      for err in errs {
-        err
-          .report(FILENAME)
-          .print((FILENAME, Source::from(SOURCE)))
-          .unwrap();
-      }
-      return;
+use std::{fs, io, path::Path, process::ExitCode};
+
+use cli::{Backend, Command, Emit, Invocation, Options};
+use sable_codegen::{c, wasm, x86_64};
+use sable_driver::{
+  cc::{assemble, compile_c},
+  session::Session,
+};
+use sable_interp::{interpreter::Interpreter, value::Value};
+use sable_mir::{
+  mir::{
+    dot::{call_graph_dot, function_dot},
+    module::MirModule,
+  },
+  transform::pass::{DumpPoint, PassManager, create_pass},
+};
+
+mod cli;
+
+/// Runs the command on `path`, returning the exit status, or `None` if it
+/// failed.
+fn compile_file(path: &Path, options: &Options) -> Option<u8> {
+  let mut session = match Session::from_path(path) {
+    Ok(session) => session,
+    Err(err) => {
+      eprintln!("error: cannot read `{}`: {}", path.display(), err);
+      return None;
     }
   };
+  session.set_lints(options.lints.clone());
 
-  let mut sema = Sema::new(ast.clone());
-  match sema.analyze() {
-    Ok(_) => println!("No errors found."),
-    Err(errors) => {
-      for error in errors {
-        error
-          .report(FILENAME)
-          .print((FILENAME, Source::from(SOURCE)))
-          .unwrap();
+  let status = run_command(&session, path, options);
+  session.emit_warnings();
+  status
+}
+
+/// Runs the passes `options` select over `module`.
+fn optimize(module: &mut MirModule, options: &Options) {
+  let mut manager = match &options.passes {
+    Some(passes) => {
+      let mut manager = PassManager::new();
+      for name in passes {
+        manager.add_pass(create_pass(name).unwrap());
       }
+      manager
     }
+    None => PassManager::with_level(options.opt_level),
+  };
+  for pass in &options.print_before {
+    manager.add_dump(DumpPoint::Before, pass.clone());
+  }
+  for pass in &options.print_after {
+    manager.add_dump(DumpPoint::After, pass.clone());
   }
+  manager.run(module);
+  if options.pass_stats {
+    eprint!("{}", manager.report());
+  }
+}
 
-  let mir_mod = MirModule::new("test");
-  let mut lowerer = Lowerer::new(mir_mod, ast);
-  let res = lowerer.lower();
+/// Writes the call graph of `module` to `output` and the control-flow
+/// graph of each function `f` to `output` with the extension `f.dot`.
+fn write_dot(module: &MirModule, output: &Path) -> io::Result<()> {
+  fs::write(output, call_graph_dot(module))?;
+  for func in module.get_funcs() {
+    let path = output.with_extension(format!("{}.dot", func.name()));
+    fs::write(path, function_dot(module, func))?;
+  }
+  Ok(())
+}
 
-  match res {
-    Ok(mir_mod) => println!("{:#?}", mir_mod),
-    Err(errors) => for error in errors {
-      println!("{:#?}", error);
+fn run_command(session: &Session, path: &Path, options: &Options) -> Option<u8> {
+  match options.command {
+    Command::Check => match session.analyze() {
+      Ok(_) => Some(0),
+      Err(failure) => {
+        session.emit(&failure);
+        None
+      }
+    },
+    Command::Ast => match session.parse() {
+      Ok(ast) => {
+        let serialized = serde_json::to_string_pretty(&*ast).unwrap();
+        println!("{serialized}");
+        Some(0)
+      }
+      Err(failure) => {
+        session.emit(&failure);
+        None
+      }
     },
+    Command::Mir | Command::Build => {
+      let mir_mod = match session.lower() {
+        Ok(mir_mod) => mir_mod,
+        Err(failure) => {
+          session.emit(&failure);
+          return None;
+        }
+      };
+      if options.debug_info {
+        mir_mod
+          .borrow_mut()
+          .set_source_file(Some(session.filename()));
+      }
+      optimize(&mut mir_mod.borrow_mut(), options);
+      let mir_mod = mir_mod.borrow();
+      if options.command == Command::Mir && options.emit != Emit::Dot {
+        print!("{mir_mod}");
+        return Some(0);
+      }
+
+      let output = match &options.output {
+        Some(output) => output.clone(),
+        None => path.with_extension(options.emit.extension()),
+      };
+      let written = match options.emit {
+        Emit::Mir => fs::write(&output, mir_mod.to_string()),
+        Emit::C => fs::write(&output, c::emit_module(&mir_mod)),
+        Emit::Wat => fs::write(&output, wasm::emit_module(&mir_mod)),
+        Emit::Asm => fs::write(&output, x86_64::emit_module(&mir_mod)),
+        Emit::Dot => write_dot(&mir_mod, &output),
+        Emit::Exe => {
+          let built = match options.backend {
+            Backend::C => compile_c(&c::emit_module(&mir_mod), &output),
+            Backend::X86_64 => assemble(&x86_64::emit_module(&mir_mod), &output),
+          };
+          if let Err(err) = built {
+            eprintln!("error: {}", err);
+            return None;
+          }
+          Ok(())
+        }
+      };
+      if let Err(err) = written {
+        eprintln!("error: cannot write `{}`: {}", output.display(), err);
+        return None;
+      }
+      Some(0)
+    }
+    Command::Run => {
+      let mir_mod = match session.lower() {
+        Ok(mir_mod) => mir_mod,
+        Err(failure) => {
+          session.emit(&failure);
+          return None;
+        }
+      };
+      optimize(&mut mir_mod.borrow_mut(), options);
+
+      match Interpreter::new(&mir_mod.borrow()).run_main() {
+        // Exit statuses are truncated to their low byte like on Unix.
+        Ok(Value::I32(status)) => Some(status as u8),
+        Ok(_) => Some(0),
+        Err(err) => {
+          eprintln!("error: {}", err);
+          None
+        }
+      }
+    }
+  }
+}
+
+fn main() -> ExitCode {
+  let options = match cli::parse_args(std::env::args().skip(1)) {
+    Ok(Invocation::Run(options)) => options,
+    Ok(Invocation::Help) => {
+      println!("{}", cli::USAGE);
+      return ExitCode::SUCCESS;
+    }
+    Err(err) => {
+      eprintln!("error: {err}\n\n{}", cli::USAGE);
+      return ExitCode::from(2);
+    }
+  };
+
+  let mut status = Some(0);
+  for path in &options.files {
+    let file_status = compile_file(path, &options);
+    status = status.and(file_status);
+  }
+
+  match status {
+    Some(status) => ExitCode::from(status),
+    None => ExitCode::FAILURE,
   }
 }